@@ -0,0 +1,245 @@
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
+use hyper::body::Body;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{header, Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+use crate::notifier::incidents::{self, IncidentTracker};
+
+type ApiError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Largest request body accepted; an acknowledgement is a few bytes of JSON.
+const MAX_BODY_BYTES: usize = 16 * 1024;
+
+/// Local HTTP API used by `logsync ack` and external tools.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiConfig {
+    #[serde(default = "default_listen")]
+    pub listen: String,
+    /// When set, requests must send `Authorization: Bearer <token>`.
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+fn default_listen() -> String {
+    "127.0.0.1:8788".to_string()
+}
+
+/// State shared by every request handler.
+#[derive(Clone)]
+pub struct ApiState {
+    pub log_file_path: String,
+    pub incidents: Option<IncidentTracker>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AckRequest {
+    by: Option<String>,
+}
+
+/// Serves the API until the listener fails.
+pub async fn serve(config: ApiConfig, state: ApiState) -> Result<(), ApiError> {
+    let listener = TcpListener::bind(&config.listen).await?;
+    println!("HTTP API listening on http://{}", config.listen);
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let state = state.clone();
+        let token = config.token.clone();
+
+        tokio::spawn(async move {
+            let service = service_fn(move |request| {
+                let state = state.clone();
+                let token = token.clone();
+                async move { Ok::<_, Infallible>(handle(request, &state, token.as_deref()).await) }
+            });
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                eprintln!("Error serving API connection: {}", e);
+            }
+        });
+    }
+}
+
+async fn handle<B>(request: Request<B>, state: &ApiState, token: Option<&str>) -> Response<Full<Bytes>>
+where
+    B: Body<Data = Bytes>,
+    B::Error: Into<ApiError>,
+{
+    if let Some(token) = token {
+        let expected = format!("Bearer {}", token);
+        let authorized = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .is_some_and(|value| value.as_bytes() == expected.as_bytes());
+        if !authorized {
+            return error(StatusCode::UNAUTHORIZED, "Missing or invalid token");
+        }
+    }
+
+    let method = request.method().clone();
+    let path = request.uri().path().trim_matches('/').to_string();
+    let segments: Vec<&str> = path.split('/').collect();
+
+    match (method, segments.as_slice()) {
+        (Method::GET, ["incidents"]) => match &state.incidents {
+            Some(tracker) => json_response(StatusCode::OK, json!(tracker.list())),
+            None => error(StatusCode::NOT_FOUND, "Incident tracking is not enabled"),
+        },
+        (Method::POST, ["ack", key]) => {
+            let key = key.to_string();
+            let body = match Limited::new(request.into_body(), MAX_BODY_BYTES).collect().await {
+                Ok(body) => body.to_bytes(),
+                Err(e) if e.is::<LengthLimitError>() => {
+                    return error(StatusCode::PAYLOAD_TOO_LARGE, "Request body too large")
+                }
+                Err(e) => return error(StatusCode::BAD_REQUEST, &e.to_string()),
+            };
+            let ack: AckRequest = if body.is_empty() {
+                AckRequest::default()
+            } else {
+                match serde_json::from_slice(&body) {
+                    Ok(ack) => ack,
+                    Err(e) => return error(StatusCode::BAD_REQUEST, &format!("Invalid JSON: {}", e)),
+                }
+            };
+            let by = ack.by.unwrap_or_else(|| "api".to_string());
+
+            // Rewrites the log file under a lock, so it stays off the async workers
+            let acknowledged = {
+                let (key, by, state) = (key.clone(), by.clone(), state.clone());
+                tokio::task::spawn_blocking(move || {
+                    incidents::acknowledge(&key, &by, &state.log_file_path, state.incidents.as_ref())
+                })
+                .await
+            };
+            match acknowledged {
+                Ok(Ok(true)) => json_response(StatusCode::OK, json!({ "acknowledged": key, "by": by })),
+                Ok(Ok(false)) => error(StatusCode::NOT_FOUND, &format!("No entry or incident {}", key)),
+                Ok(Err(e)) => error(StatusCode::INTERNAL_SERVER_ERROR, &e),
+                Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+            }
+        }
+        _ => error(StatusCode::NOT_FOUND, "Not found"),
+    }
+}
+
+fn json_response(status: StatusCode, body: Value) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::from(body.to_string())));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(header::CONTENT_TYPE, header::HeaderValue::from_static("application/json"));
+    response
+}
+
+fn error(status: StatusCode, message: &str) -> Response<Full<Bytes>> {
+    json_response(status, json!({ "error": message }))
+}
+
+/// Calls the API of a running logsync instance.
+pub async fn request(
+    config: &ApiConfig,
+    method: reqwest::Method,
+    path: &str,
+    body: Option<Value>,
+) -> Result<Value, ApiError> {
+    let url = format!("http://{}/{}", config.listen, path.trim_start_matches('/'));
+    let mut builder = reqwest::Client::new()
+        .request(method, &url)
+        .timeout(Duration::from_secs(10));
+    if let Some(token) = &config.token {
+        builder = builder.bearer_auth(token);
+    }
+    if let Some(body) = body {
+        builder = builder.json(&body);
+    }
+
+    let response = builder
+        .send()
+        .await
+        .map_err(|e| format!("Could not reach logsync at {}: {}", config.listen, e))?;
+    let status = response.status();
+    let body: Value = response.json().await.unwrap_or(Value::Null);
+    if status.is_success() {
+        Ok(body)
+    } else {
+        let message = body["error"].as_str().unwrap_or("request failed");
+        Err(format!("HTTP {}: {}", status, message).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(name: &str) -> ApiState {
+        let path = std::env::temp_dir().join(format!("logsync-api-{}-{}.json", name, std::process::id()));
+        std::fs::write(&path, "[]").unwrap();
+        ApiState {
+            log_file_path: path.to_string_lossy().into_owned(),
+            incidents: None,
+        }
+    }
+
+    fn ack(key: &str, body: impl Into<Bytes>) -> Request<Full<Bytes>> {
+        Request::post(format!("/ack/{}", key)).body(Full::new(body.into())).unwrap()
+    }
+
+    async fn body_json(response: Response<Full<Bytes>>) -> Value {
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn rejects_oversized_bodies() {
+        let state = state("oversized");
+        let body = format!("{{\"by\": \"{}\"}}", "x".repeat(MAX_BODY_BYTES));
+        let response = handle(ack("abc", body), &state, None).await;
+
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let _ = std::fs::remove_file(&state.log_file_path);
+    }
+
+    #[tokio::test]
+    async fn acknowledges_stored_entries() {
+        let state = state("ack");
+        std::fs::write(
+            &state.log_file_path,
+            r#"[{"id": "abc", "timestamp": 1, "log_type": "ERROR", "priority": "high", "message": "m", "telegram_notification": null}]"#,
+        )
+        .unwrap();
+
+        let response = handle(ack("abc", r#"{"by": "alice"}"#), &state, None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_json(response).await["by"], "alice");
+        assert!(std::fs::read_to_string(&state.log_file_path).unwrap().contains("alice"));
+
+        let response = handle(ack("missing", ""), &state, None).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let _ = std::fs::remove_file(&state.log_file_path);
+    }
+
+    #[tokio::test]
+    async fn requires_the_token_when_configured() {
+        let state = state("token");
+        let response = handle(ack("abc", ""), &state, Some("secret")).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let mut request = ack("abc", "");
+        request
+            .headers_mut()
+            .insert(header::AUTHORIZATION, header::HeaderValue::from_static("Bearer secret"));
+        assert_eq!(handle(request, &state, Some("secret")).await.status(), StatusCode::NOT_FOUND);
+        let _ = std::fs::remove_file(&state.log_file_path);
+    }
+}
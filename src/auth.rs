@@ -1,12 +1,84 @@
+use async_trait::async_trait;
 use google_drive3::DriveHub;
 use hyper_tls::HttpsConnector;
 use hyper_util::client::legacy::connect::HttpConnector;
 use hyper_util::client::legacy::Client;
 use hyper_util::rt::TokioExecutor;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
-use yup_oauth2::{InstalledFlowAuthenticator, InstalledFlowReturnMethod};
+use tokio::sync::Mutex;
+use yup_oauth2::storage::{TokenInfo, TokenStorage};
+use yup_oauth2::{
+    DeviceFlowAuthenticator, InstalledFlowAuthenticator, InstalledFlowReturnMethod, ServiceAccountAuthenticator,
+};
 
-pub async fn authenticate() -> Result<DriveHub<HttpsConnector<HttpConnector>>, Box<dyn std::error::Error>> {
+use crate::secrets::{self, SecretError, SecretStore, SECRET_KEY_VAR};
+
+/// Scope requested for Drive access; only files created by LogSync are visible.
+pub const DRIVE_SCOPE: &str = "https://www.googleapis.com/auth/drive.file";
+
+pub const DEFAULT_CLIENT_SECRET_PATH: &str = "client_secret.json";
+pub const DEFAULT_TOKEN_CACHE_PATH: &str = "tokencache.json";
+
+/// How LogSync obtains Google credentials.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthMethod {
+    /// OAuth consent in a browser on this machine.
+    #[default]
+    Installed,
+    /// OAuth device code: the URL and code can be opened on any other device.
+    Device,
+    /// A service-account JSON key; no user interaction at all.
+    ServiceAccount,
+}
+
+/// Credentials used by the Drive backup, part of its `backups` entry.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DriveAuthConfig {
+    #[serde(default)]
+    pub auth: AuthMethod,
+    /// OAuth client for the `installed` and `device` methods.
+    #[serde(default = "default_client_secret")]
+    pub client_secret: PathBuf,
+    /// Where the OAuth tokens are cached between runs; encrypted when
+    /// `LOGSYNC_SECRET_KEY` is set.
+    #[serde(default = "default_token_cache")]
+    pub token_cache: PathBuf,
+    /// Key file for the `service_account` method.
+    #[serde(default)]
+    pub service_account_key: Option<PathBuf>,
+}
+
+impl Default for DriveAuthConfig {
+    fn default() -> Self {
+        Self {
+            auth: AuthMethod::default(),
+            client_secret: default_client_secret(),
+            token_cache: default_token_cache(),
+            service_account_key: None,
+        }
+    }
+}
+
+fn default_client_secret() -> PathBuf {
+    PathBuf::from(DEFAULT_CLIENT_SECRET_PATH)
+}
+
+fn default_token_cache() -> PathBuf {
+    PathBuf::from(DEFAULT_TOKEN_CACHE_PATH)
+}
+
+/// Builds an authenticated Drive hub.
+///
+/// With `interactive` false (the monitor), the OAuth methods only use an
+/// existing token cache instead of waiting for someone to complete the
+/// consent flow; `logsync auth login` creates it.
+pub async fn authenticate(
+    config: &DriveAuthConfig,
+    interactive: bool,
+) -> Result<DriveHub<HttpsConnector<HttpConnector>>, Box<dyn std::error::Error>> {
     // Create an HTTPS connector
     let https = HttpsConnector::new();
 
@@ -16,26 +88,125 @@ pub async fn authenticate() -> Result<DriveHub<HttpsConnector<HttpConnector>>, B
         .http2_only(false) // Allow HTTP/1.1
         .build(https);
 
-    // Load the configuration from client_secret.json
-    let secret = yup_oauth2::read_application_secret("client_secret.json")
-        .await
-        .map_err(|_| "Error reading client_secret.json")?; // Handle errors in reading the secret file
-
-    // Configure the authenticator
-    let auth = InstalledFlowAuthenticator::builder(secret, InstalledFlowReturnMethod::HTTPRedirect)
-        .persist_tokens_to_disk("tokencache.json") // Save tokens to disk for future use
-        .build()
-        .await
-        .expect("Error configuring the authenticator");
+    if config.auth != AuthMethod::ServiceAccount && !interactive && !config.token_cache.exists() {
+        return Err(format!(
+            "no cached Google token in {}; run `logsync auth login` first",
+            config.token_cache.display()
+        )
+        .into());
+    }
 
-    // Define the required scopes for Google Drive access
-    let scopes = &["https://www.googleapis.com/auth/drive.file"];
+    // Configure the authenticator for the selected method
+    let auth = match config.auth {
+        AuthMethod::Installed => {
+            let secret = read_client_secret(&config.client_secret)?;
+            InstalledFlowAuthenticator::builder(secret, InstalledFlowReturnMethod::HTTPRedirect)
+                .with_storage(TokenCache::open(&config.token_cache).map_err(|e| e.to_string())?) // Save tokens to disk for future use
+                .build()
+                .await?
+        }
+        AuthMethod::Device => {
+            let secret = read_client_secret(&config.client_secret)?;
+            DeviceFlowAuthenticator::builder(secret)
+                .with_storage(TokenCache::open(&config.token_cache).map_err(|e| e.to_string())?)
+                .build()
+                .await?
+        }
+        AuthMethod::ServiceAccount => {
+            let path = config
+                .service_account_key
+                .as_ref()
+                .ok_or("'service_account_key' is required for the service_account method")?;
+            let key = secrets::read_secret_file(path)
+                .and_then(|content| Ok(yup_oauth2::parse_service_account_key(content)?))
+                .map_err(|e| format!("Error reading {}: {}", path.display(), e))?;
+            ServiceAccountAuthenticator::builder(key).build().await?
+        }
+    };
 
     // Attempt to obtain an access token for the specified scopes
-    auth.token(scopes).await?; // Return the token directly, errors will propagate
+    auth.token(&[DRIVE_SCOPE]).await?; // Return the token directly, errors will propagate
 
     // Create the Google Drive hub with the HTTP client and authenticator
     let hub = DriveHub::new(client, auth);
 
     Ok(hub) // Return the created hub
-}
\ No newline at end of file
+}
+
+fn read_client_secret(path: &Path) -> Result<yup_oauth2::ApplicationSecret, Box<dyn std::error::Error>> {
+    secrets::read_secret_file(path)
+        .and_then(|content| Ok(yup_oauth2::parse_application_secret(content)?))
+        .map_err(|e| format!("Error reading {}: {}", path.display(), e).into()) // Handle errors in reading the secret file
+}
+
+/// A token cached for a set of scopes; same layout as yup-oauth2's own disk
+/// cache, so existing plaintext caches are picked up.
+#[derive(Serialize, Deserialize)]
+struct CachedToken {
+    scopes: Vec<String>,
+    token: TokenInfo,
+}
+
+/// OAuth token cache kept in a [`SecretStore`] (owner-only, encrypted when a key is set).
+struct TokenCache {
+    path: PathBuf,
+    store: SecretStore,
+    tokens: Mutex<Vec<CachedToken>>,
+}
+
+impl TokenCache {
+    fn open(path: &Path) -> Result<Box<Self>, SecretError> {
+        let store = SecretStore::from_env()?;
+        let tokens: Vec<CachedToken> = match store.read(path)? {
+            Some(content) if !content.is_empty() => serde_json::from_slice(&content)
+                .map_err(|e| format!("Invalid token cache {}: {}", path.display(), e))?,
+            _ => Vec::new(),
+        };
+
+        if store.encrypts() {
+            // Encrypt a cache left in plaintext by an earlier version
+            if path.exists() && !secrets::is_encrypted(&std::fs::read(path)?) {
+                store.write(path, &serde_json::to_vec(&tokens)?)?;
+                println!("Encrypted the token cache {}.", path.display());
+            }
+        } else {
+            eprintln!(
+                "Warning: token cache {} is not encrypted; set {} to encrypt it.",
+                path.display(),
+                SECRET_KEY_VAR
+            );
+        }
+
+        Ok(Box::new(Self {
+            path: path.to_path_buf(),
+            store,
+            tokens: Mutex::new(tokens),
+        }))
+    }
+}
+
+#[async_trait]
+impl TokenStorage for TokenCache {
+    async fn set(&self, scopes: &[&str], token: TokenInfo) -> anyhow::Result<()> {
+        let mut tokens = self.tokens.lock().await;
+        let mut key: Vec<String> = scopes.iter().map(|scope| scope.to_string()).collect();
+        key.sort();
+        tokens.retain(|cached| {
+            let mut scopes = cached.scopes.clone();
+            scopes.sort();
+            scopes != key
+        });
+        tokens.push(CachedToken { scopes: key, token });
+
+        let content = serde_json::to_vec(&*tokens)?;
+        self.store.write(&self.path, &content).map_err(|e| anyhow::anyhow!(e))
+    }
+
+    async fn get(&self, scopes: &[&str]) -> Option<TokenInfo> {
+        let tokens = self.tokens.lock().await;
+        tokens
+            .iter()
+            .find(|cached| scopes.iter().all(|scope| cached.scopes.iter().any(|s| s == scope)))
+            .map(|cached| cached.token.clone())
+    }
+}
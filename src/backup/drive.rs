@@ -0,0 +1,156 @@
+use async_trait::async_trait;
+use google_drive3::DriveHub;
+use hyper_tls::HttpsConnector;
+use hyper_util::client::legacy::connect::HttpConnector;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::{BackupError, BackupSink, UploadProgress};
+use crate::auth::{self, DriveAuthConfig};
+use crate::driver_uploader::{download_file, list_files, upload_resumable, UploadTarget};
+use crate::utils::write_atomically;
+
+/// Google Drive, authenticated as described by `auth`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DriveConfig {
+    #[serde(flatten)]
+    pub auth: DriveAuthConfig,
+    /// Drive folder to create uploads in; the root of "My Drive" if unset.
+    #[serde(default)]
+    pub folder_id: Option<String>,
+    /// Where the IDs of uploaded files are remembered between runs.
+    #[serde(default = "default_state_path")]
+    pub state_path: PathBuf,
+}
+
+impl Default for DriveConfig {
+    fn default() -> Self {
+        Self {
+            auth: DriveAuthConfig::default(),
+            folder_id: None,
+            state_path: default_state_path(),
+        }
+    }
+}
+
+fn default_state_path() -> PathBuf {
+    PathBuf::from("./drive_files.json")
+}
+
+/// A file previously created on Drive for a given remote name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DriveFile {
+    file_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    folder_id: Option<String>,
+}
+
+pub struct DriveSink {
+    name: String,
+    config: DriveConfig,
+    hub: DriveHub<HttpsConnector<HttpConnector>>,
+    files: Mutex<HashMap<String, DriveFile>>,
+}
+
+impl DriveSink {
+    /// Never prompts for consent; without cached tokens this fails until
+    /// `logsync auth login` has been run.
+    pub async fn connect(name: String, config: DriveConfig) -> Result<Self, BackupError> {
+        let files = load_files(&config.state_path)?;
+        let hub = auth::authenticate(&config.auth, false).await.map_err(|e| e.to_string())?;
+        Ok(Self {
+            name,
+            config,
+            hub,
+            files: Mutex::new(files),
+        })
+    }
+
+    /// The file to update for `remote_name`, unless it was created in another folder.
+    fn known_file(&self, remote_name: &str) -> Option<String> {
+        self.files
+            .lock()
+            .unwrap()
+            .get(remote_name)
+            .filter(|file| file.folder_id == self.config.folder_id)
+            .map(|file| file.file_id.clone())
+    }
+
+    fn remember(&self, remote_name: &str, file_id: String) -> std::io::Result<()> {
+        let mut files = self.files.lock().unwrap();
+        files.insert(
+            remote_name.to_string(),
+            DriveFile {
+                file_id,
+                folder_id: self.config.folder_id.clone(),
+            },
+        );
+        let content = serde_json::to_vec_pretty(&*files)?;
+        write_atomically(&self.config.state_path, &content)
+    }
+}
+
+fn load_files(path: &Path) -> Result<HashMap<String, DriveFile>, BackupError> {
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    let content = std::fs::read_to_string(path)?;
+    if content.trim().is_empty() {
+        return Ok(HashMap::new());
+    }
+    Ok(serde_json::from_str(&content).map_err(|e| format!("Invalid Drive state file {}: {}", path.display(), e))?)
+}
+
+#[async_trait]
+impl BackupSink for DriveSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn upload(&self, path: &Path, remote_name: &str, progress: &UploadProgress<'_>) -> Result<(), BackupError> {
+        let on_progress = |sent, total| progress.report(sent, total);
+
+        if let Some(file_id) = self.known_file(remote_name) {
+            let target = UploadTarget::Existing { file_id: &file_id };
+            if upload_resumable(&self.hub, path, target, on_progress).await?.is_some() {
+                return Ok(());
+            }
+            eprintln!(
+                "Drive file {} for {} was deleted or trashed; uploading a new copy.",
+                file_id, remote_name
+            );
+        }
+
+        let target = UploadTarget::New {
+            name: remote_name,
+            folder_id: self.config.folder_id.as_deref(),
+        };
+        let created = upload_resumable(&self.hub, path, target, on_progress)
+            .await?
+            .ok_or("Drive rejected the new file")?;
+        let file_id = created.id.ok_or("Drive did not return an ID for the uploaded file")?;
+        self.remember(remote_name, file_id)?;
+        Ok(())
+    }
+
+    async fn list(&self) -> Result<Vec<String>, BackupError> {
+        let files = list_files(&self.hub, self.config.folder_id.as_deref(), None).await?;
+        let mut names: Vec<String> = files.into_iter().filter_map(|file| file.name).collect();
+        // Older versions created a new file on every upload, so names can repeat
+        names.sort();
+        names.dedup();
+        Ok(names)
+    }
+
+    async fn download(&self, remote_name: &str, target: &Path) -> Result<(), BackupError> {
+        // The newest file with that name, which is the one uploads keep updating
+        let file_id = list_files(&self.hub, self.config.folder_id.as_deref(), Some(remote_name))
+            .await?
+            .into_iter()
+            .find_map(|file| file.id)
+            .ok_or_else(|| format!("{} not found on Drive", remote_name))?;
+        download_file(&self.hub, &file_id, target).await
+    }
+}
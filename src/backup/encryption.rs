@@ -0,0 +1,188 @@
+use age::x25519;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use super::BackupError;
+use crate::secrets;
+use crate::utils::{format_rfc3339, now_secs};
+
+/// Identities (private keys) used by `logsync decrypt` when no identity file is given.
+pub const IDENTITY_VAR: &str = "LOGSYNC_BACKUP_IDENTITY";
+
+/// Appended to the remote name of encrypted uploads.
+pub const ENCRYPTED_SUFFIX: &str = ".age";
+
+/// Encrypts backups before upload so destinations only ever store ciphertext.
+/// Only the public keys live on the server; the identities stay offline.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EncryptionConfig {
+    /// age X25519 public keys (`age1...`), e.g. from `age-keygen`.
+    pub recipients: Vec<String>,
+    /// Label recorded with every upload to tell keys apart after a rotation.
+    #[serde(default)]
+    pub key_id: Option<String>,
+}
+
+/// How a backup was encrypted, recorded in its manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptionMetadata {
+    pub format: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+    pub recipients: Vec<String>,
+    pub original_name: String,
+    /// SHA-256 of the plaintext, hex-encoded.
+    pub sha256: String,
+    pub size: u64,
+    pub encrypted_at: String,
+}
+
+/// An encrypted copy of the backup, ready to upload.
+pub struct EncryptedBackup {
+    pub path: PathBuf,
+    pub metadata: EncryptionMetadata,
+}
+
+pub struct BackupEncryptor {
+    config: EncryptionConfig,
+    recipients: Vec<x25519::Recipient>,
+}
+
+impl BackupEncryptor {
+    pub fn new(config: &EncryptionConfig) -> Result<Self, BackupError> {
+        if config.recipients.is_empty() {
+            return Err("backup encryption needs at least one recipient".into());
+        }
+        let recipients = config
+            .recipients
+            .iter()
+            .map(|recipient| {
+                x25519::Recipient::from_str(recipient.trim())
+                    .map_err(|e| format!("invalid age recipient '{}': {}", recipient, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            config: config.clone(),
+            recipients,
+        })
+    }
+
+    /// Streams `path` through the encryptor into `dir`, hashing the plaintext on the way.
+    pub fn encrypt_file(&self, path: &Path, dir: &Path) -> Result<EncryptedBackup, BackupError> {
+        let original_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or("backup path has no file name")?;
+        let encrypted_path = dir.join(format!("{}{}", original_name, ENCRYPTED_SUFFIX));
+
+        let recipients = self.recipients.iter().map(|r| r as &dyn age::Recipient);
+        let encryptor = age::Encryptor::with_recipients(recipients)?;
+        let mut writer = encryptor.wrap_output(owner_only(&encrypted_path)?)?;
+
+        let mut input = File::open(path)?;
+        let mut hasher = Sha256::new();
+        let mut size = 0u64;
+        let mut buffer = vec![0; 64 * 1024];
+        loop {
+            let read = input.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+            writer.write_all(&buffer[..read])?;
+            size += read as u64;
+        }
+        writer.finish()?.sync_all()?;
+
+        let metadata = EncryptionMetadata {
+            format: "age-x25519".to_string(),
+            key_id: self.config.key_id.clone(),
+            recipients: self.recipients.iter().map(|r| r.to_string()).collect(),
+            original_name,
+            sha256: hex::encode(hasher.finalize()),
+            size,
+            encrypted_at: format_rfc3339(now_secs()),
+        };
+
+        Ok(EncryptedBackup {
+            path: encrypted_path,
+            metadata,
+        })
+    }
+}
+
+/// Parses age identities (`AGE-SECRET-KEY-1...` lines, `#` comments allowed).
+pub fn parse_identities(text: &str) -> Result<Vec<x25519::Identity>, BackupError> {
+    let identities = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| x25519::Identity::from_str(line).map_err(|e| format!("invalid age identity: {}", e)))
+        .collect::<Result<Vec<_>, _>>()?;
+    if identities.is_empty() {
+        return Err("no age identity found".into());
+    }
+    Ok(identities)
+}
+
+/// Loads identities from `path` (permissions checked like any secret file) or
+/// from `LOGSYNC_BACKUP_IDENTITY`.
+pub fn load_identities(path: Option<&Path>) -> Result<Vec<x25519::Identity>, BackupError> {
+    let text = match path {
+        Some(path) => String::from_utf8(secrets::read_secret_file(path)?)
+            .map_err(|_| format!("{} is not valid UTF-8", path.display()))?,
+        None => secrets::env_secret(IDENTITY_VAR)?
+            .ok_or_else(|| format!("no identity given: pass --identity <file> or set {}", IDENTITY_VAR))?,
+    };
+    parse_identities(&text)
+}
+
+/// Decrypts `input` into `output` (readable only by its owner) and returns the
+/// SHA-256 of the plaintext.
+pub fn decrypt_file(input: &Path, output: &Path, identities: &[x25519::Identity]) -> Result<String, BackupError> {
+    let decryptor = age::Decryptor::new_buffered(BufReader::new(File::open(input)?))?;
+    let mut reader = decryptor
+        .decrypt(identities.iter().map(|i| i as &dyn age::Identity))
+        .map_err(|e| match e {
+            age::DecryptError::NoMatchingKeys => "none of the identities can decrypt this file".into(),
+            e => BackupError::from(e),
+        })?;
+
+    let tmp_path = output.with_extension("partial");
+    let mut file = owner_only(&tmp_path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; 64 * 1024];
+    let result = (|| -> Result<(), BackupError> {
+        loop {
+            let read = reader.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+            file.write_all(&buffer[..read])?;
+        }
+        file.sync_all()?;
+        Ok(())
+    })();
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+    std::fs::rename(&tmp_path, output)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Creates (or truncates) a file only its owner can read.
+fn owner_only(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+}
@@ -0,0 +1,56 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use super::{BackupError, BackupSink, UploadProgress};
+use crate::utils::write_atomically;
+
+/// A directory on this machine, e.g. a mounted NAS share or a second disk.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LocalConfig {
+    pub directory: PathBuf,
+}
+
+pub struct LocalSink {
+    name: String,
+    config: LocalConfig,
+}
+
+impl LocalSink {
+    /// Creates the target directory if it does not exist yet.
+    pub fn new(name: String, config: LocalConfig) -> Result<Self, BackupError> {
+        std::fs::create_dir_all(&config.directory)
+            .map_err(|e| format!("Cannot create {}: {}", config.directory.display(), e))?;
+        Ok(Self { name, config })
+    }
+}
+
+#[async_trait]
+impl BackupSink for LocalSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn upload(&self, path: &Path, remote_name: &str, _progress: &UploadProgress<'_>) -> Result<(), BackupError> {
+        let content = tokio::fs::read(path).await?;
+        let target = self.config.directory.join(remote_name);
+        tokio::task::spawn_blocking(move || write_atomically(&target, &content)).await??;
+        Ok(())
+    }
+
+    async fn list(&self) -> Result<Vec<String>, BackupError> {
+        let mut names = Vec::new();
+        let mut entries = tokio::fs::read_dir(&self.config.directory).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            if entry.file_type().await?.is_file() {
+                names.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+        Ok(names)
+    }
+
+    async fn download(&self, remote_name: &str, target: &Path) -> Result<(), BackupError> {
+        tokio::fs::copy(self.config.directory.join(remote_name), target).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use super::encryption::EncryptionMetadata;
+use super::BackupError;
+use crate::types::LogEntry;
+
+/// Appended to a backup's remote name for its manifest.
+pub const MANIFEST_SUFFIX: &str = ".meta.json";
+
+/// Uploaded next to every backup (as `<name>.meta.json`) so destinations can be
+/// listed by host and period, and downloads checked before they are restored.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub host: String,
+    /// Remote name of the backup this manifest describes.
+    pub file: String,
+    pub uploaded_at: String,
+    /// Day the backup belongs to (`YYYY-MM-DD`, local time). Missing for
+    /// backups uploaded before names were dated.
+    #[serde(default)]
+    pub segment: Option<String>,
+    /// SHA-256 of the file as stored at the destination (after encryption), hex-encoded.
+    pub sha256: String,
+    pub size: u64,
+    /// Entries in the backup and the period they cover.
+    pub entries: usize,
+    #[serde(default)]
+    pub first_entry: Option<u64>,
+    #[serde(default)]
+    pub last_entry: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<EncryptionMetadata>,
+}
+
+/// Counts the entries of a filtered log file and the period they span. A file
+/// caught in the middle of a rewrite is an error, so it is not uploaded truncated.
+pub fn entry_stats(path: &Path) -> Result<(usize, Option<u64>, Option<u64>), BackupError> {
+    let entries = read_entries(path)?;
+    let first = entries.iter().map(|entry| entry.timestamp).min();
+    let last = entries.iter().map(|entry| entry.timestamp).max();
+    Ok((entries.len(), first, last))
+}
+
+/// Entries of a filtered log file; an empty file has none.
+pub fn read_entries(path: &Path) -> Result<Vec<LogEntry>, BackupError> {
+    if std::fs::metadata(path)?.len() == 0 {
+        return Ok(Vec::new());
+    }
+    Ok(serde_json::from_reader(BufReader::new(std::fs::File::open(path)?))
+        .map_err(|e| format!("{} is not a filtered log file: {}", path.display(), e))?)
+}
+
+/// Remote name of `file_name` backed up from `host` on `segment` (a day,
+/// `YYYY-MM-DD`), e.g. `filtered_logs.web-1.2024-03-01.json`. Uploads replace
+/// the backup of the same day only, so earlier days stay restorable, and
+/// several machines can share a destination.
+pub fn remote_name(file_name: &str, host: &str, segment: &str) -> String {
+    let tag = format!("{}.{}", sanitize_name(host), sanitize_name(segment));
+    match file_name.rsplit_once('.') {
+        Some((stem, extension)) => format!("{}.{}.{}", stem, tag, extension),
+        None => format!("{}.{}", file_name, tag),
+    }
+}
+
+/// Replaces anything but ASCII letters, digits, `-`, `_` and `.` with `_`, so
+/// host names and names listed by a destination are safe as local file names.
+pub fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || "-_.".contains(c) { c } else { '_' })
+        .collect()
+}
+
+pub fn manifest_name(remote_name: &str) -> String {
+    format!("{}{}", remote_name, MANIFEST_SUFFIX)
+}
+
+pub fn is_manifest(name: &str) -> bool {
+    name.ends_with(MANIFEST_SUFFIX)
+}
+
+/// The manifest stored next to a downloaded backup, if any.
+pub fn read_manifest(path: &Path) -> Result<Option<BackupManifest>, BackupError> {
+    let mut manifest_path = path.as_os_str().to_owned();
+    manifest_path.push(MANIFEST_SUFFIX);
+    let manifest_path = PathBuf::from(manifest_path);
+    if !manifest_path.exists() {
+        return Ok(None);
+    }
+    parse_manifest(&manifest_path).map(Some)
+}
+
+pub fn parse_manifest(path: &Path) -> Result<BackupManifest, BackupError> {
+    let content = std::fs::read(path)?;
+    Ok(serde_json::from_slice(&content).map_err(|e| format!("Invalid manifest {}: {}", path.display(), e))?)
+}
+
+/// Hex-encoded SHA-256 of a file, read in a streaming fashion.
+pub fn file_sha256(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
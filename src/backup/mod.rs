@@ -0,0 +1,202 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::os::unix::fs::DirBuilderExt;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::watch;
+
+use crate::auth::DEFAULT_CLIENT_SECRET_PATH;
+
+pub mod drive;
+pub mod encryption;
+pub mod local;
+pub mod manifest;
+pub mod restore;
+pub mod s3;
+pub mod scheduler;
+pub mod sftp;
+pub mod webdav;
+
+pub use drive::{DriveConfig, DriveSink};
+pub use encryption::{BackupEncryptor, EncryptionConfig};
+pub use local::{LocalConfig, LocalSink};
+pub use manifest::BackupManifest;
+pub use s3::{S3Config, S3Sink};
+pub use scheduler::{BackupSchedule, BackupScheduler};
+pub use sftp::{SftpConfig, SftpSink};
+pub use webdav::{WebDavConfig, WebDavSink};
+
+pub type BackupError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A destination that copies of the filtered log file are uploaded to.
+#[async_trait]
+pub trait BackupSink: Send + Sync {
+    /// Name of the destination as configured, used in logs.
+    fn name(&self) -> &str;
+
+    /// Uploads the file at `path`, replacing any earlier upload stored under `remote_name`.
+    async fn upload(&self, path: &Path, remote_name: &str, progress: &UploadProgress<'_>) -> Result<(), BackupError>;
+
+    /// Names of the files stored at the destination (under its prefix or directory).
+    async fn list(&self) -> Result<Vec<String>, BackupError>;
+
+    /// Downloads `remote_name` into the local file `target`.
+    async fn download(&self, remote_name: &str, target: &Path) -> Result<(), BackupError>;
+}
+
+/// Publishes the progress of an upload to the backup status line shown in the viewer.
+pub struct UploadProgress<'a> {
+    sink: &'a str,
+    status: &'a watch::Sender<String>,
+}
+
+impl<'a> UploadProgress<'a> {
+    pub fn new(sink: &'a str, status: &'a watch::Sender<String>) -> Self {
+        Self { sink, status }
+    }
+
+    pub fn report(&self, sent: u64, total: u64) {
+        let percent = (sent * 100).checked_div(total).unwrap_or(100);
+        self.status.send_replace(format!(
+            "Uploading to {}: {}% ({:.1} of {:.1} MB)",
+            self.sink,
+            percent,
+            sent as f64 / 1_000_000.0,
+            total as f64 / 1_000_000.0
+        ));
+    }
+}
+
+/// A named backup destination as declared in the configuration file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BackupConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: BackupKind,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum BackupKind {
+    Drive(DriveConfig),
+    Local(LocalConfig),
+    S3(S3Config),
+    Webdav(WebDavConfig),
+    Sftp(SftpConfig),
+}
+
+impl BackupConfig {
+    /// Builds the sink. Drive authenticates here, which may start the OAuth flow.
+    pub async fn build(&self) -> Result<Box<dyn BackupSink>, BackupError> {
+        let name = self.name.clone();
+        Ok(match &self.kind {
+            BackupKind::Drive(config) => Box::new(DriveSink::connect(name, config.clone()).await?),
+            BackupKind::Local(config) => Box::new(LocalSink::new(name, config.clone())?),
+            BackupKind::S3(config) => Box::new(S3Sink::new(name, config.clone())?),
+            BackupKind::Webdav(config) => Box::new(WebDavSink::new(name, config.clone())?),
+            BackupKind::Sftp(config) => Box::new(SftpSink::new(name, config.clone())),
+        })
+    }
+}
+
+/// The configured destinations; when there are none, an implicit Drive
+/// backup if `client_secret.json` exists, so existing setups keep working.
+pub fn effective_configs(configs: &[BackupConfig]) -> Vec<BackupConfig> {
+    if configs.is_empty() && Path::new(DEFAULT_CLIENT_SECRET_PATH).exists() {
+        vec![BackupConfig {
+            name: "drive".to_string(),
+            kind: BackupKind::Drive(DriveConfig::default()),
+        }]
+    } else {
+        configs.to_vec()
+    }
+}
+
+/// Builds every destination from [`effective_configs`], disabling (with a
+/// warning) those that cannot be set up. Without any, logs are only kept locally.
+pub async fn build_sinks(configs: &[BackupConfig]) -> Vec<Box<dyn BackupSink>> {
+    let configs = effective_configs(configs);
+
+    let mut sinks = Vec::new();
+    for config in &configs {
+        match config.build().await {
+            Ok(sink) => sinks.push(sink),
+            Err(e) => eprintln!("Warning: backup destination '{}' disabled: {}", config.name, e),
+        }
+    }
+    if sinks.is_empty() {
+        println!("No backup destination configured; filtered logs are only stored locally.");
+    }
+    sinks
+}
+
+/// Private directory for files on their way to or from a destination.
+pub(crate) fn staging_dir() -> std::io::Result<PathBuf> {
+    let dir = std::env::temp_dir().join(format!("logsync-{}", std::process::id()));
+    std::fs::DirBuilder::new().recursive(true).mode(0o700).create(&dir)?;
+    Ok(dir)
+}
+
+/// Writes an HTTP response body to `target` as it arrives.
+pub(crate) async fn save_response(mut response: reqwest::Response, target: &Path) -> Result<(), BackupError> {
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("HTTP {}: {}", status, body).into());
+    }
+    let mut file = tokio::fs::File::create(target).await?;
+    while let Some(chunk) = response.chunk().await? {
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+    Ok(())
+}
+
+/// Percent-encodes everything except RFC 3986 unreserved characters and `/`.
+pub(crate) fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Joins a base URL and a relative path with exactly one `/` between them.
+pub(crate) fn join_url(base: &str, path: &str) -> String {
+    format!("{}/{}", base.trim_end_matches('/'), path.trim_start_matches('/'))
+}
+
+/// Reverses [`encode_path`] (and any other percent-encoding).
+pub(crate) fn decode_path(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+        match (bytes[i], hex.and_then(|hex| u8::from_str_radix(hex, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Replaces the five predefined XML entities in text taken from a listing response.
+pub(crate) fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
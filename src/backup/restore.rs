@@ -0,0 +1,180 @@
+use age::x25519;
+use std::path::Path;
+
+use super::encryption::{decrypt_file, ENCRYPTED_SUFFIX, IDENTITY_VAR};
+use super::manifest::{self, BackupManifest};
+use super::{staging_dir, BackupError, BackupSink};
+use crate::types::LogEntry;
+use crate::utils::parse_time;
+
+/// A backup found at a destination.
+pub struct BackupListing {
+    pub name: String,
+    /// Missing for uploads made before manifests existed.
+    pub manifest: Option<BackupManifest>,
+}
+
+impl BackupListing {
+    pub fn is_encrypted(&self) -> bool {
+        self.name.ends_with(ENCRYPTED_SUFFIX)
+    }
+
+    pub fn host(&self) -> Option<&str> {
+        self.manifest.as_ref().map(|manifest| manifest.host.as_str())
+    }
+
+    /// The day the backup belongs to (`YYYY-MM-DD`).
+    pub fn segment(&self) -> Option<&str> {
+        self.manifest.as_ref().and_then(|manifest| manifest.segment.as_deref())
+    }
+}
+
+/// The backup of `host` for `segment`, or its most recent one without a segment.
+pub fn select_backup(listings: Vec<BackupListing>, host: &str, segment: Option<&str>) -> Option<BackupListing> {
+    listings
+        .into_iter()
+        .filter(|listing| listing.host() == Some(host))
+        .filter(|listing| segment.is_none_or(|segment| listing.segment() == Some(segment)))
+        .max_by_key(|listing| {
+            let uploaded_at = listing.manifest.as_ref().and_then(|manifest| parse_time(&manifest.uploaded_at));
+            (listing.segment().map(str::to_string), uploaded_at)
+        })
+}
+
+/// The backups stored at `sink`, with their manifests.
+pub async fn list_backups(sink: &dyn BackupSink) -> Result<Vec<BackupListing>, BackupError> {
+    let names = sink.list().await?;
+    let dir = staging_dir()?;
+
+    let mut listings = Vec::new();
+    for name in names.iter().filter(|name| !manifest::is_manifest(name)) {
+        let manifest_name = manifest::manifest_name(name);
+        let manifest = if names.contains(&manifest_name) {
+            // Names come from the destination; keep them inside the staging directory
+            let path = dir.join(manifest::sanitize_name(&manifest_name));
+            sink.download(&manifest_name, &path).await?;
+            let manifest = manifest::parse_manifest(&path);
+            let _ = std::fs::remove_file(&path);
+            Some(manifest?)
+        } else if name.ends_with(".json") || name.ends_with(ENCRYPTED_SUFFIX) {
+            None
+        } else {
+            // Not something LogSync uploaded
+            continue;
+        };
+        listings.push(BackupListing {
+            name: name.clone(),
+            manifest,
+        });
+    }
+    listings.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(listings)
+}
+
+/// Downloads a backup, checks it against its manifest, decrypts it with
+/// `identities` if needed and returns its entries.
+pub async fn fetch_entries(
+    sink: &dyn BackupSink,
+    listing: &BackupListing,
+    identities: &[x25519::Identity],
+) -> Result<Vec<LogEntry>, BackupError> {
+    let dir = staging_dir()?;
+    let downloaded = dir.join(format!("restore-{}", manifest::sanitize_name(&listing.name)));
+    let result = async {
+        sink.download(&listing.name, &downloaded).await?;
+        let (downloaded, identities) = (downloaded.clone(), identities.to_vec());
+        let manifest = listing.manifest.clone();
+        let encrypted = listing.is_encrypted();
+        tokio::task::spawn_blocking(move || read_backup(&downloaded, manifest.as_ref(), encrypted, &identities))
+            .await
+            .map_err(|e| BackupError::from(e.to_string()))?
+    }
+    .await;
+    let _ = std::fs::remove_file(&downloaded);
+    result
+}
+
+fn read_backup(
+    path: &Path,
+    manifest: Option<&BackupManifest>,
+    encrypted: bool,
+    identities: &[x25519::Identity],
+) -> Result<Vec<LogEntry>, BackupError> {
+    if let Some(manifest) = manifest {
+        if manifest::file_sha256(path)? != manifest.sha256 {
+            return Err("checksum mismatch: the download does not match its manifest".into());
+        }
+    }
+    if !encrypted {
+        return manifest::read_entries(path);
+    }
+
+    if identities.is_empty() {
+        return Err(format!("the backup is encrypted; pass --identity <file> or set {}", IDENTITY_VAR).into());
+    }
+    let plaintext = path.with_extension("plain");
+    let result = decrypt_file(path, &plaintext, identities).and_then(|hash| {
+        match manifest.and_then(|manifest| manifest.encryption.as_ref()) {
+            Some(encryption) if encryption.sha256 != hash => {
+                Err("checksum mismatch: the decrypted content does not match its manifest".into())
+            }
+            _ => manifest::read_entries(&plaintext),
+        }
+    });
+    let _ = std::fs::remove_file(&plaintext);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listing(host: &str, segment: Option<&str>, uploaded_at: &str) -> BackupListing {
+        let name = manifest::remote_name("filtered_logs.json", host, segment.unwrap_or("undated"));
+        BackupListing {
+            manifest: Some(BackupManifest {
+                host: host.to_string(),
+                file: name.clone(),
+                uploaded_at: uploaded_at.to_string(),
+                segment: segment.map(str::to_string),
+                sha256: String::new(),
+                size: 0,
+                entries: 0,
+                first_entry: None,
+                last_entry: None,
+                encryption: None,
+            }),
+            name,
+        }
+    }
+
+    fn listings() -> Vec<BackupListing> {
+        vec![
+            listing("web-1", Some("2024-03-01"), "2024-03-01T23:00:00+00:00"),
+            listing("web-1", Some("2024-03-02"), "2024-03-02T08:00:00+00:00"),
+            listing("web-2", Some("2024-03-03"), "2024-03-03T08:00:00+00:00"),
+            listing("web-1", None, "2024-02-01T08:00:00+00:00"),
+        ]
+    }
+
+    #[test]
+    fn names_backups_by_host_and_day() {
+        assert_eq!(
+            manifest::remote_name("filtered_logs.json", "web/1", "2024-03-01"),
+            "filtered_logs.web_1.2024-03-01.json"
+        );
+        assert_eq!(manifest::sanitize_name("../../etc/passwd"), ".._.._etc_passwd");
+    }
+
+    #[test]
+    fn selects_by_host_and_date() {
+        let chosen = select_backup(listings(), "web-1", Some("2024-03-01")).unwrap();
+        assert_eq!(chosen.name, "filtered_logs.web-1.2024-03-01.json");
+
+        let latest = select_backup(listings(), "web-1", None).unwrap();
+        assert_eq!(latest.segment(), Some("2024-03-02"));
+
+        assert!(select_backup(listings(), "web-2", Some("2024-03-01")).is_none());
+        assert!(select_backup(listings(), "db-1", None).is_none());
+    }
+}
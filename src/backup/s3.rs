@@ -0,0 +1,231 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use regex::Regex;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::time::Duration;
+
+use super::{encode_path, join_url, save_response, unescape_xml, BackupError, BackupSink, UploadProgress};
+
+/// Timeout for a single object upload.
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// SHA-256 of an empty payload, for requests without a body.
+const EMPTY_PAYLOAD_HASH: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+/// An S3-compatible bucket (AWS S3, MinIO, Ceph RGW, Garage...), written with
+/// SigV4-signed `PutObject` requests and read back with `ListObjectsV2` and `GetObject`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct S3Config {
+    /// e.g. `https://s3.eu-west-1.amazonaws.com` or `http://localhost:9000`.
+    pub endpoint: String,
+    pub bucket: String,
+    #[serde(default = "default_region")]
+    pub region: String,
+    pub access_key: String,
+    pub secret_key: String,
+    /// Prepended to object names, e.g. `servers/web-1/`.
+    #[serde(default)]
+    pub prefix: String,
+    /// Address the bucket as `endpoint/bucket` (MinIO and most self-hosted
+    /// servers) instead of `bucket.endpoint`.
+    #[serde(default = "default_path_style")]
+    pub path_style: bool,
+}
+
+fn default_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_path_style() -> bool {
+    true
+}
+
+pub struct S3Sink {
+    name: String,
+    config: S3Config,
+    client: reqwest::Client,
+}
+
+impl S3Sink {
+    pub fn new(name: String, config: S3Config) -> Result<Self, BackupError> {
+        let endpoint = reqwest::Url::parse(&config.endpoint).map_err(|e| format!("invalid 'endpoint': {}", e))?;
+        if endpoint.host_str().is_none() {
+            return Err("'endpoint' has no host".into());
+        }
+        if config.bucket.is_empty() || config.access_key.is_empty() || config.secret_key.is_empty() {
+            return Err("'bucket', 'access_key' and 'secret_key' are required".into());
+        }
+        Ok(Self {
+            name,
+            config,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    fn bucket_url(&self) -> Result<String, BackupError> {
+        if self.config.path_style {
+            Ok(join_url(&self.config.endpoint, &self.config.bucket))
+        } else {
+            let mut endpoint = reqwest::Url::parse(&self.config.endpoint)?;
+            let host = format!("{}.{}", self.config.bucket, endpoint.host_str().unwrap_or_default());
+            endpoint.set_host(Some(&host))?;
+            Ok(endpoint.to_string())
+        }
+    }
+
+    fn object_url(&self, key: &str) -> Result<reqwest::Url, BackupError> {
+        let key = encode_path(&format!("{}{}", self.config.prefix, key));
+        Ok(reqwest::Url::parse(&join_url(&self.bucket_url()?, &key))?)
+    }
+
+    /// Sends a signed request without a body.
+    async fn send_empty(&self, method: reqwest::Method, url: reqwest::Url) -> Result<reqwest::Response, BackupError> {
+        let mut request = self.client.request(method.clone(), url.clone()).timeout(UPLOAD_TIMEOUT);
+        for (header, value) in self.sign(method.as_str(), &url, EMPTY_PAYLOAD_HASH) {
+            request = request.header(header, value);
+        }
+        Ok(request.send().await?)
+    }
+
+    /// Signs a request with AWS Signature Version 4 and returns the headers to add.
+    fn sign(&self, method: &str, url: &reqwest::Url, payload_hash: &str) -> Vec<(&'static str, String)> {
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let mut query: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(key, value)| (encode_query(&key), encode_query(&value)))
+            .collect();
+        query.sort();
+        let query = query
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join("&");
+        let canonical_request = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method,
+            url.path(),
+            query,
+            host,
+            payload_hash,
+            amz_date,
+            signed_headers,
+            payload_hash
+        );
+
+        let scope = format!("{}/{}/s3/aws4_request", date, self.config.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let mut key = hmac(format!("AWS4{}", self.config.secret_key).as_bytes(), date.as_bytes());
+        for part in [self.config.region.as_str(), "s3", "aws4_request"] {
+            key = hmac(&key, part.as_bytes());
+        }
+        let signature = hex::encode(hmac(&key, string_to_sign.as_bytes()));
+
+        vec![
+            ("x-amz-date", amz_date),
+            ("x-amz-content-sha256", payload_hash.to_string()),
+            (
+                "authorization",
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                    self.config.access_key, scope, signed_headers, signature
+                ),
+            ),
+        ]
+    }
+}
+
+/// URI-encodes a query component as SigV4 expects (only unreserved characters kept).
+fn encode_query(value: &str) -> String {
+    encode_path(value).replace('/', "%2F")
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+#[async_trait]
+impl BackupSink for S3Sink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn upload(&self, path: &Path, remote_name: &str, _progress: &UploadProgress<'_>) -> Result<(), BackupError> {
+        let content = tokio::fs::read(path).await?;
+        let url = self.object_url(remote_name)?;
+        let payload_hash = hex::encode(Sha256::digest(&content));
+
+        let mut request = self.client.put(url.clone()).timeout(UPLOAD_TIMEOUT).body(content);
+        for (header, value) in self.sign("PUT", &url, &payload_hash) {
+            request = request.header(header, value);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
+        } else {
+            let body = response.text().await.unwrap_or_default();
+            Err(format!("HTTP {}: {}", status, body).into())
+        }
+    }
+
+    async fn list(&self) -> Result<Vec<String>, BackupError> {
+        let contents = Regex::new(r"(?s)<Contents>.*?<Key>(.*?)</Key>.*?</Contents>")?;
+        let next_token = Regex::new(r"<NextContinuationToken>(.*?)</NextContinuationToken>")?;
+
+        let mut names = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            // Encoded the way it is signed
+            let mut query = format!("list-type=2&prefix={}", encode_query(&self.config.prefix));
+            if let Some(token) = &token {
+                query.push_str(&format!("&continuation-token={}", encode_query(token)));
+            }
+            let mut url = reqwest::Url::parse(&self.bucket_url()?)?;
+            url.set_query(Some(&query));
+            let response = self.send_empty(reqwest::Method::GET, url).await?;
+            let status = response.status();
+            let body = response.text().await?;
+            if !status.is_success() {
+                return Err(format!("HTTP {}: {}", status, body).into());
+            }
+
+            for captures in contents.captures_iter(&body) {
+                let key = unescape_xml(&captures[1]);
+                if let Some(name) = key.strip_prefix(&self.config.prefix) {
+                    names.push(name.to_string());
+                }
+            }
+            // Results come in pages of up to 1000 keys
+            token = match next_token.captures(&body) {
+                Some(captures) if body.contains("<IsTruncated>true</IsTruncated>") => Some(unescape_xml(&captures[1])),
+                _ => break,
+            };
+        }
+        Ok(names)
+    }
+
+    async fn download(&self, remote_name: &str, target: &Path) -> Result<(), BackupError> {
+        let response = self.send_empty(reqwest::Method::GET, self.object_url(remote_name)?).await?;
+        save_response(response, target).await
+    }
+}
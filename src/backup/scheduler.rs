@@ -0,0 +1,291 @@
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::time::{interval_at, sleep_until, Instant, MissedTickBehavior};
+
+use super::manifest::{self, BackupManifest};
+use super::{staging_dir, BackupEncryptor, BackupError, BackupSink, UploadProgress};
+use crate::logger::log_file_written;
+use crate::utils::{format_rfc3339, hostname, now_secs};
+
+/// When the filtered log file is uploaded to the backup destinations.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BackupSchedule {
+    /// Quiet period after the last write before uploading.
+    #[serde(default = "default_debounce_secs")]
+    pub debounce_secs: u64,
+    /// Longest a file that keeps changing waits before it is uploaded anyway.
+    #[serde(default = "default_max_delay_secs")]
+    pub max_delay_secs: u64,
+    /// Every destination is checked (and failed uploads retried) this often; 0 disables.
+    #[serde(default = "default_sync_interval_secs")]
+    pub sync_interval_secs: u64,
+}
+
+impl Default for BackupSchedule {
+    fn default() -> Self {
+        Self {
+            debounce_secs: default_debounce_secs(),
+            max_delay_secs: default_max_delay_secs(),
+            sync_interval_secs: default_sync_interval_secs(),
+        }
+    }
+}
+
+fn default_debounce_secs() -> u64 {
+    10
+}
+
+fn default_max_delay_secs() -> u64 {
+    60
+}
+
+fn default_sync_interval_secs() -> u64 {
+    900
+}
+
+enum Command {
+    Flush(oneshot::Sender<()>),
+}
+
+/// Handle to the task that uploads the filtered log file in the background.
+#[derive(Clone)]
+pub struct BackupScheduler {
+    commands: mpsc::Sender<Command>,
+    status: watch::Receiver<String>,
+}
+
+impl BackupScheduler {
+    /// Starts the upload task. It syncs once at startup, then after writes
+    /// to the file (debounced) and on the periodic sync. Each upload is named
+    /// after this host and comes with a [`BackupManifest`]; with an encryptor,
+    /// only the encrypted copy is uploaded.
+    pub fn spawn(
+        sinks: Vec<Box<dyn BackupSink>>,
+        path: &Path,
+        schedule: BackupSchedule,
+        encryptor: Option<BackupEncryptor>,
+    ) -> Self {
+        let (commands, rx) = mpsc::channel(4);
+        let (status_tx, status) = watch::channel(String::new());
+        let backup = Backup {
+            sinks,
+            path: path.to_path_buf(),
+            encryptor: encryptor.map(Arc::new),
+        };
+        tokio::spawn(run_scheduler(backup, schedule, rx, status_tx));
+        Self { commands, status }
+    }
+
+    /// Latest upload activity (progress, last success or failure) for display.
+    pub fn status(&self) -> watch::Receiver<String> {
+        self.status.clone()
+    }
+
+    /// Uploads any pending change now and waits for it to finish.
+    pub async fn flush(&self) {
+        let (done, wait) = oneshot::channel();
+        if self.commands.send(Command::Flush(done)).await.is_ok() {
+            let _ = wait.await;
+        }
+    }
+}
+
+// What is uploaded and where
+struct Backup {
+    sinks: Vec<Box<dyn BackupSink>>,
+    path: PathBuf,
+    encryptor: Option<Arc<BackupEncryptor>>,
+}
+
+async fn run_scheduler(
+    backup: Backup,
+    schedule: BackupSchedule,
+    mut commands: mpsc::Receiver<Command>,
+    status: watch::Sender<String>,
+) {
+    // Hash of the content last uploaded successfully to each sink
+    let mut uploaded: Vec<Option<Vec<u8>>> = vec![None; backup.sinks.len()];
+    sync(&backup, &mut uploaded, &status).await;
+
+    let debounce = Duration::from_secs(schedule.debounce_secs);
+    let max_delay = Duration::from_secs(schedule.max_delay_secs);
+    let period = Duration::from_secs(schedule.sync_interval_secs.max(1));
+    let mut periodic = interval_at(Instant::now() + period, period);
+    periodic.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    // (first, last) write since the previous upload
+    let mut pending: Option<(Instant, Instant)> = None;
+
+    loop {
+        let deadline = pending.map(|(first, last)| (last + debounce).min(first + max_delay));
+        tokio::select! {
+            _ = log_file_written() => {
+                let now = Instant::now();
+                pending = Some((pending.map_or(now, |(first, _)| first), now));
+            }
+            _ = sleep_until(deadline.unwrap_or_else(Instant::now)), if deadline.is_some() => {
+                pending = None;
+                sync(&backup, &mut uploaded, &status).await;
+            }
+            _ = periodic.tick(), if schedule.sync_interval_secs > 0 => {
+                pending = None;
+                sync(&backup, &mut uploaded, &status).await;
+            }
+            command = commands.recv() => {
+                pending = None;
+                sync(&backup, &mut uploaded, &status).await;
+                match command {
+                    Some(Command::Flush(done)) => {
+                        let _ = done.send(());
+                    }
+                    None => return,
+                }
+            }
+        }
+    }
+}
+
+/// Uploads the file to every sink whose last successful upload had different content.
+async fn sync(backup: &Backup, uploaded: &mut [Option<Vec<u8>>], status: &watch::Sender<String>) {
+    if backup.sinks.is_empty() {
+        return;
+    }
+    let path = &backup.path;
+    let hash = {
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || file_hash(&path)).await
+    };
+    let hash = match hash.map_err(std::io::Error::other).and_then(|hash| hash) {
+        Ok(hash) => hash,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+        Err(e) => {
+            eprintln!("Cannot read {} for backup: {}", path.display(), e);
+            return;
+        }
+    };
+    if uploaded.iter().all(|last| last.as_ref() == Some(&hash)) {
+        return;
+    }
+
+    if backup.encryptor.is_some() {
+        status.send_replace("Encrypting backup...".to_string());
+    }
+    let prepared = {
+        let path = path.to_path_buf();
+        let encryptor = backup.encryptor.clone();
+        tokio::task::spawn_blocking(move || prepare_upload(&path, encryptor.as_deref())).await
+    };
+    let prepared = match prepared.map_err(|e| BackupError::from(e.to_string())).and_then(|prepared| prepared) {
+        Ok(prepared) => prepared,
+        Err(e) => {
+            eprintln!("Cannot prepare {} for backup: {}", path.display(), e);
+            status.send_replace(format!("Backup preparation failed: {}", e));
+            return;
+        }
+    };
+    // The file may have changed since it was hashed; remember what was actually uploaded
+    let hash = prepared.hash.clone();
+
+    for (sink, last) in backup.sinks.iter().zip(uploaded.iter_mut()) {
+        if last.as_ref() == Some(&hash) {
+            continue;
+        }
+        status.send_replace(format!("Uploading to {}...", sink.name()));
+        let progress = UploadProgress::new(sink.name(), status);
+        // The manifest goes last, so it never describes content that is not there yet
+        let mut result = sink.upload(&prepared.data, &prepared.remote_name, &progress).await;
+        if result.is_ok() {
+            let manifest_name = manifest::manifest_name(&prepared.remote_name);
+            result = sink.upload(&prepared.manifest, &manifest_name, &progress).await;
+        }
+        let time = chrono::Local::now().format("%H:%M:%S");
+        match result {
+            Ok(()) => {
+                println!("Backup uploaded to {}.", sink.name());
+                status.send_replace(format!("Uploaded to {} at {}", sink.name(), time));
+                *last = Some(hash.clone());
+            }
+            Err(e) => {
+                eprintln!("Error uploading backup to {}: {}", sink.name(), e);
+                status.send_replace(format!("Upload to {} failed at {}: {}", sink.name(), time, e));
+            }
+        }
+    }
+
+    for file in [&prepared.data, &prepared.manifest] {
+        let _ = std::fs::remove_file(file);
+    }
+}
+
+/// A snapshot of the file (encrypted if configured) and its manifest in the staging directory.
+struct PreparedUpload {
+    data: PathBuf,
+    manifest: PathBuf,
+    remote_name: String,
+    /// SHA-256 of the snapshot before encryption
+    hash: Vec<u8>,
+}
+
+fn prepare_upload(path: &Path, encryptor: Option<&BackupEncryptor>) -> Result<PreparedUpload, BackupError> {
+    let host = hostname();
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "filtered_logs.json".to_string());
+    let dir = staging_dir()?;
+    let segment = chrono::Local::now().format("%Y-%m-%d").to_string();
+
+    // Work on a copy so the manifest, hash and upload all describe the same content
+    let snapshot = dir.join(manifest::remote_name(&file_name, &host, &segment));
+    std::fs::copy(path, &snapshot)?;
+    let hash = file_hash(&snapshot)?;
+    let (entries, first_entry, last_entry) = manifest::entry_stats(&snapshot)?;
+
+    let (data, encryption) = match encryptor {
+        Some(encryptor) => {
+            let encrypted = encryptor.encrypt_file(&snapshot, &dir);
+            let _ = std::fs::remove_file(&snapshot);
+            let encrypted = encrypted?;
+            (encrypted.path, Some(encrypted.metadata))
+        }
+        None => (snapshot, None),
+    };
+    let remote_name = data
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let manifest = BackupManifest {
+        host,
+        file: remote_name.clone(),
+        uploaded_at: format_rfc3339(now_secs()),
+        segment: Some(segment),
+        sha256: manifest::file_sha256(&data)?,
+        size: std::fs::metadata(&data)?.len(),
+        entries,
+        first_entry,
+        last_entry,
+        encryption,
+    };
+    let manifest_path = dir.join(manifest::manifest_name(&remote_name));
+    std::fs::write(&manifest_path, serde_json::to_vec_pretty(&manifest)?)?;
+
+    Ok(PreparedUpload {
+        data,
+        manifest: manifest_path,
+        remote_name,
+        hash,
+    })
+}
+
+/// SHA-256 of the file, read in a streaming fashion so large archives are not loaded into memory.
+fn file_hash(path: &Path) -> std::io::Result<Vec<u8>> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hasher.finalize().to_vec())
+}
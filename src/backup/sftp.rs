@@ -0,0 +1,140 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use super::{BackupError, BackupSink, UploadProgress};
+
+/// Timeout for a whole `sftp` session.
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// An SFTP server, reached through the system `sftp` client in batch mode.
+/// Authentication uses `identity_file` or the SSH agent; the host key must
+/// already be in `known_hosts`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SftpConfig {
+    pub host: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    pub username: String,
+    #[serde(default)]
+    pub identity_file: Option<PathBuf>,
+    /// Directory on the server; must already exist.
+    #[serde(default = "default_remote_dir")]
+    pub remote_dir: String,
+}
+
+fn default_port() -> u16 {
+    22
+}
+
+fn default_remote_dir() -> String {
+    ".".to_string()
+}
+
+pub struct SftpSink {
+    name: String,
+    config: SftpConfig,
+}
+
+impl SftpSink {
+    pub fn new(name: String, config: SftpConfig) -> Self {
+        Self { name, config }
+    }
+
+    fn remote_path(&self, remote_name: &str) -> String {
+        format!("{}/{}", self.config.remote_dir.trim_end_matches('/'), remote_name)
+    }
+
+    /// Runs `batch` in one `sftp` session and returns what it printed.
+    async fn run_batch(&self, batch: &str) -> Result<String, BackupError> {
+        let mut command = Command::new("sftp");
+        command
+            .arg("-b")
+            .arg("-")
+            .arg("-P")
+            .arg(self.config.port.to_string())
+            .args(["-o", "BatchMode=yes"]);
+        if let Some(identity_file) = &self.config.identity_file {
+            command.arg("-i").arg(identity_file);
+        }
+        command
+            .arg(format!("{}@{}", self.config.username, self.config.host))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        let mut child = command
+            .spawn()
+            .map_err(|e| format!("Cannot run sftp: {}", e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(batch.as_bytes()).await?;
+        }
+
+        let output = tokio::time::timeout(UPLOAD_TIMEOUT, child.wait_with_output())
+            .await
+            .map_err(|_| "sftp timed out")??;
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        } else {
+            Err(format!(
+                "sftp exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )
+            .into())
+        }
+    }
+}
+
+/// Quotes an argument for the sftp batch-file parser.
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[async_trait]
+impl BackupSink for SftpSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn upload(&self, path: &Path, remote_name: &str, _progress: &UploadProgress<'_>) -> Result<(), BackupError> {
+        let local = path.to_str().ok_or("Backup path is not valid UTF-8")?;
+        let target = self.remote_path(remote_name);
+        // Upload under a temporary name and rename, so readers never see a partial file
+        let partial = format!("{}.partial", target);
+        let batch = format!(
+            "put {} {}\n-rm {}\nrename {} {}\n",
+            quote(local),
+            quote(&partial),
+            quote(&target),
+            quote(&partial),
+            quote(&target)
+        );
+        self.run_batch(&batch).await?;
+        Ok(())
+    }
+
+    async fn list(&self) -> Result<Vec<String>, BackupError> {
+        let output = self.run_batch(&format!("ls -1 {}\n", quote(&self.config.remote_dir))).await?;
+        // sftp echoes each batch command as `sftp> ...`; `ls` prints one path per line
+        Ok(output
+            .lines()
+            .filter(|line| !line.starts_with("sftp>"))
+            .filter_map(|line| line.trim().rsplit('/').next())
+            .filter(|name| !name.is_empty() && !name.ends_with(".partial"))
+            .map(str::to_string)
+            .collect())
+    }
+
+    async fn download(&self, remote_name: &str, target: &Path) -> Result<(), BackupError> {
+        let local = target.to_str().ok_or("Download path is not valid UTF-8")?;
+        let batch = format!("get {} {}\n", quote(&self.remote_path(remote_name)), quote(local));
+        self.run_batch(&batch).await?;
+        Ok(())
+    }
+}
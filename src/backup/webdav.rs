@@ -0,0 +1,114 @@
+use async_trait::async_trait;
+use regex::Regex;
+use serde::Deserialize;
+use std::path::Path;
+use std::time::Duration;
+
+use super::{decode_path, encode_path, join_url, save_response, unescape_xml, BackupError, BackupSink, UploadProgress};
+
+/// Timeout for a single WebDAV upload.
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// A WebDAV collection (Nextcloud, ownCloud, Apache mod_dav...). The
+/// collection at `url` must already exist.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebDavConfig {
+    pub url: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+pub struct WebDavSink {
+    name: String,
+    config: WebDavConfig,
+    client: reqwest::Client,
+}
+
+impl WebDavSink {
+    pub fn new(name: String, config: WebDavConfig) -> Result<Self, BackupError> {
+        reqwest::Url::parse(&config.url).map_err(|e| format!("invalid 'url': {}", e))?;
+        Ok(Self {
+            name,
+            config,
+            client: reqwest::Client::new(),
+        })
+    }
+
+    fn request(&self, method: reqwest::Method, url: String) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, url).timeout(UPLOAD_TIMEOUT);
+        match &self.config.username {
+            Some(username) => request.basic_auth(username, self.config.password.as_ref()),
+            None => request,
+        }
+    }
+}
+
+#[async_trait]
+impl BackupSink for WebDavSink {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn upload(&self, path: &Path, remote_name: &str, _progress: &UploadProgress<'_>) -> Result<(), BackupError> {
+        let content = tokio::fs::read(path).await?;
+        let response = self
+            .request(reqwest::Method::PUT, join_url(&self.config.url, &encode_path(remote_name)))
+            .body(content)
+            .send()
+            .await?;
+        let status = response.status();
+        if status.is_success() {
+            Ok(())
+        } else {
+            let body = response.text().await.unwrap_or_default();
+            Err(format!("HTTP {}: {}", status, body).into())
+        }
+    }
+
+    async fn list(&self) -> Result<Vec<String>, BackupError> {
+        // Servers pick their own namespace prefix (`d:`, `D:`, `lp1:`...), so match on local names
+        let responses = Regex::new(r"(?s)<(?:[\w-]+:)?response\b[^>]*>(.*?)</(?:[\w-]+:)?response>")?;
+        let href = Regex::new(r"(?s)<(?:[\w-]+:)?href\b[^>]*>(.*?)</(?:[\w-]+:)?href>")?;
+        let collection = Regex::new(r"<(?:[\w-]+:)?collection\b")?;
+
+        let method = reqwest::Method::from_bytes(b"PROPFIND")?;
+        let response = self
+            .request(method, join_url(&self.config.url, ""))
+            .header("Depth", "1")
+            .header(reqwest::header::CONTENT_TYPE, "application/xml")
+            .body(r#"<?xml version="1.0"?><d:propfind xmlns:d="DAV:"><d:prop><d:resourcetype/></d:prop></d:propfind>"#)
+            .send()
+            .await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(format!("HTTP {}: {}", status, body).into());
+        }
+
+        let mut names = Vec::new();
+        for captures in responses.captures_iter(&body) {
+            let entry = &captures[1];
+            // Skips the collection itself and any subdirectories
+            if collection.is_match(entry) {
+                continue;
+            }
+            if let Some(href) = href.captures(entry) {
+                let path = decode_path(&unescape_xml(href[1].trim()));
+                if let Some(name) = path.rsplit('/').next().filter(|name| !name.is_empty()) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        Ok(names)
+    }
+
+    async fn download(&self, remote_name: &str, target: &Path) -> Result<(), BackupError> {
+        let response = self
+            .request(reqwest::Method::GET, join_url(&self.config.url, &encode_path(remote_name)))
+            .send()
+            .await?;
+        save_response(response, target).await
+    }
+}
@@ -0,0 +1,604 @@
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::sync::mpsc::{Receiver, Sender};
+
+use crate::logger::record_entry;
+use crate::notifier::routing::{EntryMatcher, RouteMatcher};
+use crate::notifier::AlertDispatcher;
+use crate::parser::fingerprint;
+use crate::types::{DeliveryStatus, LogEntry};
+use crate::utils::{generate_entry_id, now_secs, parse_duration_secs};
+
+/// How often absence conditions are checked when no lines arrive.
+const TICK: Duration = Duration::from_secs(15);
+
+/// Type given to entries raised by conditions, usable in routes and silences.
+pub const CONDITION_LOG_TYPE: &str = "CONDITION";
+
+/// An alert condition evaluated over every monitored line, not just errors.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConditionConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: ConditionKind,
+    /// Priority of the raised entry (`very high` bypasses the digest).
+    #[serde(default = "default_priority")]
+    pub priority: String,
+    /// Minimum time between two alerts for the condition; defaults to its window.
+    #[serde(default)]
+    pub cooldown: Option<String>,
+}
+
+fn default_priority() -> String {
+    "high".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ConditionKind {
+    /// At least `count` matching lines within `window`.
+    Threshold {
+        #[serde(default, rename = "match")]
+        matcher: RouteMatcher,
+        count: usize,
+        window: String,
+    },
+    /// Matching lines in the last `window` reach `factor` times the average per
+    /// window over the preceding `baseline`.
+    Rate {
+        #[serde(default, rename = "match")]
+        matcher: RouteMatcher,
+        window: String,
+        baseline: String,
+        factor: f64,
+        #[serde(default = "default_min_count")]
+        min_count: usize,
+    },
+    /// No matching line (e.g. a heartbeat) for `within`.
+    Absence {
+        #[serde(default, rename = "match")]
+        matcher: RouteMatcher,
+        within: String,
+    },
+    /// Matching lines divided by `total` lines (every line by default) within
+    /// `window` reaches `threshold`.
+    Ratio {
+        #[serde(default, rename = "match")]
+        matcher: RouteMatcher,
+        #[serde(default)]
+        total: RouteMatcher,
+        window: String,
+        threshold: f64,
+        #[serde(default = "default_min_total")]
+        min_total: usize,
+    },
+}
+
+fn default_min_count() -> usize {
+    5
+}
+
+fn default_min_total() -> usize {
+    20
+}
+
+/// A condition that fired.
+#[derive(Debug, Clone)]
+pub struct ConditionAlert {
+    pub name: String,
+    pub priority: String,
+    pub message: String,
+    /// An absence condition whose matching line was seen again.
+    pub recovered: bool,
+}
+
+enum State {
+    Threshold {
+        matcher: EntryMatcher,
+        count: usize,
+        window: u64,
+        hits: VecDeque<u64>,
+    },
+    Rate {
+        matcher: EntryMatcher,
+        window: u64,
+        baseline: u64,
+        factor: f64,
+        min_count: usize,
+        hits: VecDeque<u64>,
+        started_at: u64,
+    },
+    Absence {
+        matcher: EntryMatcher,
+        within: u64,
+        last_seen: u64,
+        missing: bool,
+    },
+    Ratio {
+        matcher: EntryMatcher,
+        total: EntryMatcher,
+        window: u64,
+        threshold: f64,
+        min_total: usize,
+        lines: VecDeque<(u64, bool)>,
+    },
+}
+
+struct Condition {
+    name: String,
+    priority: String,
+    cooldown: u64,
+    last_fired: Option<u64>,
+    state: State,
+}
+
+impl Condition {
+    fn compile(config: &ConditionConfig, now: u64) -> Result<Self, String> {
+        let duration = |text: &str| {
+            parse_duration_secs(text)
+                .filter(|&secs| secs > 0)
+                .ok_or_else(|| format!("Invalid duration '{}'", text))
+        };
+        let matcher = |matcher: &RouteMatcher| EntryMatcher::new(matcher);
+
+        let (state, window) = match &config.kind {
+            ConditionKind::Threshold { matcher: m, count, window } => {
+                let window = duration(window)?;
+                (
+                    State::Threshold {
+                        matcher: matcher(m)?,
+                        count: (*count).max(1),
+                        window,
+                        hits: VecDeque::new(),
+                    },
+                    window,
+                )
+            }
+            ConditionKind::Rate { matcher: m, window, baseline, factor, min_count } => {
+                let window = duration(window)?;
+                let baseline = duration(baseline)?;
+                if baseline < window {
+                    return Err("baseline must be at least as long as window".to_string());
+                }
+                (
+                    State::Rate {
+                        matcher: matcher(m)?,
+                        window,
+                        baseline,
+                        factor: *factor,
+                        min_count: (*min_count).max(1),
+                        hits: VecDeque::new(),
+                        started_at: now,
+                    },
+                    window,
+                )
+            }
+            ConditionKind::Absence { matcher: m, within } => {
+                let within = duration(within)?;
+                (
+                    State::Absence {
+                        matcher: matcher(m)?,
+                        within,
+                        last_seen: now,
+                        missing: false,
+                    },
+                    within,
+                )
+            }
+            ConditionKind::Ratio { matcher: m, total, window, threshold, min_total } => {
+                let window = duration(window)?;
+                (
+                    State::Ratio {
+                        matcher: matcher(m)?,
+                        total: matcher(total)?,
+                        window,
+                        threshold: *threshold,
+                        min_total: (*min_total).max(1),
+                        lines: VecDeque::new(),
+                    },
+                    window,
+                )
+            }
+        };
+
+        let cooldown = match &config.cooldown {
+            Some(cooldown) => duration(cooldown)?,
+            None => window,
+        };
+
+        Ok(Self {
+            name: config.name.clone(),
+            priority: config.priority.clone(),
+            cooldown,
+            last_fired: None,
+            state,
+        })
+    }
+
+    fn observe(&mut self, entry: &LogEntry, now: u64) -> Option<String> {
+        match &mut self.state {
+            State::Threshold { matcher, hits, .. } | State::Rate { matcher, hits, .. } => {
+                if matcher.matches(entry) {
+                    hits.push_back(now);
+                }
+            }
+            State::Absence { matcher, last_seen, missing, within } => {
+                if matcher.matches(entry) {
+                    *last_seen = now;
+                    if std::mem::take(missing) {
+                        return Some(format!(
+                            "Condition '{}' recovered: matching line seen again (expected within {})",
+                            self.name,
+                            format_secs(*within)
+                        ));
+                    }
+                }
+                return None;
+            }
+            State::Ratio { matcher, total, lines, .. } => {
+                if total.matches(entry) {
+                    lines.push_back((now, matcher.matches(entry)));
+                }
+            }
+        }
+
+        self.evaluate(now)
+    }
+
+    fn evaluate(&mut self, now: u64) -> Option<String> {
+        if self
+            .last_fired
+            .is_some_and(|fired| now.saturating_sub(fired) < self.cooldown)
+        {
+            return None;
+        }
+
+        let message = match &mut self.state {
+            State::Threshold { count, window, hits, .. } => {
+                prune(hits, now, *window, |&t| t);
+                (hits.len() >= *count).then(|| {
+                    format!(
+                        "Condition '{}': {} matching lines in the last {} (threshold {})",
+                        self.name,
+                        hits.len(),
+                        format_secs(*window),
+                        count
+                    )
+                })
+            }
+            State::Rate { window, baseline, factor, min_count, hits, started_at, .. } => {
+                prune(hits, now, *window + *baseline, |&t| t);
+                // Wait until a full baseline has been observed
+                if now.saturating_sub(*started_at) < *window + *baseline {
+                    return None;
+                }
+                let current = hits.iter().filter(|&&t| now.saturating_sub(t) < *window).count();
+                let baseline_rate = (hits.len() - current) as f64 * *window as f64 / *baseline as f64;
+                (current >= *min_count && current as f64 >= *factor * baseline_rate).then(|| {
+                    format!(
+                        "Condition '{}': {} matching lines in the last {}, {:.1}x the baseline of {:.1} per {} over the previous {}",
+                        self.name,
+                        current,
+                        format_secs(*window),
+                        if baseline_rate > 0.0 { current as f64 / baseline_rate } else { f64::INFINITY },
+                        baseline_rate,
+                        format_secs(*window),
+                        format_secs(*baseline)
+                    )
+                })
+            }
+            State::Absence { within, last_seen, missing, .. } => {
+                (!*missing && now.saturating_sub(*last_seen) >= *within).then(|| {
+                    *missing = true;
+                    format!(
+                        "Condition '{}': no matching line for {}",
+                        self.name,
+                        format_secs(now.saturating_sub(*last_seen))
+                    )
+                })
+            }
+            State::Ratio { window, threshold, min_total, lines, .. } => {
+                prune(lines, now, *window, |&(t, _)| t);
+                let matching = lines.iter().filter(|(_, matched)| *matched).count();
+                let ratio = matching as f64 / lines.len().max(1) as f64;
+                (lines.len() >= *min_total && ratio >= *threshold).then(|| {
+                    format!(
+                        "Condition '{}': {} of {} lines ({:.0}%) in the last {} matched (threshold {:.0}%)",
+                        self.name,
+                        matching,
+                        lines.len(),
+                        ratio * 100.0,
+                        format_secs(*window),
+                        *threshold * 100.0
+                    )
+                })
+            }
+        };
+
+        // Absence alerts fire once per outage instead of following the cooldown
+        if message.is_some() && !matches!(self.state, State::Absence { .. }) {
+            self.last_fired = Some(now);
+        }
+        message
+    }
+}
+
+/// Drops items older than `window` seconds from the front of the queue.
+fn prune<T>(items: &mut VecDeque<T>, now: u64, window: u64, time: impl Fn(&T) -> u64) {
+    while items.front().is_some_and(|item| now.saturating_sub(time(item)) >= window) {
+        items.pop_front();
+    }
+}
+
+fn format_secs(secs: u64) -> String {
+    match secs {
+        s if s % 86400 == 0 => format!("{}d", s / 86400),
+        s if s % 3600 == 0 => format!("{}h", s / 3600),
+        s if s >= 60 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
+
+/// Evaluates every configured condition against the monitored lines.
+pub struct ConditionEvaluator {
+    conditions: Vec<Condition>,
+}
+
+impl ConditionEvaluator {
+    pub fn new(configs: &[ConditionConfig]) -> Result<Self, String> {
+        let now = now_secs();
+        let conditions = configs
+            .iter()
+            .map(|config| {
+                Condition::compile(config, now).map_err(|e| format!("Condition '{}': {}", config.name, e))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Self { conditions })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
+
+    /// Feeds one monitored line, returning the conditions it made fire.
+    pub fn observe(&mut self, entry: &LogEntry, now: u64) -> Vec<ConditionAlert> {
+        self.conditions
+            .iter_mut()
+            .filter_map(|condition| {
+                condition.observe(entry, now).map(|message| ConditionAlert {
+                    name: condition.name.clone(),
+                    priority: condition.priority.clone(),
+                    // Absence conditions only report recoveries when a line arrives
+                    recovered: matches!(condition.state, State::Absence { .. }),
+                    message,
+                })
+            })
+            .collect()
+    }
+
+    /// Re-evaluates time-based conditions (absences) without a new line.
+    pub fn tick(&mut self, now: u64) -> Vec<ConditionAlert> {
+        self.conditions
+            .iter_mut()
+            .filter(|condition| matches!(condition.state, State::Absence { .. }))
+            .filter_map(|condition| {
+                condition.evaluate(now).map(|message| ConditionAlert {
+                    name: condition.name.clone(),
+                    priority: condition.priority.clone(),
+                    message,
+                    recovered: false,
+                })
+            })
+            .collect()
+    }
+}
+
+impl ConditionAlert {
+    /// The entry stored and dispatched for this alert. Every alert of a
+    /// condition shares one fingerprint, so its recovery matches its incident.
+    pub fn to_entry(&self) -> LogEntry {
+        let source = Some("logsync".to_string());
+        LogEntry {
+            id: generate_entry_id(),
+            timestamp: now_secs(),
+            log_type: CONDITION_LOG_TYPE.to_string(),
+            priority: self.priority.clone(),
+            fingerprint: fingerprint(CONDITION_LOG_TYPE, source.as_deref(), &self.name),
+            message: self.message.clone(),
+            source,
+            tags: vec![self.name.clone()],
+            alert_requested: true,
+            delivery_status: DeliveryStatus::NotRequired,
+            acknowledged_by: None,
+        }
+    }
+}
+
+/// Runs the evaluator next to the line processing: receives every new line,
+/// and stores and dispatches an entry whenever a condition fires.
+pub async fn run_condition_evaluator(
+    mut evaluator: ConditionEvaluator,
+    mut lines: Receiver<LogEntry>,
+    log_file_path: String,
+    tx: Sender<LogEntry>,
+    dispatcher: AlertDispatcher,
+) {
+    let mut interval = tokio::time::interval(TICK);
+
+    loop {
+        let alerts = tokio::select! {
+            line = lines.recv() => match line {
+                Some(line) => evaluator.observe(&line, now_secs()),
+                None => break,
+            },
+            _ = interval.tick() => evaluator.tick(now_secs()),
+        };
+
+        for alert in alerts {
+            println!("{}", alert.message);
+            let entry = alert.to_entry();
+            let result = if alert.recovered {
+                // The recovery closes the incident opened by the alert instead of joining it
+                dispatcher.resolve_incident(&entry.fingerprint);
+                record_entry(entry, &log_file_path, &tx, &dispatcher.without_incidents()).await
+            } else {
+                record_entry(entry, &log_file_path, &tx, &dispatcher).await
+            };
+            if let Err(e) = result {
+                eprintln!("Error recording condition alert: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::test_support::log_entry;
+    use serde_json::json;
+
+    fn evaluator(config: serde_json::Value, now: u64) -> ConditionEvaluator {
+        let config: ConditionConfig = serde_json::from_value(config).unwrap();
+        ConditionEvaluator {
+            conditions: vec![Condition::compile(&config, now).unwrap()],
+        }
+    }
+
+    fn line(log_type: &str) -> LogEntry {
+        let mut entry = log_entry("line");
+        entry.log_type = log_type.to_string();
+        entry
+    }
+
+    /// Feeds a line of `log_type` at each time, returning the times at which an alert fired.
+    fn fired_at(evaluator: &mut ConditionEvaluator, log_type: &str, times: &[u64]) -> Vec<u64> {
+        times
+            .iter()
+            .filter(|&&now| !evaluator.observe(&line(log_type), now).is_empty())
+            .copied()
+            .collect()
+    }
+
+    #[test]
+    fn threshold_counts_matching_lines_within_the_window() {
+        let mut evaluator = evaluator(
+            json!({ "name": "errors", "kind": "threshold", "match": { "log_type": ["ERROR"] }, "count": 3, "window": "1m" }),
+            0,
+        );
+
+        assert!(fired_at(&mut evaluator, "INFO", &[0, 1, 2, 3]).is_empty());
+        let alerts = evaluator.observe(&line("ERROR"), 0);
+        assert!(alerts.is_empty());
+        assert_eq!(fired_at(&mut evaluator, "ERROR", &[10, 20]), [20]);
+        // Within the cooldown (the window by default)
+        assert!(fired_at(&mut evaluator, "ERROR", &[30]).is_empty());
+        // 0, 10 and 20 have left the window by 81
+        assert!(fired_at(&mut evaluator, "ERROR", &[81]).is_empty());
+        let alerts = evaluator.observe(&line("ERROR"), 85);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].message, "Condition 'errors': 3 matching lines in the last 1m (threshold 3)");
+        assert!(!alerts[0].recovered);
+    }
+
+    #[test]
+    fn explicit_cooldown_overrides_the_window() {
+        let mut evaluator = evaluator(
+            json!({ "name": "any", "kind": "threshold", "count": 1, "window": "1m", "cooldown": "5m" }),
+            0,
+        );
+
+        assert_eq!(fired_at(&mut evaluator, "INFO", &[0, 100, 299, 300, 301]), [0, 300]);
+    }
+
+    #[test]
+    fn rate_compares_the_window_with_the_baseline() {
+        let config = json!({
+            "name": "spike", "kind": "rate", "match": { "log_type": ["ERROR"] },
+            "window": "1m", "baseline": "10m", "factor": 3.0, "min_count": 5
+        });
+
+        // No alert before a whole baseline has been observed
+        let mut early = evaluator(config.clone(), 0);
+        assert!(fired_at(&mut early, "ERROR", &[100, 101, 102, 103, 104, 105]).is_empty());
+
+        // One line per minute during the baseline, then five in one minute
+        let mut evaluator = evaluator(config, 0);
+        let baseline: Vec<u64> = (0..10).map(|minute| 30 + minute * 60).collect();
+        assert!(fired_at(&mut evaluator, "ERROR", &baseline).is_empty());
+        assert!(fired_at(&mut evaluator, "ERROR", &[661, 662, 663, 664]).is_empty());
+        let alerts = evaluator.observe(&line("ERROR"), 665);
+        assert_eq!(alerts.len(), 1);
+        assert!(alerts[0].message.starts_with("Condition 'spike': 5 matching lines in the last 1m, 5.0x the baseline of 1.0 per 1m"));
+    }
+
+    #[test]
+    fn absence_fires_once_per_outage_and_recovers_with_the_same_fingerprint() {
+        let mut evaluator = evaluator(
+            json!({ "name": "heartbeat", "kind": "absence", "match": { "log_type": ["INFO"] }, "within": "1m" }),
+            0,
+        );
+
+        assert!(evaluator.tick(59).is_empty());
+        let missing = evaluator.tick(60);
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].message, "Condition 'heartbeat': no matching line for 1m");
+        assert!(evaluator.tick(120).is_empty());
+        assert!(fired_at(&mut evaluator, "ERROR", &[125]).is_empty());
+
+        let recovered = evaluator.observe(&line("INFO"), 130);
+        assert_eq!(recovered.len(), 1);
+        assert!(recovered[0].recovered);
+        assert_eq!(recovered[0].to_entry().fingerprint, missing[0].to_entry().fingerprint);
+        assert!(fired_at(&mut evaluator, "INFO", &[140]).is_empty());
+        assert!(evaluator.tick(199).is_empty());
+        assert_eq!(evaluator.tick(200).len(), 1);
+    }
+
+    #[test]
+    fn ratio_needs_enough_lines_in_the_window() {
+        let mut evaluator = evaluator(
+            json!({
+                "name": "error-ratio", "kind": "ratio", "match": { "log_type": ["ERROR"] },
+                "window": "1m", "threshold": 0.5, "min_total": 4
+            }),
+            0,
+        );
+
+        assert!(fired_at(&mut evaluator, "ERROR", &[0, 1]).is_empty());
+        assert!(fired_at(&mut evaluator, "INFO", &[2]).is_empty());
+        let alerts = evaluator.observe(&line("INFO"), 3);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(
+            alerts[0].message,
+            "Condition 'error-ratio': 2 of 4 lines (50%) in the last 1m matched (threshold 50%)"
+        );
+
+        // The earlier lines have left the window, so 102 only has three lines
+        assert!(fired_at(&mut evaluator, "ERROR", &[100, 101]).is_empty());
+        assert_eq!(fired_at(&mut evaluator, "INFO", &[102, 103]), [103]);
+    }
+
+    #[test]
+    fn prune_drops_items_that_left_the_window() {
+        let mut items: VecDeque<u64> = VecDeque::from([0, 10, 20, 25]);
+
+        prune(&mut items, 30, 20, |&t| t);
+
+        assert_eq!(items, [20, 25]);
+    }
+
+    #[test]
+    fn rejects_invalid_configurations() {
+        let compile = |config: serde_json::Value| {
+            Condition::compile(&serde_json::from_value(config).unwrap(), 0).map(|_| ())
+        };
+
+        assert!(compile(json!({ "name": "a", "kind": "threshold", "count": 1, "window": "0s" })).is_err());
+        assert!(compile(json!({ "name": "b", "kind": "absence", "within": "soon" })).is_err());
+        assert_eq!(
+            compile(json!({ "name": "c", "kind": "rate", "window": "10m", "baseline": "1m", "factor": 2.0 })),
+            Err("baseline must be at least as long as window".to_string())
+        );
+    }
+}
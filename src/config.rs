@@ -1,4 +1,68 @@
-use std::path::PathBuf;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::api::ApiConfig;
+use crate::backup::{BackupConfig, BackupSchedule, EncryptionConfig};
+use crate::conditions::ConditionConfig;
+use crate::notifier::{
+    ChannelConfig, IncidentPolicy, ReportDeliveryConfig, RouteConfig, Silence, TelegramBotConfig,
+};
+
+/// Default location of the optional JSON configuration file.
+pub const DEFAULT_CONFIG_PATH: &str = "./logsync.json";
+
+/// Settings read from the JSON configuration file (`LOGSYNC_CONFIG` or `./logsync.json`).
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AppConfig {
+    #[serde(default)]
+    pub channels: Vec<ChannelConfig>,
+    #[serde(default)]
+    pub routes: Vec<RouteConfig>,
+    /// Destinations for entries that match no route.
+    #[serde(default)]
+    pub default_route: Vec<String>,
+    #[serde(default)]
+    pub reports: ReportDeliveryConfig,
+    #[serde(default)]
+    pub telegram_bot: Option<TelegramBotConfig>,
+    /// Incident tracking; alerts are sent once per fingerprint until resolved.
+    #[serde(default)]
+    pub incidents: Option<IncidentPolicy>,
+    #[serde(default)]
+    pub api: Option<ApiConfig>,
+    /// Maintenance windows; runtime silences are kept separately in the silences file.
+    #[serde(default)]
+    pub silences: Vec<Silence>,
+    /// Threshold, rate, absence and ratio conditions over all monitored lines.
+    #[serde(default)]
+    pub conditions: Vec<ConditionConfig>,
+    /// Where the filtered log file is uploaded; empty means local only
+    /// (or Drive when `client_secret.json` exists).
+    #[serde(default)]
+    pub backups: Vec<BackupConfig>,
+    #[serde(default)]
+    pub backup_schedule: BackupSchedule,
+    /// Encrypts backups before they leave this machine.
+    #[serde(default)]
+    pub backup_encryption: Option<EncryptionConfig>,
+}
+
+impl AppConfig {
+    /// Loads the configuration file, returning the defaults when it does not exist.
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        let path = std::env::var("LOGSYNC_CONFIG").unwrap_or_else(|_| DEFAULT_CONFIG_PATH.to_string());
+        if !Path::new(&path).exists() {
+            return Ok(Self::default());
+        }
+        Self::from_file(&path)
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid configuration in {}: {}", path, e).into())
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct LogMonitorConfig {
@@ -36,4 +100,95 @@ impl LogMonitorConfig {
         self.monitored_file = path;
         self
     }
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Clone)]
+pub struct DigestConfig {
+    pub enabled: bool,
+    pub window_secs: u64,
+    pub max_entries: usize,
+}
+
+impl Default for DigestConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_secs: 300,
+            max_entries: 50,
+        }
+    }
+}
+
+impl DigestConfig {
+    /// Reads `LOGSYNC_DIGEST`, `LOGSYNC_DIGEST_WINDOW_SECS` and
+    /// `LOGSYNC_DIGEST_MAX_ENTRIES`, falling back to the defaults.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            enabled: env_flag("LOGSYNC_DIGEST").unwrap_or(defaults.enabled),
+            window_secs: env_parse("LOGSYNC_DIGEST_WINDOW_SECS").unwrap_or(defaults.window_secs),
+            max_entries: env_parse("LOGSYNC_DIGEST_MAX_ENTRIES").unwrap_or(defaults.max_entries),
+        }
+    }
+
+    pub fn with_window_secs(mut self, secs: u64) -> Self {
+        self.window_secs = secs;
+        self
+    }
+
+    pub fn with_max_entries(mut self, max: usize) -> Self {
+        self.max_entries = max;
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OutboxConfig {
+    pub path: PathBuf,
+    pub max_attempts: u32,
+    pub base_backoff_secs: u64,
+    pub max_backoff_secs: u64,
+}
+
+impl Default for OutboxConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("./outbox.json"),
+            max_attempts: 10,
+            base_backoff_secs: 5,
+            max_backoff_secs: 3600,
+        }
+    }
+}
+
+impl OutboxConfig {
+    /// Reads `LOGSYNC_OUTBOX_PATH`, `LOGSYNC_OUTBOX_MAX_ATTEMPTS`,
+    /// `LOGSYNC_OUTBOX_BASE_BACKOFF_SECS` and `LOGSYNC_OUTBOX_MAX_BACKOFF_SECS`.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            path: std::env::var("LOGSYNC_OUTBOX_PATH").map(PathBuf::from).unwrap_or(defaults.path),
+            max_attempts: env_parse("LOGSYNC_OUTBOX_MAX_ATTEMPTS").unwrap_or(defaults.max_attempts),
+            base_backoff_secs: env_parse("LOGSYNC_OUTBOX_BASE_BACKOFF_SECS")
+                .unwrap_or(defaults.base_backoff_secs),
+            max_backoff_secs: env_parse("LOGSYNC_OUTBOX_MAX_BACKOFF_SECS")
+                .unwrap_or(defaults.max_backoff_secs),
+        }
+    }
+
+    /// Delay before the next attempt after `attempts` failed ones, doubling each time.
+    pub fn backoff_secs(&self, attempts: u32) -> u64 {
+        let factor = 1u64.checked_shl(attempts.saturating_sub(1)).unwrap_or(u64::MAX);
+        self.base_backoff_secs.saturating_mul(factor).min(self.max_backoff_secs)
+    }
+}
+
+pub fn env_flag(key: &str) -> Option<bool> {
+    std::env::var(key)
+        .ok()
+        .map(|v| matches!(v.trim().to_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+}
+
+pub fn env_parse<T: std::str::FromStr>(key: &str) -> Option<T> {
+    std::env::var(key).ok().and_then(|v| v.trim().parse().ok())
+}
@@ -0,0 +1,199 @@
+use chrono::{DateTime, Datelike, Duration, Local, TimeZone, Timelike};
+use std::str::FromStr;
+
+/// A five-field cron expression (`minute hour day-of-month month day-of-week`)
+/// supporting `*`, lists (`1,15`), ranges (`1-5`) and steps (`*/15`).
+///
+/// Days of the week run from 0 (Sunday) to 6, with 7 also meaning Sunday.
+/// As in cron, when both day fields are restricted a time matches if either does.
+#[derive(Debug, Clone)]
+pub struct CronSchedule {
+    minutes: Vec<bool>,
+    hours: Vec<bool>,
+    days_of_month: Vec<bool>,
+    months: Vec<bool>,
+    days_of_week: Vec<bool>,
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl FromStr for CronSchedule {
+    type Err = String;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields.as_slice() else {
+            return Err(format!(
+                "Invalid cron expression '{}': expected 5 fields (minute hour day month weekday)",
+                expression
+            ));
+        };
+
+        let mut days_of_week = parse_field(day_of_week, 0, 7)?;
+        if days_of_week[7] {
+            days_of_week[0] = true;
+        }
+        days_of_week.truncate(7);
+
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days_of_month: parse_field(day_of_month, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            days_of_week,
+            any_day_of_month: *day_of_month == "*",
+            any_day_of_week: *day_of_week == "*",
+        })
+    }
+}
+
+impl CronSchedule {
+    /// Whether the minute containing `time` is one the expression fires on.
+    pub fn matches<Tz: TimeZone>(&self, time: &DateTime<Tz>) -> bool {
+        let day_of_month = self.days_of_month[time.day() as usize];
+        let day_of_week = self.days_of_week[time.weekday().num_days_from_sunday() as usize];
+        let day = match (self.any_day_of_month, self.any_day_of_week) {
+            (true, true) => true,
+            (true, false) => day_of_week,
+            (false, true) => day_of_month,
+            (false, false) => day_of_month || day_of_week,
+        };
+
+        day && self.minutes[time.minute() as usize]
+            && self.hours[time.hour() as usize]
+            && self.months[time.month() as usize]
+    }
+
+    /// Whether `time` falls within `duration_secs` after a firing, in local time.
+    pub fn is_within(&self, time: DateTime<Local>, duration_secs: u64) -> bool {
+        let start = time
+            .with_second(0)
+            .and_then(|t| t.with_nanosecond(0))
+            .unwrap_or(time);
+        let minutes = duration_secs.div_ceil(60) as i64;
+        (0..minutes)
+            .map(|offset| start - Duration::minutes(offset))
+            .any(|candidate| self.matches(&candidate) && (time - candidate).num_seconds() < duration_secs as i64)
+    }
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> Result<Vec<bool>, String> {
+    let mut allowed = vec![false; max as usize + 1];
+    let invalid = || format!("Invalid cron field '{}' (allowed {}-{})", field, min, max);
+
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(invalid());
+        }
+
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (
+                start.parse().map_err(|_| invalid())?,
+                end.parse().map_err(|_| invalid())?,
+            )
+        } else {
+            let value: u32 = range.parse().map_err(|_| invalid())?;
+            // `5/10` means every 10 starting at 5
+            (value, if part.contains('/') { max } else { value })
+        };
+        if start < min || end > max || start > end {
+            return Err(invalid());
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            allowed[value as usize] = true;
+        }
+    }
+
+    Ok(allowed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(allowed: &[bool]) -> Vec<u32> {
+        (0..allowed.len() as u32).filter(|&v| allowed[v as usize]).collect()
+    }
+
+    #[test]
+    fn parses_fields() {
+        let cases: &[(&str, u32, u32, &[u32])] = &[
+            ("5", 0, 59, &[5]),
+            ("1,15", 1, 31, &[1, 15]),
+            ("1-5", 0, 7, &[1, 2, 3, 4, 5]),
+            ("*/15", 0, 59, &[0, 15, 30, 45]),
+            ("10-20/5", 0, 59, &[10, 15, 20]),
+            ("5/20", 0, 59, &[5, 25, 45]),
+            ("*/5", 1, 12, &[1, 6, 11]),
+            ("0,30-31", 0, 59, &[0, 30, 31]),
+        ];
+        for (field, min, max, expected) in cases {
+            assert_eq!(values(&parse_field(field, *min, *max).unwrap()), *expected, "{}", field);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_fields() {
+        let cases = [
+            ("60", 0, 59),
+            ("0", 1, 31),
+            ("5-1", 0, 59),
+            ("*/0", 0, 59),
+            ("a", 0, 59),
+            ("1-", 0, 59),
+            ("", 0, 59),
+        ];
+        for (field, min, max) in cases {
+            assert_eq!(
+                parse_field(field, min, max).unwrap_err(),
+                format!("Invalid cron field '{}' (allowed {}-{})", field, min, max)
+            );
+        }
+        assert!("0 2 * *".parse::<CronSchedule>().is_err());
+    }
+
+    #[test]
+    fn seven_is_also_sunday() {
+        // 2024-01-07 was a Sunday, 2024-01-08 a Monday
+        let sunday = Local.with_ymd_and_hms(2024, 1, 7, 2, 0, 0).unwrap();
+        let monday = Local.with_ymd_and_hms(2024, 1, 8, 2, 0, 0).unwrap();
+
+        for expression in ["0 2 * * 7", "0 2 * * 0", "0 2 * * 6-7"] {
+            let schedule: CronSchedule = expression.parse().unwrap();
+            assert!(schedule.matches(&sunday), "{}", expression);
+            assert!(!schedule.matches(&monday), "{}", expression);
+        }
+        assert!("0 2 * * 8".parse::<CronSchedule>().is_err());
+    }
+
+    #[test]
+    fn either_day_field_matches_when_both_are_restricted() {
+        let schedule: CronSchedule = "0 0 1 * 1".parse().unwrap();
+
+        assert!(schedule.matches(&Local.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap()));
+        assert!(schedule.matches(&Local.with_ymd_and_hms(2024, 1, 8, 0, 0, 0).unwrap()));
+        assert!(!schedule.matches(&Local.with_ymd_and_hms(2024, 1, 9, 0, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn is_within_a_window_after_each_firing() {
+        let schedule: CronSchedule = "30 2 * * *".parse().unwrap();
+        let at = |h, m, s| Local.with_ymd_and_hms(2024, 1, 10, h, m, s).unwrap();
+
+        assert!(!schedule.is_within(at(2, 29, 59), 3600));
+        assert!(schedule.is_within(at(2, 30, 0), 3600));
+        assert!(schedule.is_within(at(3, 29, 59), 3600));
+        assert!(!schedule.is_within(at(3, 30, 0), 3600));
+
+        // Windows shorter than a minute end mid-minute
+        assert!(schedule.is_within(at(2, 30, 44), 45));
+        assert!(!schedule.is_within(at(2, 30, 45), 45));
+    }
+}
@@ -2,44 +2,347 @@ use google_drive3::DriveHub;
 use hyper_tls::HttpsConnector;
 use hyper_util::client::legacy::connect::HttpConnector;
 
-use google_drive3::api::File;
+use google_drive3::api::{File, FileList};
 
+use reqwest::{Client, StatusCode};
+use serde_json::json;
+use std::io::SeekFrom;
 use std::path::Path;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
 
-use crate::utils::{ensure_file_exists, read_file_to_buffer};
+use crate::auth::DRIVE_SCOPE;
 
-// Function that uploads the log file to Google Drive
-pub async fn upload_file(
+// Endpoint for media uploads (resumable sessions)
+const UPLOAD_URL: &str = "https://www.googleapis.com/upload/drive/v3/files";
+
+// Endpoint for metadata and downloads
+const FILES_URL: &str = "https://www.googleapis.com/drive/v3/files";
+
+// Drive requires every chunk except the last to be a multiple of 256 KiB
+const CHUNK_SIZE: u64 = 32 * 256 * 1024;
+
+// Consecutive failed attempts without progress before giving up
+const MAX_ATTEMPTS: u32 = 8;
+
+const MAX_BACKOFF_SECS: u64 = 64;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+pub type UploadError = Box<dyn std::error::Error + Send + Sync>;
+
+/// The Drive file a resumable upload writes to.
+pub enum UploadTarget<'a> {
+    /// A new file, optionally inside a folder.
+    New { name: &'a str, folder_id: Option<&'a str> },
+    /// New content for an existing file, keeping its metadata.
+    Existing { file_id: &'a str },
+}
+
+// Outcome of one request in an upload session
+enum Step {
+    Done(Box<File>),
+    // Drive has stored everything before this offset
+    Continue(u64),
+}
+
+enum Failure {
+    // Network error, 429 or 5xx: back off and resume
+    Transient(String),
+    Fatal(UploadError),
+}
+
+// Function that uploads the log file to Google Drive in chunks, resuming after
+// transient failures. Returns `None` if an existing target was deleted or trashed.
+pub async fn upload_resumable(
     hub: &DriveHub<HttpsConnector<HttpConnector>>,
-    file_path: &str,
-) -> Result<File, Box<dyn std::error::Error>> {
-    // Ensure the file exists
-    ensure_file_exists(file_path)?;
-
-    // Read the file content into a buffer
-    let buffer = read_file_to_buffer(file_path)?;
-    let cursor = std::io::Cursor::new(buffer);
+    file_path: &Path,
+    target: UploadTarget<'_>,
+    on_progress: impl Fn(u64, u64),
+) -> Result<Option<File>, UploadError> {
+    let mut file = tokio::fs::File::open(file_path).await?;
+    let total = file.metadata().await?.len();
+    let client = Client::new();
+
+    let Some(session) = start_session(hub, &client, file_path, &target, total).await? else {
+        return Ok(None);
+    };
+
+    let mut offset = 0;
+    let mut failures = 0;
+    // After a failure, ask Drive how much it received before sending more
+    let mut resync = false;
+    loop {
+        let step = if resync || (total > 0 && offset >= total) {
+            query_status(hub, &client, &session, total).await
+        } else {
+            on_progress(offset, total);
+            send_chunk(hub, &client, &session, &mut file, offset, total).await
+        };
+
+        match step {
+            Ok(Step::Done(uploaded)) => {
+                on_progress(total, total);
+                return Ok((uploaded.trashed != Some(true)).then_some(*uploaded));
+            }
+            Ok(Step::Continue(next)) => {
+                if next > offset {
+                    failures = 0;
+                }
+                offset = next;
+                resync = false;
+            }
+            Err(Failure::Transient(reason)) => {
+                failures += 1;
+                if failures >= MAX_ATTEMPTS {
+                    return Err(format!("giving up after {} attempts: {}", failures, reason).into());
+                }
+                let delay = backoff(failures);
+                eprintln!("Drive upload interrupted ({}); retrying in {}s", reason, delay.as_secs());
+                tokio::time::sleep(delay).await;
+                resync = true;
+            }
+            Err(Failure::Fatal(e)) => return Err(e),
+        }
+    }
+}
+
+// Opens an upload session and returns its URL, retrying transient failures
+async fn start_session(
+    hub: &DriveHub<HttpsConnector<HttpConnector>>,
+    client: &Client,
+    file_path: &Path,
+    target: &UploadTarget<'_>,
+    total: u64,
+) -> Result<Option<String>, UploadError> {
     let mime_type = mime_guess::from_path(file_path).first_or_octet_stream();
+    let params = "uploadType=resumable&supportsAllDrives=true&fields=id,trashed";
+
+    let mut failures = 0;
+    loop {
+        let token = access_token(hub).await?;
+        let request = match target {
+            UploadTarget::New { name, folder_id } => {
+                let mut metadata = json!({ "name": name, "mimeType": mime_type.to_string() });
+                if let Some(folder_id) = folder_id {
+                    metadata["parents"] = json!([folder_id]);
+                }
+                client.post(format!("{}?{}", UPLOAD_URL, params)).json(&metadata)
+            }
+            UploadTarget::Existing { file_id } => client
+                .patch(format!("{}/{}?{}", UPLOAD_URL, file_id, params))
+                .json(&json!({})),
+        };
+
+        let result = request
+            .bearer_auth(token)
+            .header("X-Upload-Content-Type", mime_type.to_string())
+            .header("X-Upload-Content-Length", total)
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await;
 
-    // Prepare metadata for the Google Drive file
-    let drive_file = File {
-        name: Some(
-            Path::new(file_path)
-                .file_name()
-                .unwrap()
-                .to_string_lossy()
-                .to_string(),
-        ),
-        mime_type: Some(mime_type.to_string()),
-        ..Default::default()
+        let reason = match result {
+            Ok(response) if response.status().is_success() => {
+                let location = response
+                    .headers()
+                    .get(reqwest::header::LOCATION)
+                    .and_then(|value| value.to_str().ok())
+                    .ok_or("Drive did not return an upload session URL")?;
+                return Ok(Some(location.to_string()));
+            }
+            Ok(response) if response.status() == StatusCode::NOT_FOUND => {
+                if let UploadTarget::Existing { .. } = target {
+                    return Ok(None);
+                }
+                return Err(http_error(response).await);
+            }
+            Ok(response) if is_transient(response.status()) => format!("HTTP {}", response.status()),
+            Ok(response) => return Err(http_error(response).await),
+            Err(e) => e.to_string(),
+        };
+
+        failures += 1;
+        if failures >= MAX_ATTEMPTS {
+            return Err(format!("giving up after {} attempts: {}", failures, reason).into());
+        }
+        let delay = backoff(failures);
+        eprintln!("Cannot start Drive upload ({}); retrying in {}s", reason, delay.as_secs());
+        tokio::time::sleep(delay).await;
+    }
+}
+
+// Sends the chunk starting at `offset`, read from disk so large files are never fully in memory
+async fn send_chunk(
+    hub: &DriveHub<HttpsConnector<HttpConnector>>,
+    client: &Client,
+    session: &str,
+    file: &mut tokio::fs::File,
+    offset: u64,
+    total: u64,
+) -> Result<Step, Failure> {
+    let length = CHUNK_SIZE.min(total - offset);
+    let mut chunk = Vec::with_capacity(length as usize);
+    let read = async {
+        file.seek(SeekFrom::Start(offset)).await?;
+        (&mut *file).take(length).read_to_end(&mut chunk).await
+    };
+    if let Err(e) = read.await {
+        return Err(Failure::Fatal(e.into()));
+    }
+    if chunk.len() as u64 != length {
+        return Err(Failure::Fatal("file changed during upload".into()));
+    }
+
+    let range = if total == 0 {
+        "bytes */0".to_string()
+    } else {
+        format!("bytes {}-{}/{}", offset, offset + length - 1, total)
+    };
+    let token = access_token(hub).await.map_err(Failure::Fatal)?;
+    let result = client
+        .put(session)
+        .bearer_auth(token)
+        .header(reqwest::header::CONTENT_RANGE, range)
+        .body(chunk)
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await;
+    read_step(result).await
+}
+
+// Asks Drive how many bytes of the session it has stored
+async fn query_status(
+    hub: &DriveHub<HttpsConnector<HttpConnector>>,
+    client: &Client,
+    session: &str,
+    total: u64,
+) -> Result<Step, Failure> {
+    let token = access_token(hub).await.map_err(Failure::Fatal)?;
+    let result = client
+        .put(session)
+        .bearer_auth(token)
+        .header(reqwest::header::CONTENT_RANGE, format!("bytes */{}", total))
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await;
+    read_step(result).await
+}
+
+async fn read_step(result: reqwest::Result<reqwest::Response>) -> Result<Step, Failure> {
+    let response = match result {
+        Ok(response) => response,
+        Err(e) => return Err(Failure::Transient(e.to_string())),
     };
 
-    // Upload the file to Google Drive
-    let (_, uploaded_file) = hub
-        .files()
-        .create(drive_file)
-        .upload(cursor, mime_type)
+    let status = response.status();
+    if status.is_success() {
+        let file = response.json().await.map_err(|e| Failure::Fatal(e.into()))?;
+        return Ok(Step::Done(file));
+    }
+    // 308 Resume Incomplete; `Range: bytes=0-N` is absent when nothing was stored yet
+    if status == StatusCode::PERMANENT_REDIRECT {
+        let stored = response
+            .headers()
+            .get(reqwest::header::RANGE)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit('-').next())
+            .and_then(|last| last.parse::<u64>().ok())
+            .map_or(0, |last| last + 1);
+        return Ok(Step::Continue(stored));
+    }
+    if is_transient(status) {
+        return Err(Failure::Transient(format!("HTTP {}", status)));
+    }
+    Err(Failure::Fatal(http_error(response).await))
+}
+
+/// Files named `name` (any name if `None`) in the folder (or "My Drive" root),
+/// most recently modified first. Trashed files are left out.
+pub async fn list_files(
+    hub: &DriveHub<HttpsConnector<HttpConnector>>,
+    folder_id: Option<&str>,
+    name: Option<&str>,
+) -> Result<Vec<File>, UploadError> {
+    let mut query = format!("'{}' in parents and trashed = false", escape_query(folder_id.unwrap_or("root")));
+    if let Some(name) = name {
+        query.push_str(&format!(" and name = '{}'", escape_query(name)));
+    }
+
+    let client = Client::new();
+    let mut files = Vec::new();
+    let mut page_token: Option<String> = None;
+    loop {
+        let mut params = vec![
+            ("q", query.clone()),
+            ("fields", "nextPageToken,files(id,name,size,modifiedTime)".to_string()),
+            ("orderBy", "modifiedTime desc".to_string()),
+            ("pageSize", "1000".to_string()),
+            ("supportsAllDrives", "true".to_string()),
+            ("includeItemsFromAllDrives", "true".to_string()),
+        ];
+        if let Some(token) = page_token.take() {
+            params.push(("pageToken", token));
+        }
+        let response = client
+            .get(FILES_URL)
+            .bearer_auth(access_token(hub).await?)
+            .query(&params)
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(http_error(response).await);
+        }
+        let list: FileList = response.json().await?;
+        files.extend(list.files.unwrap_or_default());
+        match list.next_page_token {
+            Some(token) => page_token = Some(token),
+            None => return Ok(files),
+        }
+    }
+}
+
+/// Downloads the content of a Drive file to `target`.
+pub async fn download_file(
+    hub: &DriveHub<HttpsConnector<HttpConnector>>,
+    file_id: &str,
+    target: &Path,
+) -> Result<(), UploadError> {
+    let response = Client::new()
+        .get(format!("{}/{}", FILES_URL, file_id))
+        .bearer_auth(access_token(hub).await?)
+        .query(&[("alt", "media"), ("supportsAllDrives", "true")])
+        .timeout(REQUEST_TIMEOUT)
+        .send()
         .await?;
+    crate::backup::save_response(response, target).await
+}
+
+// Quotes a value for a Drive search query
+fn escape_query(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+async fn access_token(hub: &DriveHub<HttpsConnector<HttpConnector>>) -> Result<String, UploadError> {
+    hub.auth
+        .get_token(&[DRIVE_SCOPE])
+        .await?
+        .ok_or_else(|| "no access token available for Google Drive".into())
+}
+
+fn is_transient(status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+async fn http_error(response: reqwest::Response) -> UploadError {
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    format!("HTTP {}: {}", status, body).into()
+}
 
-    Ok(uploaded_file)
-}
\ No newline at end of file
+// 1s, 2s, 4s... capped at MAX_BACKOFF_SECS
+fn backoff(failures: u32) -> Duration {
+    let secs = 1u64.checked_shl(failures.saturating_sub(1)).unwrap_or(u64::MAX);
+    Duration::from_secs(secs.min(MAX_BACKOFF_SECS))
+}
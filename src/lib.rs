@@ -1,11 +1,20 @@
 pub mod logger;
 pub mod auth;
+pub mod backup;
 pub mod notifier;
 pub mod utils;
 pub mod viewer;
 pub mod types;
 pub mod driver_uploader;
 pub mod report_generator;
+pub mod report_archive;
+pub mod report_formats;
+pub mod report_charts;
+pub mod report_comparison;
+pub mod conditions;
 pub mod config;
+pub mod cron;
 pub mod parser;
 pub mod processor;
+pub mod api;
+pub mod secrets;
@@ -1,32 +1,52 @@
-use google_drive3::api::File;
-use google_drive3::DriveHub;
-use hyper_tls::HttpsConnector;
-use hyper_util::client::legacy::connect::HttpConnector;
 use inotify::{EventMask, Inotify, WatchMask};
-use serde_json::{json, Value};
+use serde_json::Value;
 use std::collections::HashSet;
-use std::fs::{File as StdFile, OpenOptions};
-use std::io::{BufRead, BufReader};
-use std::io::{BufWriter, Write};
+use std::fs::File as StdFile;
+use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc::Sender;
+use tokio::sync::Notify;
 
 use tokio::time::Duration;
 
-use crate::driver_uploader::upload_file;
-use crate::notifier::{handle_telegram_alert, send_html_report_to_telegram, send_log_to_channel, send_telegram_alert};
-use crate::parser::parse_log_line;
-use crate::types::LogEntry;
+use crate::notifier::{send_log_to_channel, AlertDispatcher};
+use crate::parser::{extract_source, extract_tags, fingerprint, line_level, parse_log_line};
+use crate::types::{DeliveryStatus, LogEntry};
+use crate::utils::{generate_entry_id, now_secs, write_atomically};
 
+/// Serializes read-modify-write cycles on the filtered log file.
+static LOG_FILE_LOCK: Mutex<()> = Mutex::new(());
 
+/// File watched for new log lines.
+const MONITORED_FILE: &str = "./test_log.txt";
+
+/// Signalled when new entries are written to the filtered log file. Status
+/// changes and acknowledgements are not, and go out with the next upload.
+static LOG_FILE_WRITTEN: Notify = Notify::const_new();
+
+/// Completes once new entries have been written to the filtered log file, either
+/// since the previous call or in the future. Meant for a single waiter (the backup scheduler).
+pub async fn log_file_written() {
+    LOG_FILE_WRITTEN.notified().await
+}
 
 async fn process_log_line(
     line: &str,
     log_file_path: &str,
     tx: &Sender<LogEntry>,
     processed_errors: &Arc<Mutex<HashSet<String>>>,
+    dispatcher: &AlertDispatcher,
+    conditions: Option<&Sender<LogEntry>>,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    // O avaliador de condições recebe todas as linhas novas, não só os erros,
+    // inclusive as repetidas (um heartbeat é sempre a mesma linha)
+    if let Some(conditions) = conditions {
+        if conditions.send(observed_entry(line)).await.is_err() {
+            eprintln!("Condition evaluator stopped; line not evaluated");
+        }
+    }
+
     let line_string = line.to_string();
 
     let mut should_process = false;
@@ -42,22 +62,52 @@ async fn process_log_line(
         if let Some(log_json) = parse_log_line(line) {
             println!("Filtered log: {}", log_json);
 
-            if log_json["priority"] == "high" {
+            if log_json["priority"] == "high" || log_json["priority"] == "very high" {
                 let log_entry = create_log_entry(&log_json)?;
-
-                update_log_file(log_file_path, &log_entry)?;
-
-                send_log_to_channel(tx, log_entry.clone()).await?;
-
-                handle_telegram_alert(&log_entry).await?;
+                record_entry(log_entry, log_file_path, tx, dispatcher).await?;
             }
         }
     }
     Ok(())
 }
 
+/// Stores a new entry, shows it in the viewer and dispatches its alert.
+pub async fn record_entry(
+    mut log_entry: LogEntry,
+    log_file_path: &str,
+    tx: &Sender<LogEntry>,
+    dispatcher: &AlertDispatcher,
+) -> Result<(), Box<dyn std::error::Error>> {
+    log_entry.delivery_status = dispatcher.initial_status(&log_entry);
+
+    update_log_file(log_file_path, &log_entry)?;
+
+    send_log_to_channel(tx, log_entry.clone()).await?;
+
+    dispatcher.dispatch(&log_entry).await
+}
+
+/// Describes any monitored line for condition matching; it is never stored.
+fn observed_entry(line: &str) -> LogEntry {
+    let (log_type, priority) = line_level(line);
+    LogEntry {
+        id: String::new(),
+        timestamp: now_secs(),
+        log_type: log_type.to_string(),
+        priority: priority.to_string(),
+        message: line.trim().to_string(),
+        source: extract_source(line),
+        tags: extract_tags(line),
+        fingerprint: String::new(),
+        alert_requested: false,
+        delivery_status: DeliveryStatus::NotRequired,
+        acknowledged_by: None,
+    }
+}
+
 fn create_log_entry(log_json: &Value) -> Result<LogEntry, Box<dyn std::error::Error>> {
-    Ok(LogEntry {
+    let mut entry = LogEntry {
+        id: generate_entry_id(),
         timestamp: log_json["timestamp"].as_u64().ok_or("Invalid timestamp")?,
         log_type: log_json["type"].as_str().ok_or("Invalid type")?.to_string(),
         priority: log_json["priority"]
@@ -68,49 +118,162 @@ fn create_log_entry(log_json: &Value) -> Result<LogEntry, Box<dyn std::error::Er
             .as_str()
             .ok_or("Invalid message")?
             .to_string(),
-        telegram_notification: Some(true),
-    })
+        source: log_json["source"].as_str().map(str::to_string),
+        tags: serde_json::from_value(log_json["tags"].clone()).unwrap_or_default(),
+        fingerprint: String::new(),
+        alert_requested: true,
+        delivery_status: DeliveryStatus::NotRequired,
+        acknowledged_by: None,
+    };
+    entry.fingerprint = fingerprint(&entry.log_type, entry.source.as_deref(), &entry.message);
+    Ok(entry)
 }
 
-fn update_log_file(
-    log_file_path: &str,
-    log_entry: &LogEntry,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let mut logs: Vec<LogEntry> = if Path::new(log_file_path).exists() {
+pub fn read_log_file(log_file_path: &str) -> Result<Vec<LogEntry>, Box<dyn std::error::Error>> {
+    if Path::new(log_file_path).exists() {
         let file = StdFile::open(log_file_path)?;
         let reader = BufReader::new(file);
-        serde_json::from_reader(reader).unwrap_or_else(|_| vec![])
+        Ok(serde_json::from_reader(reader).unwrap_or_else(|_| vec![]))
     } else {
-        Vec::new()
-    };
+        Ok(Vec::new())
+    }
+}
 
-    logs.push(log_entry.clone());
+/// Replaces the file atomically, so readers that do not take `LOG_FILE_LOCK`
+/// (the backup snapshot) never see it half written.
+fn write_log_file(log_file_path: &str, logs: &[LogEntry]) -> Result<(), Box<dyn std::error::Error>> {
+    let content = serde_json::to_vec_pretty(logs).map_err(|e| format!("Error writing to log file: {}", e))?;
+    write_atomically(Path::new(log_file_path), &content)
+        .map_err(|e| format!("Error writing to log file: {}", e))?;
+    Ok(())
+}
 
-    let mut file = OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .create(true)
-        .open(log_file_path)?;
+fn update_log_file(
+    log_file_path: &str,
+    log_entry: &LogEntry,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let _guard = LOG_FILE_LOCK.lock().unwrap();
 
-    serde_json::to_writer_pretty(&mut file, &logs)
-        .map_err(|e| format!("Error writing to log file: {}", e))?;
+    let mut logs = read_log_file(log_file_path)?;
+    logs.push(log_entry.clone());
+    write_log_file(log_file_path, &logs)?;
+    LOG_FILE_WRITTEN.notify_one();
 
     println!("Log entry written successfully.");
     Ok(())
 }
 
+/// Records the alert delivery result for the entry with the given id.
+pub fn update_delivery_status(
+    log_file_path: &str,
+    id: &str,
+    status: &DeliveryStatus,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let _guard = LOG_FILE_LOCK.lock().unwrap();
+
+    let mut logs = read_log_file(log_file_path)?;
+    match logs.iter_mut().find(|entry| entry.id == id) {
+        Some(entry) => entry.delivery_status = status.clone(),
+        None => return Err(format!("Log entry {} not found", id).into()),
+    }
+    write_log_file(log_file_path, &logs)
+}
+
+/// Marks the entry with the given id as acknowledged. Returns `false` when no
+/// such entry exists.
+pub fn acknowledge_entry(
+    log_file_path: &str,
+    id: &str,
+    acknowledged_by: &str,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let _guard = LOG_FILE_LOCK.lock().unwrap();
+
+    let mut logs = read_log_file(log_file_path)?;
+    let Some(entry) = logs.iter_mut().find(|entry| entry.id == id) else {
+        return Ok(false);
+    };
+    entry.acknowledged_by = Some(acknowledged_by.to_string());
+    write_log_file(log_file_path, &logs)?;
+    Ok(true)
+}
 
+/// Adds the entries that are not stored yet (same id, or same time, source and
+/// message for entries without one) and keeps the file in time order. Returns
+/// how many were added.
+pub fn merge_entries(log_file_path: &str, entries: Vec<LogEntry>) -> Result<usize, Box<dyn std::error::Error>> {
+    let _guard = LOG_FILE_LOCK.lock().unwrap();
+
+    // Unlike `read_log_file`, an unreadable file is an error: it would be replaced by the merge
+    let mut logs: Vec<LogEntry> = match std::fs::read(log_file_path) {
+        Ok(content) if !content.iter().all(u8::is_ascii_whitespace) => serde_json::from_slice(&content)
+            .map_err(|e| format!("{} is not a valid filtered log file: {}", log_file_path, e))?,
+        Ok(_) => Vec::new(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut known: HashSet<String> = logs.iter().map(entry_key).collect();
+    let before = logs.len();
+    for entry in entries {
+        if known.insert(entry_key(&entry)) {
+            logs.push(entry);
+        }
+    }
+    let added = logs.len() - before;
+    if added > 0 {
+        logs.sort_by_key(|entry| entry.timestamp);
+        write_log_file(log_file_path, &logs)?;
+        LOG_FILE_WRITTEN.notify_one();
+    }
+    Ok(added)
+}
+
+fn entry_key(entry: &LogEntry) -> String {
+    if entry.id.is_empty() {
+        format!("{}\u{0}{}\u{0}{}", entry.timestamp, entry.source.as_deref().unwrap_or(""), entry.message)
+    } else {
+        entry.id.clone()
+    }
+}
+
+/// Reads the complete lines appended to `path` since `offset` and moves it past
+/// them; a trailing partial line is left for the next read. Starts over from
+/// the beginning when the file shrank (truncated or rotated).
+fn read_new_lines(path: &str, offset: &mut u64) -> std::io::Result<Vec<String>> {
+    let mut file = StdFile::open(path)?;
+    let len = file.metadata()?.len();
+    if len < *offset {
+        *offset = 0;
+    }
+    file.seek(SeekFrom::Start(*offset))?;
+    let mut content = Vec::new();
+    file.take(len - *offset).read_to_end(&mut content)?;
+
+    let complete = content.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+    *offset += complete as u64;
+    Ok(String::from_utf8_lossy(&content[..complete]).lines().map(str::to_string).collect())
+}
 
 pub async fn monitor_logs_and_create_json(
     log_file_path: &str,
-    hub: &DriveHub<HttpsConnector<HttpConnector>>,
     tx: Sender<LogEntry>,
     processed_errors: Arc<Mutex<HashSet<String>>>,
+    dispatcher: AlertDispatcher,
+    conditions: Option<Sender<LogEntry>>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut inotify = Inotify::init()?;
-    inotify.add_watch("./test_log.txt", WatchMask::MODIFY)?;
+    inotify.add_watch(MONITORED_FILE, WatchMask::MODIFY)?;
+
+    println!("Monitoring {} for changes...", MONITORED_FILE);
 
-    println!("Monitoring ./test_log.txt for changes...");
+    // As linhas já existentes são registradas, mas não entram nas condições:
+    // todas receberiam o horário atual e disparariam limites falsamente
+    let mut offset = 0;
+    for line in read_new_lines(MONITORED_FILE, &mut offset)? {
+        if let Err(e) = process_log_line(&line, log_file_path, &tx, &processed_errors, &dispatcher, None).await {
+            eprintln!("Error processing log line: {}", e);
+        }
+    }
 
     loop {
         let mut buffer = [0; 1024];
@@ -118,23 +281,16 @@ pub async fn monitor_logs_and_create_json(
 
         for event in events {
             if event.mask.contains(EventMask::MODIFY) {
-                let file = StdFile::open("./test_log.txt")?;
-                let reader = BufReader::new(file);
-
-                for line in reader.lines() {
-                    if let Ok(line) = line {
-                        println!("Processing line: {}", line);
-
-                        if let Err(e) =
-                            process_log_line(&line, log_file_path, &tx, &processed_errors).await
-                        {
-                            eprintln!("Error processing log line: {}", e);
-                        }
-                    }
-                }
+                // Lê apenas o que foi acrescentado desde a última leitura
+                for line in read_new_lines(MONITORED_FILE, &mut offset)? {
+                    println!("Processing line: {}", line);
 
-                if let Err(e) = upload_file(hub, log_file_path).await {
-                    eprintln!("Erro ao enviar arquivo: {}", e);
+                    if let Err(e) =
+                        process_log_line(&line, log_file_path, &tx, &processed_errors, &dispatcher, conditions.as_ref())
+                            .await
+                    {
+                        eprintln!("Error processing log line: {}", e);
+                    }
                 }
             }
         }
@@ -142,3 +298,32 @@ pub async fn monitor_logs_and_create_json(
         tokio::time::sleep(Duration::from_millis(100)).await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    #[test]
+    fn reads_only_appended_complete_lines() {
+        let path = std::env::temp_dir().join(format!("logsync-monitored-{}.txt", std::process::id()));
+        let path_str = path.to_str().unwrap();
+        std::fs::write(&path, "first\nheartbeat\n").unwrap();
+        let mut offset = 0;
+        assert_eq!(read_new_lines(path_str, &mut offset).unwrap(), vec!["first", "heartbeat"]);
+
+        // The same line again is a new line; a partial one waits for its end
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"heartbeat\npart").unwrap();
+        assert_eq!(read_new_lines(path_str, &mut offset).unwrap(), vec!["heartbeat"]);
+        assert!(read_new_lines(path_str, &mut offset).unwrap().is_empty());
+        file.write_all(b"ial\n").unwrap();
+        assert_eq!(read_new_lines(path_str, &mut offset).unwrap(), vec!["partial"]);
+
+        // A truncated file is read from the start again
+        std::fs::write(&path, "rotated\n").unwrap();
+        assert_eq!(read_new_lines(path_str, &mut offset).unwrap(), vec!["rotated"]);
+        let _ = std::fs::remove_file(path);
+    }
+}
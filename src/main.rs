@@ -1,33 +1,597 @@
-mod auth;
-mod logger;
-mod notifier;
-mod utils;
-mod viewer;
-mod driver_uploader;
-mod types;
-mod config;
-mod parser;
-mod processor;
-
 use std::{collections::HashSet, sync::{Arc, Mutex}};
 
-use logger::monitor_logs_and_create_json;
+use logsync::conditions::{run_condition_evaluator, ConditionEvaluator};
+use logsync::config::{AppConfig, DigestConfig, OutboxConfig};
+use logsync::logger::{merge_entries, monitor_logs_and_create_json};
+use logsync::notifier::reports::{deliver_report, ReportSchedule};
+use logsync::report_archive::ReportArchive;
+use logsync::report_comparison::compare_with_previous;
+use logsync::report_formats::{render, ReportFormat};
+use logsync::report_generator::{Report, ReportPeriod};
+use logsync::api::{self, ApiState};
+use logsync::notifier::{
+    build_notifiers, load_notifiers, run_incident_worker, run_outbox_worker, spawn_report_worker, telegram_config_for,
+    AlertDispatcher, DeliveryReporter, IncidentTracker, MuteList, Outbox, Router, Silence, SilenceList,
+    TelegramBot,
+};
+use logsync::auth;
+use logsync::backup::restore::{fetch_entries, list_backups, select_backup};
+use logsync::backup::{build_sinks, effective_configs, encryption, manifest, BackupEncryptor, BackupKind, BackupScheduler};
+use logsync::{secrets, utils, viewer};
+use tokio::signal::unix::{signal, SignalKind};
 use tokio::sync::mpsc;
 
+const USAGE: &str = "Usage: logsync [COMMAND]
+
+Commands:
+  (none)         Monitor logs and show them in the interactive viewer
+  send-report    Generate a report over all stored entries and send it now
+                 Options: --hourly | --daily | --weekly (the last complete period), --from <time>, --until <time>
+  report         Print a report to the terminal instead of sending it
+                 Options: --format html|markdown|csv|text|json (default: text), and the send-report options
+  reports        List past reports from the report index
+  check-notifiers Validate every notification channel and send it a test message
+  incidents      List incidents of the running instance (requires \"api\" in logsync.json)
+  ack <key> [by] Acknowledge an entry id or incident fingerprint on the running instance
+  silence list   List silences and whether they are active
+  silence add    Add a silence, e.g. silence add --source postgres --for 2h --comment \"DB maintenance\"
+                 Matchers: --source, --type, --priority, --tag, --regex (repeatable except --regex)
+                 Window: --for <duration> | --until <time>, --from <time>, --schedule <cron> --duration <duration>
+  silence rm <id> Remove a silence
+  auth login [name] Authorize the Drive backups and cache their tokens (all, or the one named)
+  decrypt <file> Decrypt a backup downloaded from a destination
+                 Options: --identity <file> (default: LOGSYNC_BACKUP_IDENTITY), --output <file>
+  restore list   List the backups at each destination, with their host, day, upload time and period
+  restore <file> Download a backup, verify it and merge its entries into filtered_logs.json
+                 Instead of <file>: --host <host> (default: this machine) and/or --date <YYYY-MM-DD>
+                 pick that host's backup of that day, or its latest one
+                 Options: --from <destination>, --identity <file> (for encrypted backups)";
+
+// Caminho do arquivo JSON onde os logs serão salvos
+const LOG_FILE_PATH: &str = "filtered_logs.json";
+
+// Tempo máximo que o envio final dos backups pode atrasar o encerramento
+const BACKUP_FLUSH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // O .env guarda segredos (tokens do Telegram): recusa se outros usuários puderem lê-lo,
+    // a menos que LOGSYNC_ALLOW_INSECURE_ENV=1 (só no ambiente, para instalações em transição)
+    if std::path::Path::new(".env").exists() {
+        if let Err(e) = secrets::check_permissions(std::path::Path::new(".env")) {
+            if logsync::config::env_flag(secrets::ALLOW_INSECURE_ENV_VAR).unwrap_or(false) {
+                eprintln!("Warning: {} ({} is set)", e, secrets::ALLOW_INSECURE_ENV_VAR);
+            } else {
+                return Err(format!("{}, or set {}=1 to start anyway", e, secrets::ALLOW_INSECURE_ENV_VAR).into());
+            }
+        }
+    }
+    dotenv::dotenv().ok();
+
+    // Carrega o arquivo de configuração opcional (logsync.json)
+    let app_config = AppConfig::load()?;
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        None => run_monitor(app_config).await,
+        Some("send-report") => send_report(&app_config, &args[1..]).await,
+        Some("report") => print_report(&app_config, &args[1..]),
+        Some("reports") => list_reports(&app_config),
+        Some("check-notifiers") => check_notifiers(&app_config).await,
+        Some("incidents") => list_incidents(&app_config).await,
+        Some("silence") => silence_command(&app_config, &args[1..]),
+        Some("auth") => match args.get(1).map(String::as_str) {
+            Some("login") => auth_login(&app_config, args.get(2)).await,
+            _ => Err(format!("Unknown auth command\n\n{}", USAGE).into()),
+        },
+        Some("decrypt") => decrypt_backup(&args[1..]),
+        Some("restore") => restore_backup(&app_config, &args[1..]).await,
+        Some("ack") => match args.get(1) {
+            Some(key) => acknowledge(&app_config, key, args.get(2)).await,
+            None => Err(format!("Missing entry id or fingerprint\n\n{}", USAGE).into()),
+        },
+        Some("-h") | Some("--help") | Some("help") => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Some(other) => Err(format!("Unknown command '{}'\n\n{}", other, USAGE).into()),
+    }
+}
+
+async fn send_report(app_config: &AppConfig, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let (label, period) = parse_report_period(args)?;
+    let notifiers = build_notifiers(&app_config.channels).enabled;
+    deliver_report(label, period, &app_config.reports, &notifiers, LOG_FILE_PATH).await;
+    Ok(())
+}
+
+fn print_report(app_config: &AppConfig, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    // --format é tratado aqui; as demais opções definem o período
+    let mut format = ReportFormat::Text;
+    let mut period_args = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--format" {
+            let value = args.next().ok_or("Missing value for --format")?;
+            format = value.parse()?;
+        } else {
+            period_args.push(arg.clone());
+        }
+    }
+    let (_, period) = parse_report_period(&period_args)?;
+
+    let entries = logsync::logger::read_log_file(LOG_FILE_PATH)?;
+    let mut report = Report::new(&entries, period);
+    compare_with_previous(&mut report, period, &entries, &app_config.reports.comparison);
+    print!("{}", render(&report, format, &app_config.reports.html)?);
+    Ok(())
+}
+
+fn parse_report_period(args: &[String]) -> Result<(&'static str, ReportPeriod), Box<dyn std::error::Error>> {
+    let mut label = "on-demand";
+    let mut period = ReportPeriod::all();
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let schedule = match flag.as_str() {
+            "--hourly" => Some(ReportSchedule::Hourly),
+            "--daily" => Some(ReportSchedule::Daily),
+            "--weekly" => Some(ReportSchedule::Weekly),
+            _ => None,
+        };
+        if let Some(schedule) = schedule {
+            label = schedule.label();
+            period = schedule
+                .previous_period(chrono::Local::now())
+                .ok_or("Cannot compute the report period")?;
+            continue;
+        }
+
+        let value = args.next().ok_or_else(|| format!("Missing value for {}", flag))?;
+        let time = utils::parse_time(value).ok_or_else(|| format!("Invalid time '{}'", value))?;
+        match flag.as_str() {
+            "--from" => period.start = time,
+            "--until" => period.end = time,
+            other => return Err(format!("Unknown option '{}'\n\n{}", other, USAGE).into()),
+        }
+        label = "range";
+    }
+    if period.start >= period.end {
+        return Err("The report period is empty: --from must be before --until".into());
+    }
+    Ok((label, period))
+}
+
+fn list_reports(app_config: &AppConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let archive = ReportArchive::new(app_config.reports.archive.clone());
+    let records = archive.records()?;
+    if records.is_empty() {
+        println!("No reports yet.");
+    }
+    for record in records.iter().rev() {
+        let files: Vec<String> = record
+            .files
+            .iter()
+            .map(|file| archive.directory().join(file).display().to_string())
+            .collect();
+        let delivered_to = if record.delivered_to.is_empty() {
+            "-".to_string()
+        } else {
+            record.delivered_to.join(",")
+        };
+        println!(
+            "{:<10} {} to {} {:>6} entries  {:<20} {}",
+            record.kind,
+            utils::format_timestamp(record.period.start),
+            utils::format_timestamp(record.period.end),
+            record.entries,
+            delivered_to,
+            files.join(" ")
+        );
+    }
+    Ok(())
+}
+
+async fn check_notifiers(app_config: &AppConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let loaded = load_notifiers(&app_config.channels);
+    if loaded.enabled.is_empty() && loaded.disabled.is_empty() {
+        return Err("No notification channels configured (see \"channels\" in logsync.json or TELEGRAM_API_TOKEN in .env)".into());
+    }
+
+    let message = format!("logsync test message from {}", utils::hostname());
+    let mut failed = loaded.disabled.len();
+    for (name, reason) in &loaded.disabled {
+        println!("{:<20} DISABLED  {}", name, reason);
+    }
+    for notifier in &loaded.enabled {
+        match notifier.notify(&message, None).await {
+            Ok(()) => println!("{:<20} OK", notifier.name()),
+            Err(e) => {
+                failed += 1;
+                println!("{:<20} FAILED    {}", notifier.name(), e);
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!("{} of {} channels failed", failed, loaded.enabled.len() + loaded.disabled.len()).into());
+    }
+    Ok(())
+}
+
+fn api_config(app_config: &AppConfig) -> Result<&api::ApiConfig, Box<dyn std::error::Error>> {
+    app_config
+        .api
+        .as_ref()
+        .ok_or_else(|| "The HTTP API is not enabled: add an \"api\" section to logsync.json".into())
+}
+
+async fn list_incidents(app_config: &AppConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let list = api::request(api_config(app_config)?, reqwest::Method::GET, "incidents", None)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let incidents = list.as_array().cloned().unwrap_or_default();
+    if incidents.is_empty() {
+        println!("No incidents.");
+    }
+    for incident in incidents {
+        println!(
+            "{:<12} {:<16} {:>5}x  {}",
+            incident["state"].as_str().unwrap_or_default(),
+            incident["fingerprint"].as_str().unwrap_or_default(),
+            incident["occurrences"],
+            incident["title"].as_str().unwrap_or_default()
+        );
+    }
+    Ok(())
+}
+
+async fn acknowledge(
+    app_config: &AppConfig,
+    key: &str,
+    by: Option<&String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let by = by
+        .cloned()
+        .or_else(|| std::env::var("USER").ok())
+        .unwrap_or_else(|| "cli".to_string());
+    let path = format!("ack/{}", key);
+    api::request(api_config(app_config)?, reqwest::Method::POST, &path, Some(serde_json::json!({ "by": by })))
+        .await
+        .map_err(|e| e.to_string())?;
+    println!("Acknowledged {} ({}).", key, by);
+    Ok(())
+}
+
+fn open_silences(app_config: &AppConfig) -> Result<SilenceList, Box<dyn std::error::Error>> {
+    let path = std::env::var("LOGSYNC_SILENCES_PATH").unwrap_or_else(|_| "./silences.json".to_string());
+    SilenceList::open(std::path::Path::new(&path), &app_config.silences)
+}
+
+fn silence_command(app_config: &AppConfig, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let silences = open_silences(app_config)?;
+
+    match args.first().map(String::as_str) {
+        Some("list") | None => {
+            let list = silences.list();
+            if list.is_empty() {
+                println!("No silences.");
+            }
+            for (silence, active) in list {
+                let state = if active { "active" } else { "inactive" };
+                println!("{:<24} {:<8} {}", silence.id, state, silence.describe());
+            }
+            Ok(())
+        }
+        Some("add") => {
+            let silence = parse_silence(&args[1..])?;
+            let silence = silences.add(silence)?;
+            println!("Added silence {}: {}", silence.id, silence.describe());
+            Ok(())
+        }
+        Some("rm") => {
+            let id = args.get(1).ok_or_else(|| format!("Missing silence id\n\n{}", USAGE))?;
+            if silences.remove(id)? {
+                println!("Removed silence {}.", id);
+                Ok(())
+            } else {
+                Err(format!("No silence with id {}", id).into())
+            }
+        }
+        Some(other) => Err(format!("Unknown silence command '{}'\n\n{}", other, USAGE).into()),
+    }
+}
+
+fn parse_silence(args: &[String]) -> Result<Silence, Box<dyn std::error::Error>> {
+    let mut silence = Silence {
+        created_by: std::env::var("USER").ok(),
+        ..Silence::default()
+    };
+
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .cloned()
+            .ok_or_else(|| format!("Missing value for {}", flag))?;
+        match flag.as_str() {
+            "--source" => silence.matcher.source.push(value),
+            "--type" => silence.matcher.log_type.push(value),
+            "--priority" => silence.matcher.priority.push(value),
+            "--tag" => silence.matcher.tags.push(value),
+            "--regex" => silence.matcher.message_regex = Some(value),
+            "--from" => silence.starts_at = Some(value),
+            "--until" => silence.ends_at = Some(value),
+            "--for" => {
+                let secs = utils::parse_duration_secs(&value).ok_or_else(|| format!("Invalid duration '{}'", value))?;
+                silence.ends_at = Some(utils::format_rfc3339(utils::now_secs().saturating_add(secs)));
+            }
+            "--schedule" => silence.schedule = Some(value),
+            "--duration" => silence.duration = Some(value),
+            "--comment" => silence.comment = Some(value),
+            other => return Err(format!("Unknown option '{}'\n\n{}", other, USAGE).into()),
+        }
+    }
 
+    if silence.matcher.is_empty() {
+        return Err("A silence needs at least one matcher (--source, --type, --priority, --tag or --regex)".into());
+    }
+    Ok(silence)
+}
+
+async fn auth_login(app_config: &AppConfig, name: Option<&String>) -> Result<(), Box<dyn std::error::Error>> {
+    let drives: Vec<_> = effective_configs(&app_config.backups)
+        .into_iter()
+        .filter(|backup| name.is_none_or(|name| &backup.name == name))
+        .filter_map(|backup| match backup.kind {
+            BackupKind::Drive(drive) => Some((backup.name, drive)),
+            _ => None,
+        })
+        .collect();
+    if drives.is_empty() {
+        return Err(match name {
+            Some(name) => format!("No Drive backup named '{}'", name),
+            None => format!(
+                "No Drive backup configured (add one to \"backups\" or place {} in the working directory)",
+                auth::DEFAULT_CLIENT_SECRET_PATH
+            ),
+        }
+        .into());
+    }
+
+    // Cada backup do Drive pode usar credenciais diferentes
+    for (name, drive) in drives {
+        println!("Authorizing Drive backup '{}'...", name);
+        auth::authenticate(&drive.auth, true).await?;
+        match drive.auth.auth {
+            auth::AuthMethod::ServiceAccount => println!("'{}': service account key is valid.", name),
+            _ => println!("'{}': tokens cached in {}.", name, drive.auth.token_cache.display()),
+        }
+    }
+    Ok(())
+}
+
+fn decrypt_backup(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut input = None;
+    let mut identity = None;
+    let mut output = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--identity" | "-i" => identity = Some(args.next().ok_or("Missing value for --identity")?),
+            "--output" | "-o" => output = Some(args.next().ok_or("Missing value for --output")?),
+            _ if input.is_none() => input = Some(arg),
+            other => return Err(format!("Unexpected argument '{}'\n\n{}", other, USAGE).into()),
+        }
+    }
+    let input = std::path::Path::new(input.ok_or_else(|| format!("Missing file to decrypt\n\n{}", USAGE))?);
+
+    // Sem --output, remove a extensão .age (sem sobrescrever um arquivo existente)
+    let output = match output {
+        Some(output) => std::path::PathBuf::from(output),
+        None => {
+            let name = input.to_string_lossy();
+            let output = std::path::PathBuf::from(
+                name.strip_suffix(encryption::ENCRYPTED_SUFFIX)
+                    .ok_or("Cannot derive the output name; pass --output")?,
+            );
+            if output.exists() {
+                return Err(format!("{} already exists; pass --output", output.display()).into());
+            }
+            output
+        }
+    };
+
+    let manifest = manifest::read_manifest(input).map_err(|e| e.to_string())?;
+    let encryption = manifest.as_ref().and_then(|manifest| manifest.encryption.as_ref());
+    if let Some(manifest) = &manifest {
+        if manifest::file_sha256(input)? != manifest.sha256 {
+            return Err(format!("{} does not match the checksum in its manifest; download it again", input.display()).into());
+        }
+    }
+    if let Some(encryption) = encryption {
+        println!(
+            "Encrypted at {} with key {} for {}",
+            encryption.encrypted_at,
+            encryption.key_id.as_deref().unwrap_or("(no key id)"),
+            encryption.recipients.join(", ")
+        );
+    }
+
+    let identities = encryption::load_identities(identity.map(std::path::Path::new)).map_err(|e| e.to_string())?;
+    let hash = encryption::decrypt_file(input, &output, &identities).map_err(|e| match encryption {
+        Some(encryption) => format!(
+            "Cannot decrypt {}: {} (encrypted with key {})",
+            input.display(),
+            e,
+            encryption.key_id.as_deref().unwrap_or("without a key id")
+        ),
+        None => format!("Cannot decrypt {}: {}", input.display(), e),
+    })?;
+
+    // Confere o conteúdo com o checksum registrado no momento do envio
+    if let Some(encryption) = encryption {
+        if encryption.sha256 != hash {
+            return Err(format!(
+                "{} does not match the checksum in its manifest; the backup may be incomplete",
+                output.display()
+            )
+            .into());
+        }
+    }
+    println!("Decrypted to {}.", output.display());
+    Ok(())
+}
+
+async fn restore_backup(app_config: &AppConfig, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = None;
+    let mut from = None;
+    let mut identity = None;
+    let mut host = None;
+    let mut date = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" => from = Some(args.next().ok_or("Missing value for --from")?),
+            "--identity" | "-i" => identity = Some(args.next().ok_or("Missing value for --identity")?),
+            "--host" => host = Some(args.next().ok_or("Missing value for --host")?.clone()),
+            "--date" => date = Some(args.next().ok_or("Missing value for --date")?),
+            _ if file.is_none() => file = Some(arg.as_str()),
+            other => return Err(format!("Unexpected argument '{}'\n\n{}", other, USAGE).into()),
+        }
+    }
+
+    let configs: Vec<_> = effective_configs(&app_config.backups)
+        .into_iter()
+        .filter(|backup| from.is_none_or(|from| &backup.name == from))
+        .collect();
+    if configs.is_empty() {
+        return Err(match from {
+            Some(from) => format!("No backup destination named '{}'", from),
+            None => "No backup destination configured (see \"backups\" in logsync.json)".to_string(),
+        }
+        .into());
+    }
+    let sinks = build_sinks(&configs).await;
+
+    // Sem nome de arquivo, --host/--date escolhem o backup (por padrão, o desta máquina)
+    let selection = (host.is_some() || date.is_some()).then(|| (host.unwrap_or_else(utils::hostname), date));
+    if file.is_some() && selection.is_some() {
+        return Err("Pass either a backup name or --host/--date, not both".into());
+    }
+
+    match file {
+        None | Some("list") if selection.is_none() => {
+            for sink in &sinks {
+                let listings = match list_backups(sink.as_ref()).await {
+                    Ok(listings) => listings,
+                    Err(e) => {
+                        eprintln!("{}: cannot list backups: {}", sink.name(), e);
+                        continue;
+                    }
+                };
+                if listings.is_empty() {
+                    println!("{}: no backups.", sink.name());
+                }
+                for listing in listings {
+                    let Some(manifest) = &listing.manifest else {
+                        println!("{:<12} {:<40} (no manifest; uploaded by an older version)", sink.name(), listing.name);
+                        continue;
+                    };
+                    let period = match (manifest.first_entry, manifest.last_entry) {
+                        (Some(first), Some(last)) => {
+                            format!("{} to {}", utils::format_timestamp(first), utils::format_timestamp(last))
+                        }
+                        _ => "-".to_string(),
+                    };
+                    println!(
+                        "{:<12} {:<48} {:<16} {:<10} {:<25} {:>6} entries  {}{}",
+                        sink.name(),
+                        listing.name,
+                        manifest.host,
+                        manifest.segment.as_deref().unwrap_or("-"),
+                        manifest.uploaded_at,
+                        manifest.entries,
+                        period,
+                        if listing.is_encrypted() { "  (encrypted)" } else { "" }
+                    );
+                }
+            }
+            Ok(())
+        }
+        file => {
+            // Usa o primeiro destino que tiver o backup
+            for sink in &sinks {
+                let listings = list_backups(sink.as_ref())
+                    .await
+                    .map_err(|e| format!("{}: cannot list backups: {}", sink.name(), e))?;
+                let listing = match (&selection, file) {
+                    (Some((host, date)), _) => select_backup(listings, host, date.map(String::as_str)),
+                    (None, Some(file)) => listings.into_iter().find(|listing| listing.name == file),
+                    (None, None) => None,
+                };
+                let Some(listing) = listing else {
+                    continue;
+                };
+                let file = listing.name.as_str();
+                if listing.manifest.is_none() {
+                    eprintln!("Warning: {} has no manifest; its checksum cannot be verified.", file);
+                }
+                let identities = if listing.is_encrypted() {
+                    encryption::load_identities(identity.map(std::path::Path::new)).map_err(|e| e.to_string())?
+                } else {
+                    Vec::new()
+                };
+
+                let entries = fetch_entries(sink.as_ref(), &listing, &identities)
+                    .await
+                    .map_err(|e| format!("Cannot restore {} from {}: {}", file, sink.name(), e))?;
+                let total = entries.len();
+                let added = merge_entries(LOG_FILE_PATH, entries)?;
+                println!(
+                    "Restored {} from {}: {} new entries, {} already present.",
+                    file,
+                    sink.name(),
+                    added,
+                    total - added
+                );
+                return Ok(());
+            }
+            Err(match (selection, file) {
+                (Some((host, Some(date))), _) => format!("No backup of {} for {} found (see `logsync restore list`)", host, date),
+                (Some((host, None)), _) => format!("No backup of {} found (see `logsync restore list`)", host),
+                (None, file) => format!("No backup named '{}' found (see `logsync restore list`)", file.unwrap_or_default()),
+            }
+            .into())
+        }
+    }
+}
+
+async fn run_monitor(app_config: AppConfig) -> Result<(), Box<dyn std::error::Error>> {
     // Define o número máximo de logs a serem armazenados
     let max_logs = 1000; // Este valor pode ser alterado conforme necessário
 
-    // Inicializa a autenticação do Google Drive
-    let drive_hub = auth::authenticate().await?;
+    // Com criptografia configurada, nada é enviado sem ela: chave inválida impede a inicialização
+    let encryptor = match &app_config.backup_encryption {
+        Some(config) => Some(BackupEncryptor::new(config).map_err(|e| format!("Invalid backup_encryption: {}", e))?),
+        None => None,
+    };
+
+    // Destinos de backup opcionais (Drive, diretório local, S3, WebDAV, SFTP)
+    let backups = build_sinks(&app_config.backups).await;
 
-    // Caminho do arquivo JSON onde os logs serão salvos
-    let log_file_path = "filtered_logs.json";
+    let log_file_path = LOG_FILE_PATH;
 
     // Verifica se o arquivo de log existe, caso contrário, cria-o
-    utils::ensure_file_exists(&log_file_path)?;
+    utils::ensure_file_exists(log_file_path)?;
+
+    // Uploads rodam em uma tarefa própria (com debounce e sincronização periódica)
+    let backups = BackupScheduler::spawn(
+        backups,
+        std::path::Path::new(log_file_path),
+        app_config.backup_schedule.clone(),
+        encryptor,
+    );
 
     // Cria um canal para enviar logs filtrados para exibição
     let (tx, rx) = mpsc::channel(100);
@@ -35,22 +599,149 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Cria um estado compartilhado para rastrear erros processados
     let processed_errors: Arc<Mutex<HashSet<String>>> = Arc::new(Mutex::new(HashSet::new()));
 
+    // Configura o envio de alertas (imediato ou em modo digest)
+    // Canais sem configuração válida são desativados com um aviso, sem derrubar o processo
+    let loaded = build_notifiers(&app_config.channels);
+    let channel_names: Vec<&str> = loaded.enabled.iter().map(|n| n.name()).collect();
+    let disabled = loaded.disabled_names();
+    let router = Router::new(&app_config.routes, &app_config.default_route, &channel_names, &disabled)
+        .map_err(|e| e.to_string())?;
+    let mut incident_policy = app_config.incidents.clone();
+    if let Some(policy) = &mut incident_policy {
+        if let Some(unknown) = policy
+            .escalation_route
+            .iter()
+            .find(|d| !channel_names.contains(&d.as_str()) && !disabled.contains(&d.as_str()))
+        {
+            return Err(format!("Incident escalation route references unknown channel '{}'", unknown).into());
+        }
+        policy.escalation_route.retain(|d| channel_names.contains(&d.as_str()));
+    }
+    let bot_channel_disabled = app_config
+        .telegram_bot
+        .as_ref()
+        .is_some_and(|bot| disabled.contains(&bot.channel.as_str()));
+    let notifiers = Arc::new(loaded.enabled);
+
+    // Alertas ficam na outbox em disco até serem entregues (com novas tentativas)
+    let outbox_config = OutboxConfig::from_env();
+    let outbox = Outbox::open(&outbox_config.path)?;
+    let reporter = DeliveryReporter::new(log_file_path, Some(tx.clone()));
+    tokio::spawn(run_outbox_worker(outbox.clone(), Arc::clone(&notifiers), outbox_config, reporter));
+
+    // Relatórios são enviados separadamente dos alertas (agendados ou em caso de tempestade)
+    let reports = spawn_report_worker(app_config.reports.clone(), Arc::clone(&notifiers), log_file_path);
+
+    // Fingerprints silenciados pelo bot (/mute) continuam sendo armazenados, sem alerta
+    let mutes_path = std::env::var("LOGSYNC_MUTES_PATH").unwrap_or_else(|_| "./mutes.json".to_string());
+    let mutes = MuteList::open(std::path::Path::new(&mutes_path))?;
+
+    // Silêncios e janelas de manutenção (arquivo de configuração, CLI e visualizador)
+    let silences = open_silences(&app_config)?;
+
+    // Incidentes por fingerprint: reenvio, escalonamento e resolução automática
+    let incidents = match incident_policy {
+        Some(policy) => {
+            let tracker = IncidentTracker::open(policy)?;
+            tokio::spawn(run_incident_worker(tracker.clone(), outbox.clone()));
+            Some(tracker)
+        }
+        None => None,
+    };
+
+    // API HTTP local (usada por `logsync ack` e `logsync incidents`)
+    if let Some(api_config) = app_config.api.clone() {
+        let state = ApiState {
+            log_file_path: log_file_path.to_string(),
+            incidents: incidents.clone(),
+        };
+        tokio::spawn(async move {
+            if let Err(e) = api::serve(api_config, state).await {
+                eprintln!("HTTP API stopped: {}", e);
+            }
+        });
+    }
+
+    // Bot do Telegram para comandos (/status, /last, /ack, /mute...)
+    if let Some(bot_config) = app_config.telegram_bot.clone() {
+        match telegram_config_for(&app_config.channels, &bot_config.channel) {
+            Some(_) if bot_channel_disabled => {
+                eprintln!("Telegram bot disabled: channel '{}' is disabled", bot_config.channel)
+            }
+            Some(telegram) => {
+                let mut bot = TelegramBot::new(telegram, bot_config, log_file_path, mutes.clone(), outbox.clone())
+                    .with_reports(reports.clone());
+                if let Some(incidents) = &incidents {
+                    bot = bot.with_incidents(incidents.clone());
+                }
+                tokio::spawn(bot.run());
+            }
+            None => eprintln!("Telegram bot disabled: no Telegram channel named '{}'", bot_config.channel),
+        }
+    }
+
+    let mut dispatcher = AlertDispatcher::new(router, outbox, &DigestConfig::from_env())
+        .with_reports(reports)
+        .with_mutes(mutes)
+        .with_silences(silences.clone());
+    if let Some(incidents) = incidents {
+        dispatcher = dispatcher.with_incidents(incidents);
+    }
+
+    // Condições de limite, taxa, ausência e proporção avaliadas sobre todas as linhas
+    let evaluator = ConditionEvaluator::new(&app_config.conditions)?;
+    let conditions_tx = if evaluator.is_empty() {
+        None
+    } else {
+        let (conditions_tx, conditions_rx) = mpsc::channel(1000);
+        tokio::spawn(run_condition_evaluator(
+            evaluator,
+            conditions_rx,
+            log_file_path.to_string(),
+            tx.clone(),
+            dispatcher.clone(),
+        ));
+        Some(conditions_tx)
+    };
+
     // Inicia a monitoria dos logs em segundo plano
     let _monitor_task = {
-        let drive_hub = drive_hub.clone();
         let processed_errors = Arc::clone(&processed_errors);
         let log_file_path = log_file_path.to_string();
-        
+
         tokio::spawn(async move {
-            if let Err(e) = monitor_logs_and_create_json(&log_file_path, &drive_hub, tx, processed_errors).await {
+            if let Err(e) = monitor_logs_and_create_json(&log_file_path, tx, processed_errors, dispatcher, conditions_tx).await {
                 eprintln!("Error during log monitoring and upload: {}", e);
             }
         })
     };
 
-    // Exibe os logs em tempo real no terminal interativo
-    let _ = viewer::start_interactive_viewer(rx, max_logs).await;
+    // Exibe os logs em tempo real no terminal interativo, até sair ou receber SIGINT/SIGTERM
+    tokio::select! {
+        _ = viewer::start_interactive_viewer(rx, max_logs, Some(silences), Some(backups.status())) => {}
+        _ = shutdown_signal() => viewer::restore_terminal(),
+    }
+
+    // Envia as últimas alterações para os backups antes de encerrar
+    println!("Flushing backups...");
+    if tokio::time::timeout(BACKUP_FLUSH_TIMEOUT, backups.flush()).await.is_err() {
+        eprintln!("Backup flush timed out; the latest changes may not have been uploaded.");
+    }
 
     Ok(())
 }
 
+async fn shutdown_signal() {
+    let mut terminate = match signal(SignalKind::terminate()) {
+        Ok(terminate) => terminate,
+        Err(e) => {
+            eprintln!("Cannot listen for SIGTERM: {}", e);
+            let _ = tokio::signal::ctrl_c().await;
+            return;
+        }
+    };
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {}
+        _ = terminate.recv() => {}
+    }
+}
@@ -0,0 +1,109 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{post_json, NotifyError, Notifier};
+use crate::types::LogEntry;
+
+/// Discord rejects message content longer than this.
+const DISCORD_MAX_CONTENT: usize = 2000;
+
+/// Discord channel webhook.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DiscordConfig {
+    pub webhook_url: String,
+    #[serde(default)]
+    pub username: Option<String>,
+}
+
+pub struct DiscordNotifier {
+    name: String,
+    config: DiscordConfig,
+    client: reqwest::Client,
+}
+
+impl DiscordNotifier {
+    pub fn new(name: String, config: DiscordConfig) -> Self {
+        Self {
+            name,
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for DiscordNotifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn max_message_len(&self) -> Option<usize> {
+        Some(DISCORD_MAX_CONTENT)
+    }
+
+    async fn notify(&self, message: &str, _entry: Option<&LogEntry>) -> Result<(), NotifyError> {
+        let mut body = json!({ "content": message });
+        if let Some(username) = &self.config.username {
+            body["username"] = json!(username);
+        }
+
+        post_json(&self.client, &self.config.webhook_url, &body).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::test_support::{log_entry, MockServer};
+    use crate::notifier::TemplatedNotifier;
+    use hyper::StatusCode;
+
+    fn notifier(url: &str, username: Option<&str>) -> DiscordNotifier {
+        let config = DiscordConfig {
+            webhook_url: format!("{}/api/webhooks/1/token", url),
+            username: username.map(str::to_string),
+        };
+        DiscordNotifier::new("discord".to_string(), config)
+    }
+
+    #[tokio::test]
+    async fn posts_the_content_and_username() {
+        let server = MockServer::start(|_| (StatusCode::NO_CONTENT, String::new())).await;
+
+        notifier(&server.url, Some("LogSync")).notify("disk full", None).await.unwrap();
+        notifier(&server.url, None).notify("disk full", None).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/api/webhooks/1/token");
+        assert_eq!(requests[0].json(), json!({ "content": "disk full", "username": "LogSync" }));
+        assert_eq!(requests[1].json(), json!({ "content": "disk full" }));
+    }
+
+    #[tokio::test]
+    async fn long_alerts_are_split_to_the_content_limit() {
+        let server = MockServer::start(|_| (StatusCode::NO_CONTENT, String::new())).await;
+        let entry = log_entry(&"a long line of text\n".repeat(250));
+        let templated = TemplatedNotifier::new(Box::new(notifier(&server.url, None)), None);
+
+        templated.notify("ignored", Some(&entry)).await.unwrap();
+
+        let contents: Vec<String> = server
+            .requests()
+            .iter()
+            .map(|r| r.json()["content"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(contents.len(), 3);
+        assert!(contents.iter().all(|content| content.chars().count() <= DISCORD_MAX_CONTENT));
+        assert_eq!(contents.concat().matches("a long line of text").count(), 250);
+    }
+
+    #[tokio::test]
+    async fn reports_error_responses() {
+        let server = MockServer::start(|_| (StatusCode::TOO_MANY_REQUESTS, "rate limited".to_string())).await;
+
+        let error = notifier(&server.url, None).notify("x", None).await.unwrap_err();
+
+        assert_eq!(error.to_string(), "HTTP 429 Too Many Requests: rate limited");
+    }
+}
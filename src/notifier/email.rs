@@ -0,0 +1,196 @@
+use async_trait::async_trait;
+use lettre::message::{header::ContentType, Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde::Deserialize;
+use std::path::Path;
+
+use super::{NotifyError, Notifier};
+use crate::types::LogEntry;
+
+/// SMTP email delivery.
+///
+/// `tls` selects between STARTTLS (`"starttls"`, the default), implicit TLS
+/// (`"tls"`) and an unencrypted connection (`"none"`, for local test servers).
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmailConfig {
+    pub host: String,
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default = "default_tls")]
+    pub tls: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    pub from: String,
+    pub to: Vec<String>,
+    #[serde(default = "default_subject_prefix")]
+    pub subject_prefix: String,
+}
+
+fn default_tls() -> String {
+    "starttls".to_string()
+}
+
+fn default_subject_prefix() -> String {
+    "[LogSync]".to_string()
+}
+
+pub struct EmailNotifier {
+    name: String,
+    config: EmailConfig,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl EmailNotifier {
+    pub fn new(name: String, config: EmailConfig) -> Result<Self, NotifyError> {
+        let mut builder = match config.tls.as_str() {
+            "starttls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.host)?,
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.host)?,
+            "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.host),
+            other => return Err(format!("Unknown SMTP tls mode: {}", other).into()),
+        };
+
+        if let Some(port) = config.port {
+            builder = builder.port(port);
+        }
+        if let (Some(username), Some(password)) = (&config.username, &config.password) {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        // Validate addresses once instead of on every alert
+        config.from.parse::<Mailbox>()?;
+        for recipient in &config.to {
+            recipient.parse::<Mailbox>()?;
+        }
+
+        Ok(Self {
+            name,
+            transport: builder.build(),
+            config,
+        })
+    }
+
+    fn message_builder(&self, subject: String) -> Result<lettre::message::MessageBuilder, NotifyError> {
+        let mut builder = Message::builder().from(self.config.from.parse()?).subject(subject);
+        for recipient in &self.config.to {
+            builder = builder.to(recipient.parse()?);
+        }
+        Ok(builder)
+    }
+
+    fn subject(&self, message: &str, entry: Option<&LogEntry>) -> String {
+        let summary = match entry {
+            Some(entry) => format!("{} {}", entry.log_type, first_line(&entry.message)),
+            None => first_line(message).to_string(),
+        };
+        format!("{} {}", self.config.subject_prefix, super::truncate_chars(&summary, 120))
+    }
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or_default()
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn notify(&self, message: &str, entry: Option<&LogEntry>) -> Result<(), NotifyError> {
+        let email = self
+            .message_builder(self.subject(message, entry))?
+            .body(message.to_string())?;
+        self.transport.send(email).await?;
+        Ok(())
+    }
+
+    async fn send_report(&self, path: &Path, caption: &str) -> Result<(), NotifyError> {
+        let content = tokio::fs::read(path).await?;
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "report".to_string());
+        let content_type = ContentType::parse(mime_guess::from_path(path).first_or_octet_stream().as_ref())?;
+
+        let email = self
+            .message_builder(format!("{} {}", self.config.subject_prefix, caption))?
+            .multipart(
+                MultiPart::mixed()
+                    .singlepart(SinglePart::plain(caption.to_string()))
+                    .singlepart(Attachment::new(file_name).body(content, content_type)),
+            )?;
+        self.transport.send(email).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::test_support::{log_entry, MockSmtpServer};
+
+    fn notifier(port: u16) -> EmailNotifier {
+        let config = EmailConfig {
+            host: "127.0.0.1".to_string(),
+            port: Some(port),
+            tls: "none".to_string(),
+            username: None,
+            password: None,
+            from: "LogSync <logsync@example.com>".to_string(),
+            to: vec!["ops@example.com".to_string(), "dev@example.com".to_string()],
+            subject_prefix: default_subject_prefix(),
+        };
+        EmailNotifier::new("email".to_string(), config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn sends_alerts_with_the_entry_as_subject() {
+        let server = MockSmtpServer::start().await;
+        let entry = log_entry("disk full on /var\nat sda1");
+
+        notifier(server.port).notify("ERROR: disk full on /var", Some(&entry)).await.unwrap();
+
+        let mails = server.mails();
+        assert_eq!(mails.len(), 1);
+        assert_eq!(mails[0].from, "<logsync@example.com>");
+        assert_eq!(mails[0].to, ["<ops@example.com>", "<dev@example.com>"]);
+        assert!(mails[0].data.contains("Subject: [LogSync] ERROR disk full on /var\r\n"), "{}", mails[0].data);
+        assert!(mails[0].data.contains("\r\n\r\nERROR: disk full on /var\r\n"), "{}", mails[0].data);
+    }
+
+    #[tokio::test]
+    async fn attaches_reports() {
+        let server = MockSmtpServer::start().await;
+        let path = std::env::temp_dir().join(format!("logsync-email-report-{}.csv", std::process::id()));
+        std::fs::write(&path, "type,count\nERROR,3\n").unwrap();
+
+        let result = notifier(server.port).send_report(&path, "LogSync daily report").await;
+        std::fs::remove_file(&path).unwrap();
+        result.unwrap();
+
+        let data = &server.mails()[0].data;
+        assert!(data.contains("Subject: [LogSync] LogSync daily report\r\n"), "{}", data);
+        assert!(data.contains("Content-Disposition: attachment; filename=\""), "{}", data);
+        assert!(data.contains("Content-Type: text/csv"), "{}", data);
+    }
+
+    #[test]
+    fn rejects_unknown_tls_modes_and_bad_addresses() {
+        let config = |tls: &str, from: &str| EmailConfig {
+            host: "127.0.0.1".to_string(),
+            port: None,
+            tls: tls.to_string(),
+            username: None,
+            password: None,
+            from: from.to_string(),
+            to: vec!["ops@example.com".to_string()],
+            subject_prefix: default_subject_prefix(),
+        };
+
+        assert!(EmailNotifier::new("email".to_string(), config("ssl", "a@example.com")).is_err());
+        assert!(EmailNotifier::new("email".to_string(), config("none", "not an address")).is_err());
+    }
+}
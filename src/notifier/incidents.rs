@@ -0,0 +1,429 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::outbox::Outbox;
+use crate::logger::{acknowledge_entry, read_log_file};
+use crate::types::LogEntry;
+use crate::utils::{now_secs, write_atomically};
+
+/// How often the incident worker checks for re-notifications, escalations and resolutions.
+const TICK: Duration = Duration::from_secs(30);
+
+/// Resolved incidents are kept this long for `/incidents` and the HTTP API.
+const RESOLVED_RETENTION_SECS: u64 = 7 * 86400;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IncidentState {
+    Open,
+    Acknowledged,
+    Resolved,
+}
+
+/// Occurrences of one error fingerprint, from the first alert until it goes quiet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Incident {
+    pub fingerprint: String,
+    pub title: String,
+    pub log_type: String,
+    pub priority: String,
+    pub source: Option<String>,
+    pub destinations: Vec<String>,
+    pub state: IncidentState,
+    pub opened_at: u64,
+    pub last_seen: u64,
+    pub occurrences: u64,
+    pub last_notified_at: u64,
+    pub escalated: bool,
+    pub acknowledged_by: Option<String>,
+    pub resolved_at: Option<u64>,
+}
+
+impl Incident {
+    fn is_active(&self) -> bool {
+        self.state != IncidentState::Resolved
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "{} ({} occurrences, source {})\nfingerprint: {}",
+            self.title,
+            self.occurrences,
+            self.source.as_deref().unwrap_or("unknown"),
+            self.fingerprint
+        )
+    }
+}
+
+/// Re-notification, escalation and auto-resolution settings.
+#[derive(Debug, Clone, Deserialize)]
+pub struct IncidentPolicy {
+    /// Repeat the alert this often while unacknowledged (0 disables).
+    #[serde(default)]
+    pub renotify_every_mins: u64,
+    /// Escalate unacknowledged incidents after this long (0 disables).
+    #[serde(default)]
+    pub escalate_after_mins: u64,
+    #[serde(default)]
+    pub escalation_route: Vec<String>,
+    /// Resolve once no occurrence has been seen for this long (0 disables).
+    #[serde(default = "default_resolve_after_quiet_mins")]
+    pub resolve_after_quiet_mins: u64,
+    #[serde(default = "default_path")]
+    pub path: PathBuf,
+}
+
+fn default_resolve_after_quiet_mins() -> u64 {
+    30
+}
+
+fn default_path() -> PathBuf {
+    PathBuf::from("./incidents.json")
+}
+
+/// What the worker has to announce for an incident.
+enum IncidentAction {
+    Renotify(Incident),
+    Escalate(Incident),
+    Resolve(Incident),
+}
+
+/// Incidents keyed by fingerprint, persisted so they survive restarts.
+#[derive(Clone)]
+pub struct IncidentTracker {
+    policy: IncidentPolicy,
+    incidents: Arc<Mutex<HashMap<String, Incident>>>,
+}
+
+impl IncidentTracker {
+    pub fn open(policy: IncidentPolicy) -> Result<Self, Box<dyn std::error::Error>> {
+        let incidents: HashMap<String, Incident> = if policy.path.exists() {
+            let content = std::fs::read_to_string(&policy.path)?;
+            if content.trim().is_empty() {
+                HashMap::new()
+            } else {
+                serde_json::from_str(&content)
+                    .map_err(|e| format!("Invalid incidents file {}: {}", policy.path.display(), e))?
+            }
+        } else {
+            HashMap::new()
+        };
+
+        Ok(Self {
+            policy,
+            incidents: Arc::new(Mutex::new(incidents)),
+        })
+    }
+
+    pub fn policy(&self) -> &IncidentPolicy {
+        &self.policy
+    }
+
+    /// Whether an open or acknowledged incident exists for `fingerprint`.
+    pub fn is_active(&self, fingerprint: &str) -> bool {
+        self.incidents
+            .lock()
+            .unwrap()
+            .get(fingerprint)
+            .is_some_and(Incident::is_active)
+    }
+
+    /// Records an occurrence. Opens a new incident when none is active and
+    /// returns `true` in that case.
+    pub fn observe(&self, entry: &LogEntry, destinations: &[String]) -> bool {
+        let now = now_secs();
+        let mut incidents = self.incidents.lock().unwrap();
+
+        let opened = match incidents.get_mut(&entry.fingerprint) {
+            Some(incident) if incident.is_active() => {
+                incident.last_seen = now;
+                incident.occurrences += 1;
+                false
+            }
+            _ => {
+                incidents.insert(
+                    entry.fingerprint.clone(),
+                    Incident {
+                        fingerprint: entry.fingerprint.clone(),
+                        title: entry.message.clone(),
+                        log_type: entry.log_type.clone(),
+                        priority: entry.priority.clone(),
+                        source: entry.source.clone(),
+                        destinations: destinations.to_vec(),
+                        state: IncidentState::Open,
+                        opened_at: now,
+                        last_seen: now,
+                        occurrences: 1,
+                        last_notified_at: now,
+                        escalated: false,
+                        acknowledged_by: None,
+                        resolved_at: None,
+                    },
+                );
+                true
+            }
+        };
+
+        self.persist(&incidents);
+        opened
+    }
+
+    /// Acknowledges the active incident for `fingerprint`, stopping re-notifications
+    /// and escalation.
+    pub fn acknowledge(&self, fingerprint: &str, acknowledged_by: &str) -> Option<Incident> {
+        let mut incidents = self.incidents.lock().unwrap();
+        let incident = incidents.get_mut(fingerprint).filter(|i| i.is_active())?;
+        incident.state = IncidentState::Acknowledged;
+        incident.acknowledged_by = Some(acknowledged_by.to_string());
+        let acknowledged = incident.clone();
+        self.persist(&incidents);
+        Some(acknowledged)
+    }
+
+    /// Resolves the active incident for `fingerprint` without announcing it,
+    /// for callers that send their own recovery message.
+    pub fn resolve(&self, fingerprint: &str, now: u64) -> Option<Incident> {
+        let mut incidents = self.incidents.lock().unwrap();
+        let incident = incidents.get_mut(fingerprint).filter(|i| i.is_active())?;
+        incident.state = IncidentState::Resolved;
+        incident.resolved_at = Some(now);
+        let resolved = incident.clone();
+        self.persist(&incidents);
+        Some(resolved)
+    }
+
+    /// All known incidents, active ones first, most recent first.
+    pub fn list(&self) -> Vec<Incident> {
+        let mut list: Vec<Incident> = self.incidents.lock().unwrap().values().cloned().collect();
+        list.sort_by_key(|i| (!i.is_active(), std::cmp::Reverse(i.last_seen)));
+        list
+    }
+
+    fn tick(&self, now: u64) -> Vec<IncidentAction> {
+        let policy = &self.policy;
+        let mut actions = Vec::new();
+        let mut incidents = self.incidents.lock().unwrap();
+
+        for incident in incidents.values_mut().filter(|i| i.is_active()) {
+            if policy.resolve_after_quiet_mins > 0
+                && now.saturating_sub(incident.last_seen) >= policy.resolve_after_quiet_mins * 60
+            {
+                incident.state = IncidentState::Resolved;
+                incident.resolved_at = Some(now);
+                actions.push(IncidentAction::Resolve(incident.clone()));
+                continue;
+            }
+
+            if incident.state != IncidentState::Open {
+                continue;
+            }
+
+            if policy.escalate_after_mins > 0
+                && !incident.escalated
+                && !policy.escalation_route.is_empty()
+                && now.saturating_sub(incident.opened_at) >= policy.escalate_after_mins * 60
+            {
+                incident.escalated = true;
+                actions.push(IncidentAction::Escalate(incident.clone()));
+            }
+
+            if policy.renotify_every_mins > 0
+                && now.saturating_sub(incident.last_notified_at) >= policy.renotify_every_mins * 60
+            {
+                incident.last_notified_at = now;
+                actions.push(IncidentAction::Renotify(incident.clone()));
+            }
+        }
+
+        incidents.retain(|_, i| {
+            i.resolved_at
+                .is_none_or(|resolved| now.saturating_sub(resolved) < RESOLVED_RETENTION_SECS)
+        });
+
+        if !actions.is_empty() {
+            self.persist(&incidents);
+        }
+        actions
+    }
+
+    fn persist(&self, incidents: &HashMap<String, Incident>) {
+        let result = serde_json::to_vec_pretty(incidents)
+            .map_err(std::io::Error::from)
+            .and_then(|content| write_atomically(&self.policy.path, &content));
+        if let Err(e) = result {
+            eprintln!("Error writing incidents file: {}", e);
+        }
+    }
+}
+
+/// Acknowledges the incident with fingerprint `key`, or else the stored entry
+/// with id `key` together with its incident. Returns `false` when neither exists.
+pub fn acknowledge(
+    key: &str,
+    acknowledged_by: &str,
+    log_file_path: &str,
+    incidents: Option<&IncidentTracker>,
+) -> Result<bool, String> {
+    if incidents.is_some_and(|tracker| tracker.acknowledge(key, acknowledged_by).is_some()) {
+        return Ok(true);
+    }
+
+    let entry = read_log_file(log_file_path)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|entry| entry.id == key);
+    let Some(entry) = entry else {
+        return Ok(false);
+    };
+
+    acknowledge_entry(log_file_path, key, acknowledged_by).map_err(|e| e.to_string())?;
+    if let Some(tracker) = incidents {
+        tracker.acknowledge(&entry.fingerprint, acknowledged_by);
+    }
+    Ok(true)
+}
+
+/// Periodically re-notifies, escalates and resolves incidents, queueing the
+/// messages in the outbox.
+pub async fn run_incident_worker(tracker: IncidentTracker, outbox: Outbox) {
+    loop {
+        tokio::time::sleep(TICK).await;
+
+        let now = now_secs();
+        for action in tracker.tick(now) {
+            let (message, destinations) = match action {
+                IncidentAction::Renotify(incident) => (
+                    format!(
+                        "🔁 Still unacknowledged after {} min: {}",
+                        now.saturating_sub(incident.opened_at) / 60,
+                        incident.summary()
+                    ),
+                    incident.destinations,
+                ),
+                IncidentAction::Escalate(incident) => (
+                    format!(
+                        "⏫ Escalated, unacknowledged for {} min: {}",
+                        now.saturating_sub(incident.opened_at) / 60,
+                        incident.summary()
+                    ),
+                    tracker.policy().escalation_route.clone(),
+                ),
+                IncidentAction::Resolve(incident) => {
+                    let mut destinations = incident.destinations.clone();
+                    if incident.escalated {
+                        for destination in &tracker.policy().escalation_route {
+                            if !destinations.contains(destination) {
+                                destinations.push(destination.clone());
+                            }
+                        }
+                    }
+                    (
+                        format!(
+                            "✅ Resolved after {} min without new occurrences: {}",
+                            tracker.policy().resolve_after_quiet_mins,
+                            incident.summary()
+                        ),
+                        destinations,
+                    )
+                }
+            };
+
+            if destinations.is_empty() {
+                continue;
+            }
+            if let Err(e) = outbox.enqueue(message, Vec::new(), destinations) {
+                eprintln!("Error queueing incident notification: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::test_support::log_entry;
+
+    /// Tracker with one open incident, returned with the time it was opened.
+    fn open_incident(name: &str, policy: &str) -> (IncidentTracker, u64) {
+        let path = std::env::temp_dir().join(format!("logsync-incidents-{}-{}.json", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut policy: IncidentPolicy = serde_json::from_str(policy).unwrap();
+        policy.path = path;
+        let tracker = IncidentTracker::open(policy).unwrap();
+        assert!(tracker.observe(&log_entry("db down"), &["ops".to_string()]));
+        let opened_at = tracker.list()[0].opened_at;
+        (tracker, opened_at)
+    }
+
+    fn kinds(actions: &[IncidentAction]) -> Vec<&'static str> {
+        actions
+            .iter()
+            .map(|action| match action {
+                IncidentAction::Renotify(_) => "renotify",
+                IncidentAction::Escalate(_) => "escalate",
+                IncidentAction::Resolve(_) => "resolve",
+            })
+            .collect()
+    }
+
+    #[test]
+    fn renotifies_until_acknowledged() {
+        let (tracker, t0) = open_incident("renotify", r#"{"renotify_every_mins": 15, "resolve_after_quiet_mins": 0}"#);
+
+        assert!(kinds(&tracker.tick(t0 + 14 * 60)).is_empty());
+        assert_eq!(kinds(&tracker.tick(t0 + 15 * 60)), ["renotify"]);
+        assert!(kinds(&tracker.tick(t0 + 29 * 60)).is_empty());
+        assert_eq!(kinds(&tracker.tick(t0 + 30 * 60)), ["renotify"]);
+
+        tracker.acknowledge("3f2a9c1d0b7e4a55", "ana").unwrap();
+        assert!(kinds(&tracker.tick(t0 + 60 * 60)).is_empty());
+        let _ = std::fs::remove_file(&tracker.policy().path);
+    }
+
+    #[test]
+    fn escalates_once_when_a_route_is_configured() {
+        let (tracker, t0) = open_incident(
+            "escalate",
+            r#"{"escalate_after_mins": 60, "escalation_route": ["oncall"], "resolve_after_quiet_mins": 0}"#,
+        );
+
+        assert!(kinds(&tracker.tick(t0 + 59 * 60)).is_empty());
+        assert_eq!(kinds(&tracker.tick(t0 + 60 * 60)), ["escalate"]);
+        assert!(kinds(&tracker.tick(t0 + 120 * 60)).is_empty());
+        assert!(tracker.list()[0].escalated);
+        let _ = std::fs::remove_file(&tracker.policy().path);
+
+        let (tracker, t0) = open_incident("no-route", r#"{"escalate_after_mins": 60, "resolve_after_quiet_mins": 0}"#);
+        assert!(kinds(&tracker.tick(t0 + 60 * 60)).is_empty());
+        let _ = std::fs::remove_file(&tracker.policy().path);
+    }
+
+    #[test]
+    fn resolves_after_going_quiet_even_when_acknowledged() {
+        let (tracker, t0) = open_incident("resolve", r#"{"renotify_every_mins": 5, "resolve_after_quiet_mins": 30}"#);
+        tracker.acknowledge("3f2a9c1d0b7e4a55", "ana").unwrap();
+
+        assert!(kinds(&tracker.tick(t0 + 29 * 60)).is_empty());
+        assert_eq!(kinds(&tracker.tick(t0 + 30 * 60)), ["resolve"]);
+        assert!(!tracker.is_active("3f2a9c1d0b7e4a55"));
+        assert_eq!(tracker.list()[0].resolved_at, Some(t0 + 30 * 60));
+        assert!(kinds(&tracker.tick(t0 + 31 * 60)).is_empty());
+
+        // A new occurrence after the resolution opens a fresh incident.
+        assert!(tracker.observe(&log_entry("db down"), &["ops".to_string()]));
+        let _ = std::fs::remove_file(&tracker.policy().path);
+    }
+
+    #[test]
+    fn zero_quiet_period_never_resolves() {
+        let (tracker, t0) = open_incident("never", r#"{"resolve_after_quiet_mins": 0}"#);
+
+        assert!(kinds(&tracker.tick(t0)).is_empty());
+        assert!(kinds(&tracker.tick(t0 + 365 * 86400)).is_empty());
+        assert!(tracker.is_active("3f2a9c1d0b7e4a55"));
+        let _ = std::fs::remove_file(&tracker.policy().path);
+    }
+}
@@ -0,0 +1,495 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use std::collections::BTreeMap;
+use std::env;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::DigestConfig;
+use crate::types::{DeliveryStatus, LogEntry};
+use crate::utils::now_secs;
+
+pub mod discord;
+pub mod email;
+pub mod incidents;
+pub mod mutes;
+pub mod outbox;
+pub mod reports;
+pub mod routing;
+pub mod silences;
+pub mod slack;
+pub mod teams;
+pub mod telegram;
+pub mod telegram_bot;
+pub mod template;
+#[cfg(test)]
+pub(crate) mod test_support;
+pub mod webhook;
+
+pub use discord::{DiscordConfig, DiscordNotifier};
+pub use email::{EmailConfig, EmailNotifier};
+pub use incidents::{run_incident_worker, IncidentPolicy, IncidentTracker};
+pub use mutes::MuteList;
+pub use outbox::{run_outbox_worker, DeliveryReporter, Outbox};
+pub use reports::{spawn_report_worker, ReportDeliveryConfig, ReportHandle};
+pub use routing::{RouteConfig, Router};
+pub use silences::{Silence, SilenceList};
+pub use slack::{SlackConfig, SlackNotifier};
+pub use teams::{TeamsConfig, TeamsNotifier};
+pub use telegram::{TelegramConfig, TelegramNotifier};
+pub use telegram_bot::{TelegramBot, TelegramBotConfig};
+pub use template::{MessageFormat, TemplatedNotifier};
+pub use webhook::{WebhookConfig, WebhookNotifier};
+
+/// How many sample messages are listed per group in a digest.
+const DIGEST_SAMPLES_PER_GROUP: usize = 3;
+
+/// Timeout applied to every outgoing HTTP notification.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub type NotifyError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A destination that alerts can be delivered to.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Name of the destination as configured, used in logs and routing.
+    fn name(&self) -> &str;
+
+    /// Markup the channel interprets in message text.
+    fn format(&self) -> MessageFormat {
+        MessageFormat::Plain
+    }
+
+    /// Longest message the channel accepts, in characters; longer ones are split.
+    fn max_message_len(&self) -> Option<usize> {
+        None
+    }
+
+    /// Sends `message`. `entry` is the log entry that triggered the alert,
+    /// or `None` for messages that cover several entries (e.g. digests).
+    async fn notify(&self, message: &str, entry: Option<&LogEntry>) -> Result<(), NotifyError>;
+
+    /// Sends a report file. Channels that cannot carry attachments return an error.
+    async fn send_report(&self, _path: &Path, _caption: &str) -> Result<(), NotifyError> {
+        Err(format!("channel '{}' does not support report delivery", self.name()).into())
+    }
+}
+
+/// A named notification channel as declared in the configuration file.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChannelConfig {
+    pub name: String,
+    /// Message template for single-entry alerts (see `template::render`).
+    #[serde(default)]
+    pub template: Option<String>,
+    #[serde(flatten)]
+    pub kind: ChannelKind,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ChannelKind {
+    Telegram(TelegramConfig),
+    Slack(SlackConfig),
+    Discord(DiscordConfig),
+    Teams(TeamsConfig),
+    Webhook(WebhookConfig),
+    Email(EmailConfig),
+}
+
+impl ChannelConfig {
+    /// Checks that the settings required to deliver anything are present.
+    pub fn validate(&self) -> Result<(), String> {
+        let required = |field: &str, value: &str| {
+            if value.trim().is_empty() {
+                Err(format!("'{}' is empty", field))
+            } else {
+                Ok(())
+            }
+        };
+        let url = |field: &str, value: &str| {
+            required(field, value)?;
+            match reqwest::Url::parse(value) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(()),
+                Ok(_) => Err(format!("'{}' must be an http(s) URL", field)),
+                Err(e) => Err(format!("invalid '{}': {}", field, e)),
+            }
+        };
+
+        match &self.kind {
+            ChannelKind::Telegram(config) => {
+                required("token", &config.token)?;
+                required("chat_id", &config.chat_id)?;
+                if config.format == MessageFormat::Markdown {
+                    return Err("'format' must be plain, markdownv2 or html".to_string());
+                }
+                url("api_base", &config.api_base)
+            }
+            ChannelKind::Slack(config) => url("webhook_url", &config.webhook_url),
+            ChannelKind::Discord(config) => url("webhook_url", &config.webhook_url),
+            ChannelKind::Teams(config) => url("webhook_url", &config.webhook_url),
+            ChannelKind::Webhook(config) => url("url", &config.url),
+            ChannelKind::Email(config) => {
+                required("host", &config.host)?;
+                required("from", &config.from)?;
+                if config.to.is_empty() {
+                    return Err("'to' is empty".to_string());
+                }
+                Ok(())
+            }
+        }
+    }
+
+    pub fn build(&self) -> Result<Box<dyn Notifier>, NotifyError> {
+        self.validate()?;
+
+        let name = self.name.clone();
+        let notifier: Box<dyn Notifier> = match &self.kind {
+            ChannelKind::Telegram(config) => Box::new(TelegramNotifier::new(name, config.clone())),
+            ChannelKind::Slack(config) => Box::new(SlackNotifier::new(name, config.clone())),
+            ChannelKind::Discord(config) => Box::new(DiscordNotifier::new(name, config.clone())),
+            ChannelKind::Teams(config) => Box::new(TeamsNotifier::new(name, config.clone())),
+            ChannelKind::Webhook(config) => Box::new(WebhookNotifier::new(name, config.clone())),
+            ChannelKind::Email(config) => Box::new(EmailNotifier::new(name, config.clone())?),
+        };
+
+        Ok(Box::new(TemplatedNotifier::new(notifier, self.template.clone())))
+    }
+}
+
+/// Channels that could be built, and the names of those that were disabled
+/// together with the reason.
+pub struct LoadedNotifiers {
+    pub enabled: Vec<Box<dyn Notifier>>,
+    pub disabled: Vec<(String, String)>,
+}
+
+impl LoadedNotifiers {
+    pub fn disabled_names(&self) -> Vec<&str> {
+        self.disabled.iter().map(|(name, _)| name.as_str()).collect()
+    }
+}
+
+/// Validates and builds every configured channel. A channel with missing or
+/// invalid settings is disabled instead of failing the whole configuration.
+///
+/// When no channels are configured, falls back to a Telegram channel read from
+/// `TELEGRAM_API_TOKEN`/`TELEGRAM_CHAT_ID`.
+pub fn load_notifiers(channels: &[ChannelConfig]) -> LoadedNotifiers {
+    let mut loaded = LoadedNotifiers {
+        enabled: Vec::new(),
+        disabled: Vec::new(),
+    };
+
+    if channels.is_empty() {
+        match TelegramConfig::from_env() {
+            Ok(Some(config)) => {
+                let telegram = Box::new(TelegramNotifier::new("telegram".to_string(), config));
+                loaded.enabled.push(Box::new(TemplatedNotifier::new(
+                    telegram,
+                    env::var("TELEGRAM_TEMPLATE").ok(),
+                )));
+            }
+            Ok(None) => {}
+            Err(reason) => loaded.disabled.push(("telegram".to_string(), reason)),
+        }
+        return loaded;
+    }
+
+    for channel in channels {
+        match channel.build() {
+            Ok(notifier) => loaded.enabled.push(notifier),
+            Err(e) => loaded.disabled.push((channel.name.clone(), e.to_string())),
+        }
+    }
+    loaded
+}
+
+/// Like `load_notifiers`, printing a warning for every disabled channel.
+pub fn build_notifiers(channels: &[ChannelConfig]) -> LoadedNotifiers {
+    let loaded = load_notifiers(channels);
+    for (name, reason) in &loaded.disabled {
+        eprintln!("Warning: notification channel '{}' disabled: {}", name, reason);
+    }
+    if loaded.enabled.is_empty() {
+        eprintln!("Warning: no notification channel is configured; entries are stored but no alerts are sent");
+    }
+    loaded
+}
+
+/// Telegram settings of the channel called `name`, falling back to the
+/// environment for the implicit `telegram` channel.
+pub fn telegram_config_for(channels: &[ChannelConfig], name: &str) -> Option<TelegramConfig> {
+    channels
+        .iter()
+        .find_map(|channel| match &channel.kind {
+            ChannelKind::Telegram(config) if channel.name == name => Some(config.clone()),
+            _ => None,
+        })
+        .or_else(|| {
+            (channels.is_empty() && name == "telegram")
+                .then(|| TelegramConfig::from_env().ok().flatten())
+                .flatten()
+        })
+}
+
+pub async fn send_log_to_channel(
+    tx: &Sender<LogEntry>,
+    log_entry: LogEntry,
+) -> Result<(), Box<dyn std::error::Error>> {
+    tx.send(log_entry)
+        .await
+        .map_err(|e| format!("Error sending log to channel: {}", e).into())
+}
+
+/// Posts a JSON body and turns non-2xx responses into errors.
+pub(crate) async fn post_json(
+    client: &reqwest::Client,
+    url: &str,
+    body: &Value,
+) -> Result<(), NotifyError> {
+    let response = client
+        .post(url)
+        .json(body)
+        .timeout(HTTP_TIMEOUT)
+        .send()
+        .await?;
+
+    check_response(response).await
+}
+
+pub(crate) async fn check_response(response: reqwest::Response) -> Result<(), NotifyError> {
+    let status = response.status();
+    if status.is_success() {
+        Ok(())
+    } else {
+        let body = response.text().await.unwrap_or_default();
+        Err(format!("HTTP {}: {}", status, body).into())
+    }
+}
+
+/// Cuts `text` to at most `max_chars` characters, marking the cut with an ellipsis.
+pub(crate) fn truncate_chars(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+/// Routes alerts to their destinations and queues them in the outbox, either
+/// immediately or through the digest buffer.
+///
+/// CRITICAL entries always bypass the digest and are queued immediately.
+#[derive(Clone)]
+pub struct AlertDispatcher {
+    router: Arc<Router>,
+    outbox: Outbox,
+    digest_tx: Option<Sender<()>>,
+    reports: Option<ReportHandle>,
+    mutes: Option<MuteList>,
+    silences: Option<SilenceList>,
+    incidents: Option<IncidentTracker>,
+}
+
+impl AlertDispatcher {
+    /// Creates a dispatcher, spawning the digest worker when digest mode is
+    /// enabled. Otherwise entries still held for a digest are released at once.
+    pub fn new(router: Router, outbox: Outbox, config: &DigestConfig) -> Self {
+        let digest_tx = if config.enabled {
+            let (tx, rx) = mpsc::channel(100);
+            tokio::spawn(run_digest_worker(rx, config.clone(), outbox.clone()));
+            Some(tx)
+        } else {
+            // Entries held by a run with digest mode on would otherwise never be sent
+            let held = outbox.held_len();
+            if held > 0 {
+                eprintln!("Digest mode is off; sending {} entries held for a digest by a previous run", held);
+                if let Err(e) = outbox.release_digests() {
+                    eprintln!("Error queueing digest: {}", e);
+                }
+            }
+            None
+        };
+
+        Self {
+            router: Arc::new(router),
+            outbox,
+            digest_tx,
+            reports: None,
+            mutes: None,
+            silences: None,
+            incidents: None,
+        }
+    }
+
+    /// Suppresses alerts for muted fingerprints (entries are still stored).
+    pub fn with_mutes(mut self, mutes: MuteList) -> Self {
+        self.mutes = Some(mutes);
+        self
+    }
+
+    /// Suppresses alerts for entries matching an active silence (entries are still stored).
+    pub fn with_silences(mut self, silences: SilenceList) -> Self {
+        self.silences = Some(silences);
+        self
+    }
+
+    /// Groups alerts into incidents: only the first occurrence of an active
+    /// incident is sent, later ones are counted.
+    pub fn with_incidents(mut self, incidents: IncidentTracker) -> Self {
+        self.incidents = Some(incidents);
+        self
+    }
+
+    /// The same dispatcher without incident grouping.
+    pub fn without_incidents(&self) -> Self {
+        Self {
+            incidents: None,
+            ..self.clone()
+        }
+    }
+
+    /// Resolves the active incident for `fingerprint`, if any.
+    pub fn resolve_incident(&self, fingerprint: &str) {
+        if let Some(incidents) = &self.incidents {
+            incidents.resolve(fingerprint, now_secs());
+        }
+    }
+
+    /// Reports every dispatched alert to the report worker for storm detection.
+    pub fn with_reports(mut self, reports: ReportHandle) -> Self {
+        self.reports = Some(reports);
+        self
+    }
+
+    /// Delivery status an entry starts with: pending if an alert will be sent
+    /// for it, silenced if a mute or silence suppresses the alert.
+    pub fn initial_status(&self, log_entry: &LogEntry) -> DeliveryStatus {
+        if !log_entry.alert_requested {
+            return DeliveryStatus::NotRequired;
+        }
+        if self
+            .mutes
+            .as_ref()
+            .is_some_and(|mutes| mutes.is_muted(&log_entry.fingerprint))
+        {
+            return DeliveryStatus::Silenced("muted".to_string());
+        }
+        if let Some(silence) = self.silences.as_ref().and_then(|s| s.silencing(log_entry)) {
+            return DeliveryStatus::Silenced(format!("silence {}", silence.id));
+        }
+
+        let grouped = self
+            .incidents
+            .as_ref()
+            .is_some_and(|incidents| incidents.is_active(&log_entry.fingerprint));
+        if grouped || self.router.route(log_entry).is_empty() {
+            DeliveryStatus::NotRequired
+        } else {
+            DeliveryStatus::Pending
+        }
+    }
+
+    pub async fn dispatch(&self, log_entry: &LogEntry) -> Result<(), Box<dyn std::error::Error>> {
+        let pending = log_entry.delivery_status == DeliveryStatus::Pending;
+        if let Some(incidents) = &self.incidents {
+            if pending || incidents.is_active(&log_entry.fingerprint) {
+                incidents.observe(log_entry, &self.router.route(log_entry));
+            }
+        }
+        if !pending {
+            return Ok(());
+        }
+
+        if let Some(reports) = &self.reports {
+            reports.alert_raised();
+        }
+
+        let destinations = self.router.route(log_entry);
+        match &self.digest_tx {
+            Some(tx) if !is_critical(log_entry) => {
+                self.outbox.hold_for_digest(log_entry.clone(), destinations)?;
+                tx.send(())
+                    .await
+                    .map_err(|e| format!("Error queueing log for digest: {}", e).into())
+            }
+            _ => self
+                .outbox
+                .enqueue(log_entry.message.clone(), vec![log_entry.clone()], destinations),
+        }
+    }
+}
+
+fn is_critical(log_entry: &LogEntry) -> bool {
+    log_entry.log_type == "CRITICAL" || log_entry.priority == "very high"
+}
+
+/// Turns the entries held in the outbox into one digest per destination once
+/// the window elapses or `max_entries` is reached. Entries held by a previous
+/// run are released after the first window, and the rest when the channel closes.
+async fn run_digest_worker(mut rx: Receiver<()>, config: DigestConfig, outbox: Outbox) {
+    let window = Duration::from_secs(config.window_secs.max(1));
+    let mut buffered = outbox.held_len();
+    let mut deadline = tokio::time::Instant::now() + window;
+
+    loop {
+        tokio::select! {
+            received = rx.recv() => match received {
+                Some(()) => {
+                    if buffered == 0 {
+                        deadline = tokio::time::Instant::now() + window;
+                    }
+                    buffered += 1;
+                    if buffered >= config.max_entries.max(1) {
+                        release_digests(&outbox);
+                        buffered = 0;
+                    }
+                }
+                None => {
+                    release_digests(&outbox);
+                    return;
+                }
+            },
+            _ = tokio::time::sleep_until(deadline), if buffered > 0 => {
+                release_digests(&outbox);
+                buffered = 0;
+            }
+        }
+    }
+}
+
+fn release_digests(outbox: &Outbox) {
+    if let Err(e) = outbox.release_digests() {
+        eprintln!("Error queueing digest: {}", e);
+    }
+}
+
+/// Formats entries as a digest grouped by type, priority and source.
+pub fn format_digest(entries: &[LogEntry]) -> String {
+    let mut groups: BTreeMap<(&str, &str, &str), Vec<&LogEntry>> = BTreeMap::new();
+    for entry in entries {
+        let source = entry.source.as_deref().unwrap_or("unknown");
+        groups
+            .entry((entry.log_type.as_str(), entry.priority.as_str(), source))
+            .or_default()
+            .push(entry);
+    }
+
+    let mut digest = format!("LogSync digest: {} entries in {} groups\n", entries.len(), groups.len());
+    for ((log_type, priority, source), group) in &groups {
+        digest.push_str(&format!("\n[{} / {}] {} ({})\n", log_type, priority, source, group.len()));
+        for entry in group.iter().take(DIGEST_SAMPLES_PER_GROUP) {
+            digest.push_str(&format!("  - {}\n", entry.message));
+        }
+        if group.len() > DIGEST_SAMPLES_PER_GROUP {
+            digest.push_str(&format!("  ... and {} more\n", group.len() - DIGEST_SAMPLES_PER_GROUP));
+        }
+    }
+
+    digest
+}
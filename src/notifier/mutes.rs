@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::utils::{now_secs, write_atomically};
+
+/// Fingerprints whose notifications are suppressed until a given time,
+/// persisted so mutes survive restarts.
+#[derive(Clone)]
+pub struct MuteList {
+    path: PathBuf,
+    mutes: Arc<Mutex<HashMap<String, u64>>>,
+}
+
+impl MuteList {
+    pub fn open(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let mutes: HashMap<String, u64> = if path.exists() {
+            let content = std::fs::read_to_string(path)?;
+            if content.trim().is_empty() {
+                HashMap::new()
+            } else {
+                serde_json::from_str(&content)
+                    .map_err(|e| format!("Invalid mutes file {}: {}", path.display(), e))?
+            }
+        } else {
+            HashMap::new()
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            mutes: Arc::new(Mutex::new(mutes)),
+        })
+    }
+
+    /// Mutes `fingerprint` for `duration_secs` from now.
+    pub fn mute(&self, fingerprint: &str, duration_secs: u64) -> std::io::Result<u64> {
+        let until = now_secs().saturating_add(duration_secs);
+        let mut mutes = self.mutes.lock().unwrap();
+        mutes.insert(fingerprint.to_string(), until);
+        self.persist(&mutes)?;
+        Ok(until)
+    }
+
+    /// Removes a mute. Returns `false` if the fingerprint was not muted.
+    pub fn unmute(&self, fingerprint: &str) -> std::io::Result<bool> {
+        let mut mutes = self.mutes.lock().unwrap();
+        let removed = mutes.remove(fingerprint).is_some();
+        if removed {
+            self.persist(&mutes)?;
+        }
+        Ok(removed)
+    }
+
+    pub fn is_muted(&self, fingerprint: &str) -> bool {
+        self.mutes
+            .lock()
+            .unwrap()
+            .get(fingerprint)
+            .is_some_and(|&until| until > now_secs())
+    }
+
+    /// Mutes that have not expired yet, as `(fingerprint, until)` pairs.
+    pub fn active(&self) -> Vec<(String, u64)> {
+        let now = now_secs();
+        let mut active: Vec<(String, u64)> = self
+            .mutes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, &until)| until > now)
+            .map(|(fingerprint, &until)| (fingerprint.clone(), until))
+            .collect();
+        active.sort_by_key(|(_, until)| *until);
+        active
+    }
+
+    fn persist(&self, mutes: &HashMap<String, u64>) -> std::io::Result<()> {
+        let content = serde_json::to_vec_pretty(mutes)?;
+        write_atomically(&self.path, &content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn huge_durations_mute_until_the_end_of_time() {
+        let path = std::env::temp_dir().join(format!("logsync-mutes-{}.json", std::process::id()));
+        let mutes = MuteList::open(&path).unwrap();
+
+        assert_eq!(mutes.mute("fp", u64::MAX).unwrap(), u64::MAX);
+        assert!(mutes.is_muted("fp"));
+        assert_eq!(crate::utils::format_timestamp(u64::MAX), "9999-12-30 23:59:59");
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,460 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc::Sender;
+use tokio::sync::Notify;
+
+use super::{format_digest, Notifier};
+use crate::config::OutboxConfig;
+use crate::logger::update_delivery_status;
+use crate::types::{DeliveryStatus, LogEntry};
+use crate::utils::{generate_entry_id, now_secs, write_atomically};
+
+/// An alert waiting to be delivered to one or more destinations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxItem {
+    pub id: String,
+    pub message: String,
+    /// Entries covered by the alert (one for single alerts, several for digests).
+    pub entries: Vec<LogEntry>,
+    /// Destinations that have not acknowledged the alert yet.
+    pub destinations: Vec<String>,
+    pub attempts: u32,
+    pub next_attempt_at: u64,
+    pub last_error: Option<String>,
+    /// Held for the next digest instead of being delivered on its own.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub held_for_digest: bool,
+    /// Entries (by id) whose alert already failed in another item, such as
+    /// the digest for another destination, with the reason.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub failed_entries: BTreeMap<String, String>,
+}
+
+/// Alerts persisted on disk until every destination has accepted them, so
+/// undelivered alerts survive restarts.
+#[derive(Clone)]
+pub struct Outbox {
+    path: PathBuf,
+    items: Arc<Mutex<Vec<OutboxItem>>>,
+    wake: Arc<Notify>,
+}
+
+impl Outbox {
+    /// Opens the outbox at `path`, loading any alerts left from a previous run.
+    pub fn open(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let items: Vec<OutboxItem> = if path.exists() {
+            let content = std::fs::read_to_string(path)?;
+            if content.trim().is_empty() {
+                Vec::new()
+            } else {
+                serde_json::from_str(&content)
+                    .map_err(|e| format!("Invalid outbox file {}: {}", path.display(), e))?
+            }
+        } else {
+            Vec::new()
+        };
+
+        if !items.is_empty() {
+            println!("Replaying {} undelivered alert(s) from {}", items.len(), path.display());
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            items: Arc::new(Mutex::new(items)),
+            wake: Arc::new(Notify::new()),
+        })
+    }
+
+    /// Persists a new alert and wakes the delivery worker.
+    pub fn enqueue(
+        &self,
+        message: String,
+        entries: Vec<LogEntry>,
+        destinations: Vec<String>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        {
+            let mut items = self.items.lock().unwrap();
+            items.push(OutboxItem {
+                id: generate_entry_id(),
+                message,
+                entries,
+                destinations,
+                attempts: 0,
+                next_attempt_at: 0,
+                last_error: None,
+                held_for_digest: false,
+                failed_entries: BTreeMap::new(),
+            });
+            self.persist(&items)?;
+        }
+
+        self.wake.notify_one();
+        Ok(())
+    }
+
+    /// Persists an entry for the next digest. It is not delivered until
+    /// `release_digests` turns the held entries into digests.
+    pub fn hold_for_digest(
+        &self,
+        entry: LogEntry,
+        destinations: Vec<String>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut items = self.items.lock().unwrap();
+        items.push(OutboxItem {
+            id: generate_entry_id(),
+            message: entry.message.clone(),
+            entries: vec![entry],
+            destinations,
+            attempts: 0,
+            next_attempt_at: 0,
+            last_error: None,
+            held_for_digest: true,
+            failed_entries: BTreeMap::new(),
+        });
+        self.persist(&items)?;
+        Ok(())
+    }
+
+    /// Number of entries waiting for the next digest, including those left
+    /// from a previous run.
+    pub fn held_len(&self) -> usize {
+        self.items.lock().unwrap().iter().filter(|item| item.held_for_digest).count()
+    }
+
+    /// Replaces the held entries with one digest per destination and wakes
+    /// the delivery worker.
+    pub fn release_digests(&self) -> Result<(), Box<dyn std::error::Error>> {
+        {
+            let mut items = self.items.lock().unwrap();
+            let mut per_destination: BTreeMap<String, Vec<LogEntry>> = BTreeMap::new();
+            for item in items.iter().filter(|item| item.held_for_digest) {
+                for destination in &item.destinations {
+                    per_destination
+                        .entry(destination.clone())
+                        .or_default()
+                        .extend(item.entries.iter().cloned());
+                }
+            }
+            if per_destination.is_empty() {
+                return Ok(());
+            }
+
+            items.retain(|item| !item.held_for_digest);
+            for (destination, entries) in per_destination {
+                items.push(OutboxItem {
+                    id: generate_entry_id(),
+                    message: format_digest(&entries),
+                    entries,
+                    destinations: vec![destination],
+                    attempts: 0,
+                    next_attempt_at: 0,
+                    last_error: None,
+                    held_for_digest: false,
+                    failed_entries: BTreeMap::new(),
+                });
+            }
+            self.persist(&items)?;
+        }
+
+        self.wake.notify_one();
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn persist(&self, items: &[OutboxItem]) -> std::io::Result<()> {
+        let content = serde_json::to_vec_pretty(items)?;
+        write_atomically(&self.path, &content)
+    }
+
+    fn due_items(&self, now: u64) -> Vec<OutboxItem> {
+        self.items
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|item| !item.held_for_digest && item.next_attempt_at <= now)
+            .cloned()
+            .collect()
+    }
+
+    fn next_due(&self) -> Option<u64> {
+        self.items
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|item| !item.held_for_digest)
+            .map(|item| item.next_attempt_at)
+            .min()
+    }
+
+    /// Replaces an item after a failed delivery attempt that will be retried,
+    /// keeping failures recorded by other items meanwhile.
+    fn update(&self, mut item: OutboxItem) {
+        let mut items = self.items.lock().unwrap();
+        if let Some(existing) = items.iter_mut().find(|i| i.id == item.id) {
+            item.failed_entries = std::mem::take(&mut existing.failed_entries);
+            *existing = item;
+        }
+        if let Err(e) = self.persist(&items) {
+            eprintln!("Error writing outbox file: {}", e);
+        }
+    }
+
+    /// Removes an item that was delivered or given up, and returns the status
+    /// to record for each of its entries.
+    ///
+    /// An entry covered by several items (one digest per destination) is only
+    /// reported once its last item finishes, and as failed if any of them failed.
+    fn finish(&self, finished: &OutboxItem, status: DeliveryStatus) -> Vec<(LogEntry, DeliveryStatus)> {
+        let mut items = self.items.lock().unwrap();
+        let failed_entries = items
+            .iter()
+            .find(|i| i.id == finished.id)
+            .map(|i| i.failed_entries.clone())
+            .unwrap_or_default();
+        items.retain(|i| i.id != finished.id);
+
+        let mut reports = Vec::new();
+        for entry in &finished.entries {
+            let status = match (&status, failed_entries.get(&entry.id)) {
+                (DeliveryStatus::Failed(reason), Some(earlier)) => {
+                    DeliveryStatus::Failed(format!("{}; {}", earlier, reason))
+                }
+                (_, Some(earlier)) => DeliveryStatus::Failed(earlier.clone()),
+                (status, None) => status.clone(),
+            };
+
+            let mut pending = items
+                .iter_mut()
+                .filter(|item| item.entries.iter().any(|e| e.id == entry.id))
+                .peekable();
+            if pending.peek().is_none() {
+                reports.push((entry.clone(), status));
+            } else if let DeliveryStatus::Failed(reason) = &status {
+                for item in pending {
+                    item.failed_entries.insert(entry.id.clone(), reason.clone());
+                }
+            }
+        }
+
+        if let Err(e) = self.persist(&items) {
+            eprintln!("Error writing outbox file: {}", e);
+        }
+        reports
+    }
+}
+
+/// Records delivery results in the log store and forwards them to the viewer.
+#[derive(Clone)]
+pub struct DeliveryReporter {
+    log_file_path: String,
+    viewer_tx: Option<Sender<LogEntry>>,
+}
+
+impl DeliveryReporter {
+    pub fn new(log_file_path: &str, viewer_tx: Option<Sender<LogEntry>>) -> Self {
+        Self {
+            log_file_path: log_file_path.to_string(),
+            viewer_tx,
+        }
+    }
+
+    pub fn report(&self, entries: &[LogEntry], status: &DeliveryStatus) {
+        for entry in entries {
+            if let Err(e) = update_delivery_status(&self.log_file_path, &entry.id, status) {
+                eprintln!("Error updating delivery status: {}", e);
+            }
+
+            if let Some(tx) = &self.viewer_tx {
+                let mut updated = entry.clone();
+                updated.delivery_status = status.clone();
+                let _ = tx.try_send(updated);
+            }
+        }
+    }
+}
+
+/// Delivers outbox items, retrying failed destinations with exponential backoff
+/// until they succeed or `max_attempts` is reached.
+pub async fn run_outbox_worker(
+    outbox: Outbox,
+    notifiers: Arc<Vec<Box<dyn Notifier>>>,
+    config: OutboxConfig,
+    reporter: DeliveryReporter,
+) {
+    loop {
+        for item in outbox.due_items(now_secs()) {
+            match attempt_delivery(item, &notifiers, &config).await {
+                Attempt::Retry(item) => outbox.update(item),
+                Attempt::Finished(item, status) => {
+                    for (entry, status) in outbox.finish(&item, status) {
+                        reporter.report(&[entry], &status);
+                    }
+                }
+            }
+        }
+
+        let wait = outbox
+            .next_due()
+            .map(|due| due.saturating_sub(now_secs()).max(1))
+            .unwrap_or(60);
+
+        tokio::select! {
+            _ = outbox.wake.notified() => {}
+            _ = tokio::time::sleep(Duration::from_secs(wait)) => {}
+        }
+    }
+}
+
+enum Attempt {
+    /// Some destinations failed and are retried later.
+    Retry(OutboxItem),
+    /// Delivered to every destination, or given up.
+    Finished(OutboxItem, DeliveryStatus),
+}
+
+/// Tries every remaining destination once.
+async fn attempt_delivery(mut item: OutboxItem, notifiers: &[Box<dyn Notifier>], config: &OutboxConfig) -> Attempt {
+    let entry = match item.entries.as_slice() {
+        [single] => Some(single),
+        _ => None,
+    };
+
+    let mut remaining = Vec::new();
+    let mut errors = Vec::new();
+    for destination in &item.destinations {
+        let result = match notifiers.iter().find(|n| n.name() == destination) {
+            Some(notifier) => notifier.notify(&item.message, entry).await,
+            None => Err(format!("unknown channel '{}'", destination).into()),
+        };
+
+        match result {
+            Ok(()) => println!("Alert sent successfully to {}.", destination),
+            Err(e) => {
+                eprintln!("Error sending alert to {}: {}", destination, e);
+                errors.push(format!("{}: {}", destination, e));
+                remaining.push(destination.clone());
+            }
+        }
+    }
+
+    if remaining.is_empty() {
+        return Attempt::Finished(item, DeliveryStatus::Sent);
+    }
+
+    item.attempts += 1;
+    item.destinations = remaining;
+    item.last_error = Some(errors.join("; "));
+
+    if item.attempts >= config.max_attempts {
+        let reason = format!(
+            "gave up after {} attempts: {}",
+            item.attempts,
+            item.last_error.as_deref().unwrap_or_default()
+        );
+        eprintln!("Dropping alert {}: {}", item.id, reason);
+        return Attempt::Finished(item, DeliveryStatus::Failed(reason));
+    }
+
+    item.next_attempt_at = now_secs() + config.backoff_secs(item.attempts);
+    Attempt::Retry(item)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DigestConfig;
+    use crate::notifier::test_support::log_entry;
+
+    /// Entries are told apart by id when a digest is delivered.
+    fn entry(message: &str) -> LogEntry {
+        let mut entry = log_entry(message);
+        entry.id = generate_entry_id();
+        entry
+    }
+
+    #[test]
+    fn held_digest_entries_survive_a_restart() {
+        let path = std::env::temp_dir().join(format!("logsync-outbox-digest-{}.json", std::process::id()));
+        let outbox = Outbox::open(&path).unwrap();
+        outbox.hold_for_digest(entry("timeout"), vec!["ops".to_string()]).unwrap();
+        outbox
+            .hold_for_digest(entry("refused"), vec!["ops".to_string(), "dev".to_string()])
+            .unwrap();
+        assert!(outbox.due_items(now_secs()).is_empty());
+        drop(outbox);
+
+        let reopened = Outbox::open(&path).unwrap();
+        assert_eq!(reopened.held_len(), 2);
+        assert!(reopened.due_items(now_secs()).is_empty());
+
+        reopened.release_digests().unwrap();
+        let due = reopened.due_items(now_secs());
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(reopened.held_len(), 0);
+        assert_eq!(due.len(), 2);
+        let ops = due.iter().find(|item| item.destinations == ["ops"]).unwrap();
+        assert_eq!(ops.entries.len(), 2);
+        assert!(ops.message.starts_with("LogSync digest: 2 entries"));
+        let dev = due.iter().find(|item| item.destinations == ["dev"]).unwrap();
+        assert_eq!(dev.entries.len(), 1);
+    }
+
+    #[test]
+    fn held_entries_are_released_when_digest_mode_is_off() {
+        let path = std::env::temp_dir().join(format!("logsync-outbox-undigested-{}.json", std::process::id()));
+        let outbox = Outbox::open(&path).unwrap();
+        outbox.hold_for_digest(entry("timeout"), vec!["ops".to_string()]).unwrap();
+        drop(outbox);
+
+        let reopened = Outbox::open(&path).unwrap();
+        let router = crate::notifier::Router::new(&[], &["ops".to_string()], &["ops"], &[]).unwrap();
+        let _dispatcher = crate::notifier::AlertDispatcher::new(router, reopened.clone(), &DigestConfig::default());
+        let due = reopened.due_items(now_secs());
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(reopened.held_len(), 0);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].destinations, ["ops"]);
+        assert_eq!(due[0].entries[0].message, "timeout");
+    }
+
+    #[test]
+    fn entries_in_several_digests_are_reported_once_with_the_combined_status() {
+        let path = std::env::temp_dir().join(format!("logsync-outbox-combined-{}.json", std::process::id()));
+        let outbox = Outbox::open(&path).unwrap();
+        let (shared, ops_only, dev_only) = (entry("timeout"), entry("refused"), entry("reset"));
+        outbox
+            .hold_for_digest(shared.clone(), vec!["ops".to_string(), "dev".to_string()])
+            .unwrap();
+        outbox.hold_for_digest(ops_only.clone(), vec!["ops".to_string()]).unwrap();
+        outbox.hold_for_digest(dev_only.clone(), vec!["dev".to_string()]).unwrap();
+        outbox.release_digests().unwrap();
+        let due = outbox.due_items(now_secs());
+        let ops = due.iter().find(|item| item.destinations == ["ops"]).unwrap();
+        let dev = due.iter().find(|item| item.destinations == ["dev"]).unwrap();
+
+        // The failure is kept for the shared entry until its other digest finishes
+        let reports = outbox.finish(ops, DeliveryStatus::Failed("ops: HTTP 500".to_string()));
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].0.id, ops_only.id);
+        let reopened = Outbox::open(&path).unwrap();
+        let reports = reopened.finish(dev, DeliveryStatus::Sent);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(reopened.is_empty());
+        let status = |id: &str| reports.iter().find(|(e, _)| e.id == id).map(|(_, s)| s.clone());
+        assert_eq!(status(&shared.id), Some(DeliveryStatus::Failed("ops: HTTP 500".to_string())));
+        assert_eq!(status(&dev_only.id), Some(DeliveryStatus::Sent));
+    }
+}
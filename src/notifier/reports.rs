@@ -0,0 +1,362 @@
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, Local, NaiveDate, Timelike};
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::time::Instant;
+
+use super::Notifier;
+use crate::logger::read_log_file;
+use crate::report_archive::{ReportArchive, ReportArchiveConfig, ReportRecord};
+use crate::report_comparison::{compare_with_previous, ComparisonConfig};
+use crate::report_formats::{write_report, ReportFormat};
+use crate::report_generator::{HtmlReportConfig, Report, ReportPeriod};
+use crate::utils::now_secs;
+
+/// When reports are delivered automatically.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportSchedule {
+    /// At the top of every hour, covering the previous hour.
+    Hourly,
+    /// At local midnight, covering the previous day.
+    Daily,
+    /// At local midnight between Sunday and Monday, covering the previous week.
+    Weekly,
+    /// When `storm_threshold` alerts are raised within `storm_window_secs`.
+    OnStorm,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReportDeliveryConfig {
+    #[serde(default)]
+    pub schedule: Vec<ReportSchedule>,
+    /// Channels that receive reports; all channels when empty.
+    #[serde(default)]
+    pub destinations: Vec<String>,
+    #[serde(default = "default_storm_threshold")]
+    pub storm_threshold: usize,
+    #[serde(default = "default_storm_window_secs")]
+    pub storm_window_secs: u64,
+    /// Formats generated for every report; `text` is sent as a message, the
+    /// others as attachments.
+    #[serde(default = "default_formats")]
+    pub formats: Vec<ReportFormat>,
+    #[serde(default)]
+    pub html: HtmlReportConfig,
+    #[serde(default)]
+    pub archive: ReportArchiveConfig,
+    /// Comparison with the previous period, for reports over a closed period.
+    #[serde(default)]
+    pub comparison: ComparisonConfig,
+}
+
+fn default_formats() -> Vec<ReportFormat> {
+    vec![ReportFormat::Html]
+}
+
+fn default_storm_threshold() -> usize {
+    20
+}
+
+fn default_storm_window_secs() -> u64 {
+    300
+}
+
+impl ReportSchedule {
+    pub fn label(self) -> &'static str {
+        match self {
+            ReportSchedule::Hourly => "hourly",
+            ReportSchedule::Daily => "daily",
+            ReportSchedule::Weekly => "weekly",
+            ReportSchedule::OnStorm => "storm",
+        }
+    }
+
+    /// The hour, day or week containing `time`; `None` for `OnStorm`.
+    pub fn period_containing(self, time: DateTime<Local>) -> Option<ReportPeriod> {
+        let (start, end) = match self {
+            ReportSchedule::Hourly => {
+                let start = time.with_minute(0)?.with_second(0)?.with_nanosecond(0)?;
+                (start, start + ChronoDuration::hours(1))
+            }
+            ReportSchedule::Daily => {
+                let day = time.date_naive();
+                (local_midnight(day)?, local_midnight(day + ChronoDuration::days(1))?)
+            }
+            ReportSchedule::Weekly => {
+                let monday = time.date_naive() - ChronoDuration::days(i64::from(time.weekday().num_days_from_monday()));
+                (local_midnight(monday)?, local_midnight(monday + ChronoDuration::days(7))?)
+            }
+            ReportSchedule::OnStorm => return None,
+        };
+        Some(ReportPeriod {
+            start: start.timestamp().max(0) as u64,
+            end: end.timestamp().max(0) as u64,
+        })
+    }
+
+    /// The last complete hour, day or week before `time`.
+    pub fn previous_period(self, time: DateTime<Local>) -> Option<ReportPeriod> {
+        let current = self.period_containing(time)?;
+        let before = DateTime::from_timestamp(current.start as i64 - 1, 0)?.with_timezone(&Local);
+        self.period_containing(before)
+    }
+}
+
+fn local_midnight(day: NaiveDate) -> Option<DateTime<Local>> {
+    day.and_hms_opt(0, 0, 0)?.and_local_timezone(Local).earliest()
+}
+
+impl Default for ReportDeliveryConfig {
+    fn default() -> Self {
+        Self {
+            schedule: Vec::new(),
+            destinations: Vec::new(),
+            storm_threshold: default_storm_threshold(),
+            storm_window_secs: default_storm_window_secs(),
+            formats: default_formats(),
+            html: HtmlReportConfig::default(),
+            archive: ReportArchiveConfig::default(),
+            comparison: ComparisonConfig::default(),
+        }
+    }
+}
+
+enum ReportRequest {
+    OnDemand,
+    AlertRaised,
+}
+
+/// Handle used to request reports from the report worker.
+#[derive(Clone)]
+pub struct ReportHandle {
+    tx: Sender<ReportRequest>,
+}
+
+impl ReportHandle {
+    /// Generates a fresh report over all stored entries and delivers it.
+    pub async fn request_report(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.tx
+            .send(ReportRequest::OnDemand)
+            .await
+            .map_err(|e| format!("Error requesting report: {}", e).into())
+    }
+
+    /// Records an alert for storm detection. Never blocks the alert path.
+    pub fn alert_raised(&self) {
+        let _ = self.tx.try_send(ReportRequest::AlertRaised);
+    }
+}
+
+/// Spawns the worker that generates and delivers reports on schedule, on storm
+/// and on demand.
+pub fn spawn_report_worker(
+    config: ReportDeliveryConfig,
+    notifiers: Arc<Vec<Box<dyn Notifier>>>,
+    log_file_path: &str,
+) -> ReportHandle {
+    let (tx, rx) = mpsc::channel(100);
+    tokio::spawn(run_report_worker(rx, config, notifiers, log_file_path.to_string()));
+    ReportHandle { tx }
+}
+
+async fn run_report_worker(
+    mut rx: Receiver<ReportRequest>,
+    config: ReportDeliveryConfig,
+    notifiers: Arc<Vec<Box<dyn Notifier>>>,
+    log_file_path: String,
+) {
+    let storm_window = Duration::from_secs(config.storm_window_secs.max(1));
+    let mut recent_alerts: VecDeque<Instant> = VecDeque::new();
+    let mut last_storm_report: Option<Instant> = None;
+    let mut period_ends = scheduled_period_ends(&config.schedule, Local::now());
+
+    loop {
+        let next_end = period_ends.iter().map(|&(_, end)| end).min();
+
+        tokio::select! {
+            request = rx.recv() => match request {
+                Some(ReportRequest::OnDemand) => {
+                    deliver_report("on-demand", ReportPeriod::all(), &config, &notifiers, &log_file_path).await;
+                }
+                Some(ReportRequest::AlertRaised) => {
+                    if !config.schedule.contains(&ReportSchedule::OnStorm) {
+                        continue;
+                    }
+                    let now = Instant::now();
+                    recent_alerts.push_back(now);
+                    while recent_alerts.front().is_some_and(|t| now.duration_since(*t) > storm_window) {
+                        recent_alerts.pop_front();
+                    }
+
+                    // One storm report per window, so a long storm does not flood the channel
+                    let cooled_down = last_storm_report.is_none_or(|t| now.duration_since(t) > storm_window);
+                    if recent_alerts.len() >= config.storm_threshold.max(1) && cooled_down {
+                        last_storm_report = Some(now);
+                        let period = ReportPeriod::since(now_secs().saturating_sub(config.storm_window_secs));
+                        deliver_report("storm", period, &config, &notifiers, &log_file_path).await;
+                    }
+                }
+                None => return,
+            },
+            _ = sleep_until_timestamp(next_end) => {
+                // Hourly, daily and weekly periods can all end at the same midnight
+                for (schedule, period) in take_due_periods(&mut period_ends, Local::now()) {
+                    deliver_report(schedule.label(), period, &config, &notifiers, &log_file_path).await;
+                }
+            }
+        }
+    }
+}
+
+/// End of the current period of every enabled time-based schedule.
+fn scheduled_period_ends(schedules: &[ReportSchedule], now: DateTime<Local>) -> Vec<(ReportSchedule, u64)> {
+    schedules
+        .iter()
+        .filter_map(|&schedule| Some((schedule, schedule.period_containing(now)?.end)))
+        .collect()
+}
+
+/// The last complete period of every schedule whose tracked period has ended
+/// by `now`, moving those schedules on to the period containing `now`.
+fn take_due_periods(
+    period_ends: &mut [(ReportSchedule, u64)],
+    now: DateTime<Local>,
+) -> Vec<(ReportSchedule, ReportPeriod)> {
+    let now_secs = now.timestamp().max(0) as u64;
+    let mut due = Vec::new();
+    for (schedule, end) in period_ends.iter_mut().filter(|(_, end)| *end <= now_secs) {
+        if let (Some(previous), Some(current)) = (schedule.previous_period(now), schedule.period_containing(now)) {
+            due.push((*schedule, previous));
+            *end = current.end;
+        }
+    }
+    due
+}
+
+async fn sleep_until_timestamp(timestamp: Option<u64>) {
+    match timestamp {
+        Some(timestamp) => tokio::time::sleep(Duration::from_secs(timestamp.saturating_sub(now_secs()).max(1))).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Generates a fresh report over the entries within `period`, compared with the
+/// previous period, archives it and sends it to the report destinations. Failures are logged and never affect
+/// alert delivery.
+pub async fn deliver_report(
+    label: &str,
+    period: ReportPeriod,
+    config: &ReportDeliveryConfig,
+    notifiers: &[Box<dyn Notifier>],
+    log_file_path: &str,
+) {
+    let entries = match read_log_file(log_file_path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Error reading log entries for {} report: {}", label, e);
+            return;
+        }
+    };
+
+    let mut report = Report::new(&entries, period);
+    compare_with_previous(&mut report, period, &entries, &config.comparison);
+    let archive = ReportArchive::new(config.archive.clone());
+    let caption = format!("LogSync {} report: {} entries", label, report.entries.len());
+    let destinations: Vec<&dyn Notifier> = notifiers
+        .iter()
+        .filter(|n| config.destinations.is_empty() || config.destinations.iter().any(|d| d == n.name()))
+        .map(|n| n.as_ref())
+        .collect();
+
+    let mut files = Vec::new();
+    let mut delivered_to: Vec<String> = Vec::new();
+    for &format in &config.formats {
+        let path = match write_report(&report, format, &config.html) {
+            Ok(path) => path,
+            Err(e) => {
+                eprintln!("Error generating {} report ({}): {}", label, format.extension(), e);
+                continue;
+            }
+        };
+        // The archived copy keeps its name after the next report replaces `path`
+        let path = match archive.store(label, &path, report.generated_at) {
+            Ok(archived) => {
+                files.push(archived.file_name().unwrap_or_default().to_string_lossy().into_owned());
+                archived
+            }
+            Err(e) => {
+                eprintln!("Error archiving {} report: {}", label, e);
+                path
+            }
+        };
+
+        for notifier in &destinations {
+            let result = if format == ReportFormat::Text {
+                match std::fs::read_to_string(&path) {
+                    Ok(digest) => notifier.notify(&digest, None).await,
+                    Err(e) => Err(e.into()),
+                }
+            } else {
+                notifier.send_report(&path, &caption).await
+            };
+            match result {
+                Ok(()) => {
+                    println!("Report ({}) sent successfully to {}.", format.extension(), notifier.name());
+                    if !delivered_to.iter().any(|name| name == notifier.name()) {
+                        delivered_to.push(notifier.name().to_string());
+                    }
+                }
+                Err(e) => eprintln!("Error sending report ({}) to {}: {}", format.extension(), notifier.name(), e),
+            }
+        }
+    }
+
+    if files.is_empty() {
+        return;
+    }
+    let record = ReportRecord {
+        files,
+        kind: label.to_string(),
+        period: report.period,
+        generated_at: report.generated_at,
+        entries: report.entries.len(),
+        delivered_to,
+    };
+    if let Err(e) = archive.record(record) {
+        eprintln!("Error updating the report index: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn delivers_every_schedule_ending_at_midnight() {
+        let schedules = [ReportSchedule::Hourly, ReportSchedule::Daily, ReportSchedule::Weekly, ReportSchedule::OnStorm];
+        // The last second of a Sunday, then the midnight ending the hour, day and week
+        let sunday_night = Local.with_ymd_and_hms(2026, 10, 18, 23, 59, 59).single().unwrap();
+        let midnight = Local.with_ymd_and_hms(2026, 10, 19, 0, 0, 0).single().unwrap();
+
+        let mut period_ends = scheduled_period_ends(&schedules, sunday_night);
+        assert_eq!(period_ends.len(), 3);
+        assert!(period_ends.iter().all(|&(_, end)| end == midnight.timestamp() as u64));
+        assert!(take_due_periods(&mut period_ends, sunday_night).is_empty());
+
+        let due = take_due_periods(&mut period_ends, midnight);
+        assert_eq!(due.len(), 3);
+        for (schedule, period) in due {
+            assert_eq!(Some(period), schedule.previous_period(midnight));
+            assert_eq!(Some(period), schedule.period_containing(sunday_night));
+        }
+
+        assert!(take_due_periods(&mut period_ends, midnight + ChronoDuration::minutes(30)).is_empty());
+        let due = take_due_periods(&mut period_ends, midnight + ChronoDuration::hours(1));
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].0, ReportSchedule::Hourly);
+    }
+}
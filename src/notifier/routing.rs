@@ -0,0 +1,272 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::NotifyError;
+use crate::types::LogEntry;
+
+/// A routing rule as declared in the configuration file.
+///
+/// Every condition that is set must match (lists match if any element does).
+/// When `continue` is false, evaluation stops at the first matching rule.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RouteConfig {
+    #[serde(default, rename = "match")]
+    pub matcher: RouteMatcher,
+    pub destinations: Vec<String>,
+    #[serde(default, rename = "continue")]
+    pub continue_matching: bool,
+}
+
+/// Conditions on an entry, shared by routes and silences.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RouteMatcher {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub priority: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub log_type: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub source: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_regex: Option<String>,
+}
+
+impl RouteMatcher {
+    pub fn is_empty(&self) -> bool {
+        self.priority.is_empty()
+            && self.log_type.is_empty()
+            && self.source.is_empty()
+            && self.tags.is_empty()
+            && self.message_regex.is_none()
+    }
+
+    /// Short human-readable form, e.g. `source=postgres type=ERROR`.
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        for (name, values) in [
+            ("priority", &self.priority),
+            ("type", &self.log_type),
+            ("source", &self.source),
+            ("tag", &self.tags),
+        ] {
+            if !values.is_empty() {
+                parts.push(format!("{}={}", name, values.join("|")));
+            }
+        }
+        if let Some(regex) = &self.message_regex {
+            parts.push(format!("message=/{}/", regex));
+        }
+        if parts.is_empty() {
+            "everything".to_string()
+        } else {
+            parts.join(" ")
+        }
+    }
+}
+
+/// A `RouteMatcher` with its message regex compiled.
+#[derive(Debug, Clone)]
+pub struct EntryMatcher {
+    matcher: RouteMatcher,
+    message_regex: Option<Regex>,
+}
+
+impl EntryMatcher {
+    pub fn new(matcher: &RouteMatcher) -> Result<Self, String> {
+        let message_regex = matcher
+            .message_regex
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| format!("Invalid message_regex: {}", e))?;
+
+        Ok(Self {
+            matcher: matcher.clone(),
+            message_regex,
+        })
+    }
+
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        let m = &self.matcher;
+        let any_eq = |values: &[String], actual: &str| {
+            values.is_empty() || values.iter().any(|v| v.eq_ignore_ascii_case(actual))
+        };
+
+        any_eq(&m.priority, &entry.priority)
+            && any_eq(&m.log_type, &entry.log_type)
+            && (m.source.is_empty() || entry.source.as_deref().is_some_and(|s| any_eq(&m.source, s)))
+            && (m.tags.is_empty() || entry.tags.iter().any(|tag| any_eq(&m.tags, tag)))
+            && self.message_regex.as_ref().is_none_or(|re| re.is_match(&entry.message))
+    }
+}
+
+struct Route {
+    matcher: EntryMatcher,
+    destinations: Vec<String>,
+    continue_matching: bool,
+}
+
+/// Resolves which named destinations an entry is delivered to.
+pub struct Router {
+    routes: Vec<Route>,
+    default_route: Vec<String>,
+    disabled: Vec<String>,
+}
+
+impl Router {
+    /// Compiles the rules and checks that every destination names a configured channel.
+    /// Destinations in `disabled` are accepted but never returned by `route`.
+    ///
+    /// Without any rules or default route, entries go to every enabled channel.
+    pub fn new(
+        routes: &[RouteConfig],
+        default_route: &[String],
+        channel_names: &[&str],
+        disabled: &[&str],
+    ) -> Result<Self, NotifyError> {
+        let check = |name: &String| {
+            if channel_names.contains(&name.as_str()) || disabled.contains(&name.as_str()) {
+                Ok(())
+            } else {
+                Err(format!("Route refers to unknown channel '{}'", name))
+            }
+        };
+
+        let compiled = routes
+            .iter()
+            .map(|route| {
+                route.destinations.iter().try_for_each(check)?;
+                let matcher = EntryMatcher::new(&route.matcher)
+                    .map_err(|e| format!("{} in route", e))?;
+
+                Ok(Route {
+                    matcher,
+                    destinations: route.destinations.clone(),
+                    continue_matching: route.continue_matching,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        default_route.iter().try_for_each(check)?;
+
+        let default_route = if routes.is_empty() && default_route.is_empty() {
+            channel_names.iter().map(|name| name.to_string()).collect()
+        } else {
+            default_route.to_vec()
+        };
+
+        Ok(Self {
+            routes: compiled,
+            default_route,
+            disabled: disabled.iter().map(|name| name.to_string()).collect(),
+        })
+    }
+
+    /// Returns the destinations for `entry`, without duplicates, in rule order.
+    pub fn route(&self, entry: &LogEntry) -> Vec<String> {
+        let mut destinations: Vec<String> = Vec::new();
+        let mut matched = false;
+
+        for route in &self.routes {
+            if !route.matcher.matches(entry) {
+                continue;
+            }
+            matched = true;
+            for destination in &route.destinations {
+                if !destinations.contains(destination) {
+                    destinations.push(destination.clone());
+                }
+            }
+            if !route.continue_matching {
+                break;
+            }
+        }
+
+        if !matched {
+            destinations = self.default_route.clone();
+        }
+        destinations.retain(|destination| !self.disabled.contains(destination));
+        destinations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::test_support::log_entry;
+
+    const CHANNELS: [&str; 5] = ["ops", "db-team", "audit", "pager", "mail"];
+
+    fn compile(routes: &str, default_route: &[&str]) -> Router {
+        let routes: Vec<RouteConfig> = serde_json::from_str(routes).unwrap();
+        let default_route: Vec<String> = default_route.iter().map(|name| name.to_string()).collect();
+        Router::new(&routes, &default_route, &CHANNELS, &[]).unwrap()
+    }
+
+    fn entry(priority: &str, source: &str) -> LogEntry {
+        let mut entry = log_entry("connection refused");
+        entry.priority = priority.to_string();
+        entry.source = Some(source.to_string());
+        entry
+    }
+
+    #[test]
+    fn stops_at_the_first_matching_route() {
+        let router = compile(
+            r#"[
+                {"match": {"source": ["postgres"]}, "destinations": ["db-team"]},
+                {"match": {"priority": ["very high"]}, "destinations": ["pager"]}
+            ]"#,
+            &["ops"],
+        );
+
+        assert_eq!(router.route(&entry("very high", "postgres")), ["db-team"]);
+        assert_eq!(router.route(&entry("very high", "api")), ["pager"]);
+    }
+
+    #[test]
+    fn continue_fans_out_to_later_routes_without_duplicates() {
+        let router = compile(
+            r#"[
+                {"match": {"tags": ["db"]}, "destinations": ["audit"], "continue": true},
+                {"match": {"source": ["postgres"]}, "destinations": ["db-team", "audit"], "continue": true},
+                {"match": {"priority": ["very high"]}, "destinations": ["pager"]},
+                {"match": {}, "destinations": ["mail"]}
+            ]"#,
+            &["ops"],
+        );
+
+        assert_eq!(router.route(&entry("very high", "postgres")), ["audit", "db-team", "pager"]);
+        assert_eq!(router.route(&entry("low", "postgres")), ["audit", "db-team", "mail"]);
+    }
+
+    #[test]
+    fn unmatched_entries_use_the_default_route() {
+        let router = compile(
+            r#"[{"match": {"source": ["postgres"]}, "destinations": ["db-team"]}]"#,
+            &["ops", "mail"],
+        );
+        assert_eq!(router.route(&entry("high", "api")), ["ops", "mail"]);
+
+        // A route matching with `continue` still counts as a match
+        let router = compile(
+            r#"[{"match": {"source": ["api"]}, "destinations": ["audit"], "continue": true}]"#,
+            &["ops"],
+        );
+        assert_eq!(router.route(&entry("high", "api")), ["audit"]);
+        assert_eq!(router.route(&entry("high", "web")), ["ops"]);
+
+        // Without routes or a default route, entries go everywhere
+        assert_eq!(compile("[]", &[]).route(&entry("high", "api")), CHANNELS);
+    }
+
+    #[test]
+    fn disabled_and_unknown_destinations() {
+        let routes: Vec<RouteConfig> =
+            serde_json::from_str(r#"[{"match": {}, "destinations": ["ops", "slack"]}]"#).unwrap();
+
+        let router = Router::new(&routes, &[], &["ops"], &["slack"]).unwrap();
+        assert_eq!(router.route(&entry("high", "api")), ["ops"]);
+        assert!(Router::new(&routes, &[], &["ops"], &[]).is_err());
+    }
+}
@@ -0,0 +1,358 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use super::routing::{EntryMatcher, RouteMatcher};
+use crate::cron::CronSchedule;
+use crate::types::LogEntry;
+use crate::utils::{format_rfc3339, generate_entry_id, now_secs, parse_duration_secs, parse_time, write_atomically};
+
+/// Suppresses notifications for matching entries during a time window.
+///
+/// A silence needs an `ends_at` time, a recurring `schedule` (a cron expression
+/// marking when each window starts) with a `duration`, or both.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Silence {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default, rename = "match")]
+    pub matcher: RouteMatcher,
+    /// RFC 3339 or local `YYYY-MM-DD HH:MM`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub starts_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ends_at: Option<String>,
+    /// e.g. `0 2 * * 0` for a window every Sunday at 02:00 local time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<String>,
+    /// Length of each scheduled window, e.g. `2h`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+}
+
+impl Silence {
+    /// One-line description of the matcher and window.
+    pub fn describe(&self) -> String {
+        let mut window = Vec::new();
+        if let Some(starts_at) = &self.starts_at {
+            window.push(format!("from {}", starts_at));
+        }
+        if let Some(ends_at) = &self.ends_at {
+            window.push(format!("until {}", ends_at));
+        }
+        if let Some(schedule) = &self.schedule {
+            window.push(format!(
+                "every '{}' for {}",
+                schedule,
+                self.duration.as_deref().unwrap_or("?")
+            ));
+        }
+
+        let mut description = format!("{} [{}]", self.matcher.describe(), window.join(", "));
+        if let Some(comment) = &self.comment {
+            description.push_str(&format!(" - {}", comment));
+        }
+        description
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CompiledSilence {
+    silence: Silence,
+    matcher: EntryMatcher,
+    starts_at: Option<u64>,
+    ends_at: Option<u64>,
+    schedule: Option<(CronSchedule, u64)>,
+}
+
+impl CompiledSilence {
+    fn compile(silence: Silence) -> Result<Self, String> {
+        let time = |value: &Option<String>, field: &str| {
+            value
+                .as_deref()
+                .map(|text| parse_time(text).ok_or_else(|| format!("Invalid {} '{}'", field, text)))
+                .transpose()
+        };
+        let starts_at = time(&silence.starts_at, "starts_at")?;
+        let ends_at = time(&silence.ends_at, "ends_at")?;
+
+        let schedule = match (&silence.schedule, &silence.duration) {
+            (Some(expression), Some(duration)) => {
+                let duration = parse_duration_secs(duration)
+                    .filter(|&secs| secs > 0 && secs <= 7 * 86400)
+                    .ok_or_else(|| format!("Invalid silence duration '{}' (at most 7d)", duration))?;
+                Some((expression.parse::<CronSchedule>()?, duration))
+            }
+            (Some(_), None) => return Err("A scheduled silence needs a duration".to_string()),
+            (None, _) => None,
+        };
+        if ends_at.is_none() && schedule.is_none() {
+            return Err("A silence needs an ends_at time or a schedule".to_string());
+        }
+
+        Ok(Self {
+            matcher: EntryMatcher::new(&silence.matcher)?,
+            silence,
+            starts_at,
+            ends_at,
+            schedule,
+        })
+    }
+
+    fn is_active_at(&self, now: u64) -> bool {
+        self.starts_at.is_none_or(|starts_at| starts_at <= now)
+            && self.ends_at.is_none_or(|ends_at| now < ends_at)
+            && self.schedule.as_ref().is_none_or(|(schedule, duration)| {
+                chrono::DateTime::from_timestamp(now as i64, 0)
+                    .is_some_and(|time| schedule.is_within(time.with_timezone(&chrono::Local), *duration))
+            })
+    }
+
+    fn is_expired_at(&self, now: u64) -> bool {
+        self.ends_at.is_some_and(|ends_at| ends_at <= now)
+    }
+}
+
+struct RuntimeSilences {
+    modified: Option<SystemTime>,
+    silences: Vec<CompiledSilence>,
+    /// Entries of the silences file that failed to compile; never applied, but
+    /// written back so a bad edit does not lose them.
+    invalid: Vec<Silence>,
+}
+
+/// Silences from the configuration file plus those added at runtime (CLI or
+/// viewer). Runtime silences are persisted and re-read whenever the file
+/// changes, so a running monitor picks up silences added by `logsync silence`.
+#[derive(Clone)]
+pub struct SilenceList {
+    path: PathBuf,
+    configured: Arc<Vec<CompiledSilence>>,
+    runtime: Arc<Mutex<RuntimeSilences>>,
+}
+
+impl SilenceList {
+    pub fn open(path: &Path, configured: &[Silence]) -> Result<Self, Box<dyn std::error::Error>> {
+        let configured = configured
+            .iter()
+            .enumerate()
+            .map(|(index, silence)| {
+                let mut silence = silence.clone();
+                if silence.id.is_empty() {
+                    silence.id = format!("config-{}", index + 1);
+                }
+                let id = silence.id.clone();
+                CompiledSilence::compile(silence).map_err(|e| format!("Silence {}: {}", id, e))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let list = Self {
+            path: path.to_path_buf(),
+            configured: Arc::new(configured),
+            runtime: Arc::new(Mutex::new(RuntimeSilences {
+                modified: None,
+                silences: Vec::new(),
+                invalid: Vec::new(),
+            })),
+        };
+        list.reload(&mut list.runtime.lock().unwrap())?;
+        Ok(list)
+    }
+
+    /// Validates and stores a runtime silence, returning it with its id.
+    pub fn add(&self, mut silence: Silence) -> Result<Silence, Box<dyn std::error::Error>> {
+        silence.id = generate_entry_id();
+        let compiled = CompiledSilence::compile(silence.clone())?;
+
+        let mut runtime = self.runtime.lock().unwrap();
+        self.refresh(&mut runtime);
+        let now = now_secs();
+        runtime.silences.retain(|s| !s.is_expired_at(now));
+        runtime.silences.push(compiled);
+        self.persist(&mut runtime)?;
+        Ok(silence)
+    }
+
+    /// Removes a runtime silence. Returns `false` if no such silence exists;
+    /// silences from the configuration file cannot be removed.
+    pub fn remove(&self, id: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        if self.configured.iter().any(|s| s.silence.id == id) {
+            return Err(format!("Silence {} is defined in the configuration file", id).into());
+        }
+
+        let mut runtime = self.runtime.lock().unwrap();
+        self.refresh(&mut runtime);
+        let before = runtime.silences.len() + runtime.invalid.len();
+        runtime.silences.retain(|s| s.silence.id != id);
+        runtime.invalid.retain(|s| s.id != id);
+        if runtime.silences.len() + runtime.invalid.len() == before {
+            return Ok(false);
+        }
+        self.persist(&mut runtime)?;
+        Ok(true)
+    }
+
+    /// Removes every runtime silence currently suppressing `entry`, returning how many.
+    pub fn remove_matching(&self, entry: &LogEntry) -> Result<usize, Box<dyn std::error::Error>> {
+        let now = now_secs();
+        let mut runtime = self.runtime.lock().unwrap();
+        self.refresh(&mut runtime);
+        let before = runtime.silences.len();
+        runtime
+            .silences
+            .retain(|s| !(s.is_active_at(now) && s.matcher.matches(entry)));
+        let removed = before - runtime.silences.len();
+        if removed > 0 {
+            self.persist(&mut runtime)?;
+        }
+        Ok(removed)
+    }
+
+    /// Every silence that has not expired, with whether it is active right now.
+    pub fn list(&self) -> Vec<(Silence, bool)> {
+        let now = now_secs();
+        let mut runtime = self.runtime.lock().unwrap();
+        self.refresh(&mut runtime);
+
+        self.configured
+            .iter()
+            .chain(runtime.silences.iter())
+            .filter(|s| !s.is_expired_at(now))
+            .map(|s| (s.silence.clone(), s.is_active_at(now)))
+            .collect()
+    }
+
+    /// The silence currently suppressing notifications for `entry`, if any.
+    pub fn silencing(&self, entry: &LogEntry) -> Option<Silence> {
+        let now = now_secs();
+        let mut runtime = self.runtime.lock().unwrap();
+        self.refresh(&mut runtime);
+
+        self.configured
+            .iter()
+            .chain(runtime.silences.iter())
+            .find(|s| s.is_active_at(now) && s.matcher.matches(entry))
+            .map(|s| s.silence.clone())
+    }
+
+    fn refresh(&self, runtime: &mut RuntimeSilences) {
+        if let Err(e) = self.reload(runtime) {
+            eprintln!("Error reloading silences file: {}", e);
+        }
+    }
+
+    fn reload(&self, runtime: &mut RuntimeSilences) -> Result<(), Box<dyn std::error::Error>> {
+        let modified = std::fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        if modified.is_none() || modified == runtime.modified {
+            return Ok(());
+        }
+
+        let content = std::fs::read_to_string(&self.path)?;
+        let silences: Vec<Silence> = if content.trim().is_empty() {
+            Vec::new()
+        } else {
+            serde_json::from_str(&content)
+                .map_err(|e| format!("Invalid silences file {}: {}", self.path.display(), e))?
+        };
+        runtime.silences.clear();
+        runtime.invalid.clear();
+        for silence in silences {
+            match CompiledSilence::compile(silence.clone()) {
+                Ok(compiled) => runtime.silences.push(compiled),
+                Err(e) => {
+                    eprintln!("Ignoring silence {} in {}: {}", silence.id, self.path.display(), e);
+                    runtime.invalid.push(silence);
+                }
+            }
+        }
+        runtime.modified = modified;
+        Ok(())
+    }
+
+    fn persist(&self, runtime: &mut RuntimeSilences) -> std::io::Result<()> {
+        let silences: Vec<&Silence> = runtime
+            .silences
+            .iter()
+            .map(|s| &s.silence)
+            .chain(runtime.invalid.iter())
+            .collect();
+        let content = serde_json::to_vec_pretty(&silences)?;
+        write_atomically(&self.path, &content)?;
+        runtime.modified = std::fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        Ok(())
+    }
+}
+
+/// Builds a silence for entries like `entry` (same source and type, or the
+/// same message when the source is unknown) lasting `duration_secs`.
+pub fn silence_like(entry: &LogEntry, duration_secs: u64, created_by: &str) -> Silence {
+    let mut matcher = RouteMatcher {
+        log_type: vec![entry.log_type.clone()],
+        ..RouteMatcher::default()
+    };
+    match &entry.source {
+        Some(source) => matcher.source = vec![source.clone()],
+        None => matcher.message_regex = Some(format!("^{}$", regex::escape(&entry.message))),
+    }
+
+    Silence {
+        matcher,
+        ends_at: Some(format_rfc3339(now_secs().saturating_add(duration_secs))),
+        created_by: Some(created_by.to_string()),
+        ..Silence::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::test_support::log_entry;
+
+    #[test]
+    fn huge_durations_silence_until_the_end_of_time() {
+        let mut entry = log_entry("disk full");
+        entry.source = Some("postgres".to_string());
+        let silence = CompiledSilence::compile(silence_like(&entry, u64::MAX, "test")).unwrap();
+
+        assert!(silence.silence.ends_at.as_deref().unwrap().starts_with("9999-12-"));
+        assert!(silence.is_active_at(now_secs()));
+        assert!(!silence.is_expired_at(now_secs() + 100 * 365 * 86400));
+    }
+
+    #[test]
+    fn invalid_runtime_silences_are_skipped_and_kept() {
+        let path = std::env::temp_dir().join(format!("logsync-silences-invalid-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"[
+                {"id": "good", "match": {"source": ["api"]}, "ends_at": "9999-01-01T00:00:00Z"},
+                {"id": "bad", "match": {"source": ["db"]}, "schedule": "0 25 * * *", "duration": "1h"}
+            ]"#,
+        )
+        .unwrap();
+
+        let list = SilenceList::open(&path, &[]).unwrap();
+        let listed: Vec<String> = list.list().into_iter().map(|(s, _)| s.id).collect();
+        let added = list
+            .add(Silence {
+                ends_at: Some("9999-01-01T00:00:00Z".to_string()),
+                ..Silence::default()
+            })
+            .unwrap();
+        let stored: Vec<Silence> = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let removed = list.remove("bad").unwrap();
+        let remaining: Vec<Silence> = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(listed, ["good"]);
+        let stored: Vec<&str> = stored.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(stored, ["good", added.id.as_str(), "bad"]);
+        assert!(removed);
+        assert_eq!(remaining.len(), 2);
+    }
+}
@@ -0,0 +1,75 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{post_json, NotifyError, Notifier};
+use crate::types::LogEntry;
+
+/// Slack incoming webhook.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SlackConfig {
+    pub webhook_url: String,
+}
+
+pub struct SlackNotifier {
+    name: String,
+    config: SlackConfig,
+    client: reqwest::Client,
+}
+
+impl SlackNotifier {
+    pub fn new(name: String, config: SlackConfig) -> Self {
+        Self {
+            name,
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for SlackNotifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn notify(&self, message: &str, _entry: Option<&LogEntry>) -> Result<(), NotifyError> {
+        post_json(&self.client, &self.config.webhook_url, &json!({ "text": message })).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::test_support::{log_entry, MockServer};
+    use hyper::StatusCode;
+
+    fn notifier(url: &str) -> SlackNotifier {
+        let config = SlackConfig {
+            webhook_url: format!("{}/services/T000/B000/XXXX", url),
+        };
+        SlackNotifier::new("slack".to_string(), config)
+    }
+
+    #[tokio::test]
+    async fn posts_the_message_as_text() {
+        let server = MockServer::ok("ok").await;
+        let entry = log_entry("disk full");
+
+        notifier(&server.url).notify("🔴 ERROR: disk full", Some(&entry)).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/services/T000/B000/XXXX");
+        assert_eq!(requests[0].json(), json!({ "text": "🔴 ERROR: disk full" }));
+    }
+
+    #[tokio::test]
+    async fn reports_error_responses() {
+        let server = MockServer::start(|_| (StatusCode::FORBIDDEN, "invalid_token".to_string())).await;
+
+        let error = notifier(&server.url).notify("x", None).await.unwrap_err();
+
+        assert_eq!(error.to_string(), "HTTP 403 Forbidden: invalid_token");
+    }
+}
@@ -0,0 +1,110 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{post_json, MessageFormat, NotifyError, Notifier};
+use crate::types::LogEntry;
+
+/// Microsoft Teams incoming webhook.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TeamsConfig {
+    pub webhook_url: String,
+    #[serde(default = "default_title")]
+    pub title: String,
+}
+
+fn default_title() -> String {
+    "LogSync alert".to_string()
+}
+
+pub struct TeamsNotifier {
+    name: String,
+    config: TeamsConfig,
+    client: reqwest::Client,
+}
+
+impl TeamsNotifier {
+    pub fn new(name: String, config: TeamsConfig) -> Self {
+        Self {
+            name,
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for TeamsNotifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Teams renders the card text as Markdown.
+    fn format(&self) -> MessageFormat {
+        MessageFormat::Markdown
+    }
+
+    async fn notify(&self, message: &str, _entry: Option<&LogEntry>) -> Result<(), NotifyError> {
+        let body = json!({
+            "@type": "MessageCard",
+            "@context": "http://schema.org/extensions",
+            "summary": self.config.title,
+            "title": self.config.title,
+            "text": message
+        });
+
+        post_json(&self.client, &self.config.webhook_url, &body).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::test_support::{log_entry, MockServer};
+    use crate::notifier::TemplatedNotifier;
+
+    fn notifier(url: &str) -> TeamsNotifier {
+        let config = TeamsConfig {
+            webhook_url: format!("{}/webhookb2/abc", url),
+            title: default_title(),
+        };
+        TeamsNotifier::new("teams".to_string(), config)
+    }
+
+    #[tokio::test]
+    async fn posts_a_message_card() {
+        let server = MockServer::ok("1").await;
+
+        notifier(&server.url).notify("disk full", None).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/webhookb2/abc");
+        assert_eq!(
+            requests[0].json(),
+            json!({
+                "@type": "MessageCard",
+                "@context": "http://schema.org/extensions",
+                "summary": "LogSync alert",
+                "title": "LogSync alert",
+                "text": "disk full"
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn escapes_markdown_in_log_text() {
+        let server = MockServer::ok("1").await;
+        let entry = log_entry("**not bold** [link](http://evil.example) <b>");
+        let templated = TemplatedNotifier::new(Box::new(notifier(&server.url)), Some("{{priority_label}}: {{message}}".to_string()));
+
+        templated.notify("ignored", Some(&entry)).await.unwrap();
+        templated.notify("digest_with *stars*", None).await.unwrap();
+
+        let texts: Vec<serde_json::Value> = server.requests().iter().map(|r| r.json()["text"].clone()).collect();
+        assert_eq!(
+            texts[0],
+            "HIGH: \\*\\*not bold\\*\\* \\[link\\]\\(http://evil\\.example\\) \\<b\\>"
+        );
+        assert_eq!(texts[1], "digest\\_with \\*stars\\*");
+    }
+}
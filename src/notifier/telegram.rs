@@ -0,0 +1,320 @@
+use async_trait::async_trait;
+use reqwest::multipart;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::env;
+use std::path::Path;
+use std::time::Duration;
+
+use super::{check_response, post_json, MessageFormat, NotifyError, Notifier, HTTP_TIMEOUT};
+use crate::secrets::env_secret;
+use crate::types::LogEntry;
+
+const DEFAULT_API_BASE: &str = "https://api.telegram.org";
+
+/// Telegram rejects messages longer than this many characters.
+pub const TELEGRAM_MAX_MESSAGE_LEN: usize = 4096;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TelegramConfig {
+    pub token: String,
+    pub chat_id: String,
+    /// Base URL of the Bot API, overridable to point at a local mock server.
+    #[serde(default = "default_api_base")]
+    pub api_base: String,
+    /// Parse mode for messages (`plain`, `markdownv2` or `html`).
+    #[serde(default)]
+    pub format: MessageFormat,
+    /// Attach "Acknowledge"/"Mute" buttons to alerts (handled by the bot).
+    #[serde(default)]
+    pub buttons: bool,
+}
+
+fn default_api_base() -> String {
+    DEFAULT_API_BASE.to_string()
+}
+
+impl TelegramConfig {
+    /// Reads `TELEGRAM_API_TOKEN`, `TELEGRAM_CHAT_ID` (either may also come from
+    /// a `*_FILE` variable or a systemd credential) and the optional
+    /// `TELEGRAM_API_BASE`, `TELEGRAM_PARSE_MODE` and `TELEGRAM_BUTTONS`.
+    ///
+    /// Returns `None` when neither is set, and an error when only one is or a
+    /// secret file cannot be used.
+    pub fn from_env() -> Result<Option<Self>, String> {
+        let token = env_secret("TELEGRAM_API_TOKEN").map_err(|e| e.to_string())?;
+        let chat_id = env_secret("TELEGRAM_CHAT_ID").map_err(|e| e.to_string())?;
+        let (token, chat_id) = match (token, chat_id) {
+            (Some(token), Some(chat_id)) => (token, chat_id),
+            (None, None) => return Ok(None),
+            (Some(_), None) => return Err("TELEGRAM_CHAT_ID is not set".to_string()),
+            (None, Some(_)) => return Err("TELEGRAM_API_TOKEN is not set".to_string()),
+        };
+
+        Ok(Some(Self {
+            token,
+            chat_id,
+            api_base: env::var("TELEGRAM_API_BASE").unwrap_or_else(|_| default_api_base()),
+            format: match env::var("TELEGRAM_PARSE_MODE").map(|v| v.to_lowercase()).as_deref() {
+                Ok("markdownv2") => MessageFormat::MarkdownV2,
+                Ok("html") => MessageFormat::Html,
+                _ => MessageFormat::Plain,
+            },
+            buttons: crate::config::env_flag("TELEGRAM_BUTTONS").unwrap_or(false),
+        }))
+    }
+}
+
+pub struct TelegramNotifier {
+    name: String,
+    config: TelegramConfig,
+    client: reqwest::Client,
+}
+
+impl TelegramNotifier {
+    pub fn new(name: String, config: TelegramConfig) -> Self {
+        Self {
+            name,
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn method_url(&self, method: &str) -> String {
+        format!(
+            "{}/bot{}/{}",
+            self.config.api_base.trim_end_matches('/'),
+            self.config.token,
+            method
+        )
+    }
+
+    pub fn config(&self) -> &TelegramConfig {
+        &self.config
+    }
+
+    pub async fn send_message(&self, text: &str) -> Result<(), NotifyError> {
+        self.send_message_with_markup(&self.config.chat_id, text, None).await
+    }
+
+    pub async fn send_message_with_markup(
+        &self,
+        chat_id: &str,
+        text: &str,
+        reply_markup: Option<Value>,
+    ) -> Result<(), NotifyError> {
+        let mut body = json!({
+            "chat_id": chat_id,
+            "text": text
+        });
+        match self.config.format {
+            MessageFormat::MarkdownV2 => body["parse_mode"] = json!("MarkdownV2"),
+            MessageFormat::Html => body["parse_mode"] = json!("HTML"),
+            // Rejected by `ChannelConfig::validate`; Telegram has no standard Markdown mode
+            MessageFormat::Plain | MessageFormat::Markdown => {}
+        }
+        if let Some(markup) = reply_markup {
+            body["reply_markup"] = markup;
+        }
+
+        post_json(&self.client, &self.method_url("sendMessage"), &body).await
+    }
+
+    /// Calls a Bot API method and returns its `result` field.
+    pub async fn call(&self, method: &str, body: &Value, timeout: Duration) -> Result<Value, NotifyError> {
+        let response = self
+            .client
+            .post(self.method_url(method))
+            .json(body)
+            .timeout(timeout)
+            .send()
+            .await?;
+
+        let status = response.status();
+        let payload: Value = response.json().await?;
+        if !status.is_success() || payload["ok"] != json!(true) {
+            return Err(format!("Telegram {} failed ({}): {}", method, status, payload).into());
+        }
+        Ok(payload["result"].clone())
+    }
+
+    pub async fn send_document(&self, path: &Path, caption: &str) -> Result<(), NotifyError> {
+        // Check if the file exists
+        if !path.exists() {
+            return Err(format!("File not found: {}", path.display()).into());
+        }
+
+        let file_content = tokio::fs::read(path).await?;
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.display().to_string());
+
+        let form = multipart::Form::new()
+            .part("chat_id", multipart::Part::text(self.config.chat_id.clone()))
+            .part("caption", multipart::Part::text(caption.to_string()))
+            .part("document", multipart::Part::bytes(file_content).file_name(file_name));
+
+        let response = self
+            .client
+            .post(self.method_url("sendDocument"))
+            .multipart(form)
+            .timeout(HTTP_TIMEOUT)
+            .send()
+            .await?;
+
+        check_response(response).await
+    }
+}
+
+#[async_trait]
+impl Notifier for TelegramNotifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn format(&self) -> MessageFormat {
+        self.config.format
+    }
+
+    fn max_message_len(&self) -> Option<usize> {
+        Some(TELEGRAM_MAX_MESSAGE_LEN)
+    }
+
+    async fn notify(&self, message: &str, entry: Option<&LogEntry>) -> Result<(), NotifyError> {
+        let markup = entry
+            .filter(|_| self.config.buttons)
+            .map(alert_keyboard);
+        self.send_message_with_markup(&self.config.chat_id, message, markup).await
+    }
+
+    async fn send_report(&self, path: &Path, caption: &str) -> Result<(), NotifyError> {
+        self.send_document(path, caption).await
+    }
+}
+
+/// Inline keyboard with "Acknowledge" and "Mute 1h" buttons for an alert.
+pub fn alert_keyboard(entry: &LogEntry) -> Value {
+    json!({
+        "inline_keyboard": [[
+            { "text": "Acknowledge", "callback_data": format!("ack:{}", entry.id) },
+            { "text": "Mute 1h", "callback_data": format!("mute:{}:1h", entry.fingerprint) }
+        ]]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::test_support::{log_entry, MockServer};
+    use crate::notifier::TemplatedNotifier;
+    use hyper::StatusCode;
+
+    fn notifier(url: &str, format: MessageFormat, buttons: bool) -> TelegramNotifier {
+        let config = TelegramConfig {
+            token: "123:ABC".to_string(),
+            chat_id: "-10042".to_string(),
+            api_base: format!("{}/", url),
+            format,
+            buttons,
+        };
+        TelegramNotifier::new("telegram".to_string(), config)
+    }
+
+    #[tokio::test]
+    async fn sends_messages_with_parse_mode_and_buttons() {
+        let server = MockServer::ok(r#"{"ok": true, "result": {}}"#).await;
+        let entry = log_entry("disk full");
+
+        notifier(&server.url, MessageFormat::Plain, false).notify("plain", Some(&entry)).await.unwrap();
+        notifier(&server.url, MessageFormat::Html, true).notify("<b>bold</b>", Some(&entry)).await.unwrap();
+        notifier(&server.url, MessageFormat::MarkdownV2, true).notify("digest", None).await.unwrap();
+
+        let requests = server.requests();
+        assert!(requests.iter().all(|r| r.path == "/bot123:ABC/sendMessage"));
+        assert_eq!(requests[0].json(), json!({ "chat_id": "-10042", "text": "plain" }));
+        assert_eq!(
+            requests[1].json(),
+            json!({
+                "chat_id": "-10042",
+                "text": "<b>bold</b>",
+                "parse_mode": "HTML",
+                "reply_markup": { "inline_keyboard": [[
+                    { "text": "Acknowledge", "callback_data": "ack:e1" },
+                    { "text": "Mute 1h", "callback_data": "mute:3f2a9c1d0b7e4a55:1h" }
+                ]] }
+            })
+        );
+        // Digests cover several entries, so they get no buttons
+        assert_eq!(
+            requests[2].json(),
+            json!({ "chat_id": "-10042", "text": "digest", "parse_mode": "MarkdownV2" })
+        );
+    }
+
+    #[tokio::test]
+    async fn long_alerts_are_split_into_valid_messages() {
+        let server = MockServer::ok(r#"{"ok": true, "result": {}}"#).await;
+        let entry = log_entry(&"connection <reset> & retried\n".repeat(300));
+        let templated = TemplatedNotifier::new(Box::new(notifier(&server.url, MessageFormat::Html, false)), None);
+
+        templated.notify("ignored", Some(&entry)).await.unwrap();
+
+        let texts: Vec<String> = server
+            .requests()
+            .iter()
+            .map(|r| r.json()["text"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(texts.len(), 3);
+        assert!(texts.iter().all(|text| text.chars().count() <= TELEGRAM_MAX_MESSAGE_LEN));
+        assert_eq!(texts.concat().matches("connection &lt;reset&gt; &amp; retried").count(), 300);
+    }
+
+    #[tokio::test]
+    async fn uploads_reports_as_documents() {
+        let server = MockServer::ok(r#"{"ok": true, "result": {}}"#).await;
+        let path = std::env::temp_dir().join(format!("logsync-telegram-report-{}.html", std::process::id()));
+        std::fs::write(&path, "<html>report</html>").unwrap();
+
+        let result = notifier(&server.url, MessageFormat::Plain, false)
+            .send_report(&path, "LogSync daily report")
+            .await;
+        std::fs::remove_file(&path).unwrap();
+        result.unwrap();
+
+        let request = &server.requests()[0];
+        let body = String::from_utf8_lossy(&request.body);
+        assert_eq!(request.path, "/bot123:ABC/sendDocument");
+        assert!(request.headers["content-type"].to_str().unwrap().starts_with("multipart/form-data"));
+        assert!(body.contains("name=\"caption\"\r\n\r\nLogSync daily report"), "{}", body);
+        assert!(body.contains("<html>report</html>"));
+    }
+
+    #[tokio::test]
+    async fn call_surfaces_api_errors() {
+        let server = MockServer::start(|_| {
+            (StatusCode::BAD_REQUEST, r#"{"ok": false, "description": "Bad Request: chat not found"}"#.to_string())
+        })
+        .await;
+
+        let error = notifier(&server.url, MessageFormat::Plain, false)
+            .call("getMe", &json!({}), Duration::from_secs(5))
+            .await
+            .unwrap_err();
+
+        assert!(error.to_string().starts_with("Telegram getMe failed (400 Bad Request)"), "{}", error);
+    }
+
+    #[test]
+    fn rejects_the_standard_markdown_format() {
+        let channel = |format: &str| {
+            serde_json::from_value::<crate::notifier::ChannelConfig>(json!({
+                "name": "ops", "kind": "telegram", "token": "t", "chat_id": "1", "format": format
+            }))
+            .unwrap()
+        };
+
+        assert!(channel("markdownv2").validate().is_ok());
+        assert_eq!(channel("markdown").validate(), Err("'format' must be plain, markdownv2 or html".to_string()));
+    }
+}
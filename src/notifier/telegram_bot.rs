@@ -0,0 +1,503 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use super::incidents::{self, IncidentState, IncidentTracker};
+use super::outbox::Outbox;
+use super::reports::ReportHandle;
+use super::telegram::{TelegramConfig, TelegramNotifier, TELEGRAM_MAX_MESSAGE_LEN};
+use super::template::{split_message, MessageFormat};
+use super::MuteList;
+use crate::logger::read_log_file;
+use crate::types::LogEntry;
+use crate::utils::{format_timestamp, now_secs, parse_duration_secs};
+
+const HELP: &str = "Commands:
+/status - entries, pending alerts and active mutes
+/last [n] - the last n entries (default 5)
+/report - send a fresh report now
+/mute <fingerprint> <duration> - e.g. /mute 3f2a9c1d0b7e4a55 1h
+/unmute <fingerprint>
+/incidents - open and recently resolved incidents
+/ack <id|fingerprint> - acknowledge an entry or incident";
+
+/// Largest `n` accepted by `/last`.
+const MAX_LAST: usize = 50;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TelegramBotConfig {
+    /// Name of the Telegram channel whose token the bot uses.
+    #[serde(default = "default_channel")]
+    pub channel: String,
+    /// Chats allowed to issue commands; everything else is ignored.
+    pub allowed_chat_ids: Vec<String>,
+    #[serde(default = "default_poll_timeout_secs")]
+    pub poll_timeout_secs: u64,
+}
+
+fn default_channel() -> String {
+    "telegram".to_string()
+}
+
+fn default_poll_timeout_secs() -> u64 {
+    30
+}
+
+/// Answers commands and inline-button presses by long polling `getUpdates`.
+pub struct TelegramBot {
+    api: TelegramNotifier,
+    config: TelegramBotConfig,
+    log_file_path: String,
+    mutes: MuteList,
+    outbox: Outbox,
+    reports: Option<ReportHandle>,
+    incidents: Option<IncidentTracker>,
+}
+
+impl TelegramBot {
+    pub fn new(
+        telegram: TelegramConfig,
+        config: TelegramBotConfig,
+        log_file_path: &str,
+        mutes: MuteList,
+        outbox: Outbox,
+    ) -> Self {
+        Self {
+            api: TelegramNotifier::new(config.channel.clone(), telegram),
+            config,
+            log_file_path: log_file_path.to_string(),
+            mutes,
+            outbox,
+            reports: None,
+            incidents: None,
+        }
+    }
+
+    pub fn with_reports(mut self, reports: ReportHandle) -> Self {
+        self.reports = Some(reports);
+        self
+    }
+
+    pub fn with_incidents(mut self, incidents: IncidentTracker) -> Self {
+        self.incidents = Some(incidents);
+        self
+    }
+
+    pub async fn run(self) {
+        let poll_timeout = self.config.poll_timeout_secs;
+        let http_timeout = Duration::from_secs(poll_timeout + 10);
+        let mut offset: i64 = 0;
+
+        println!("Telegram bot listening for commands...");
+
+        loop {
+            let body = json!({
+                "offset": offset,
+                "timeout": poll_timeout,
+                "allowed_updates": ["message", "callback_query"]
+            });
+
+            let updates = match self.api.call("getUpdates", &body, http_timeout).await {
+                Ok(updates) => updates,
+                Err(e) => {
+                    eprintln!("Error polling Telegram updates: {}", e);
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    continue;
+                }
+            };
+
+            for update in updates.as_array().into_iter().flatten() {
+                if let Some(id) = update["update_id"].as_i64() {
+                    offset = offset.max(id + 1);
+                }
+                if let Err(e) = self.handle_update(update).await {
+                    eprintln!("Error handling Telegram update: {}", e);
+                }
+            }
+        }
+    }
+
+    async fn handle_update(&self, update: &Value) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        if let Some(callback) = update.get("callback_query") {
+            let chat_id = chat_id_of(&callback["message"]["chat"]["id"]);
+            let user = user_name(&callback["from"]);
+            let reply = if self.is_allowed(&chat_id) {
+                self.handle_callback(callback["data"].as_str().unwrap_or_default(), &user)
+            } else {
+                "Not authorized.".to_string()
+            };
+
+            self.api
+                .call(
+                    "answerCallbackQuery",
+                    &json!({ "callback_query_id": callback["id"], "text": reply }),
+                    Duration::from_secs(10),
+                )
+                .await?;
+            return Ok(());
+        }
+
+        let message = &update["message"];
+        let Some(text) = message["text"].as_str() else {
+            return Ok(());
+        };
+        let chat_id = chat_id_of(&message["chat"]["id"]);
+        if !self.is_allowed(&chat_id) {
+            eprintln!("Ignoring Telegram command from unauthorized chat {}", chat_id);
+            return Ok(());
+        }
+
+        let reply = self.handle_command(text, &user_name(&message["from"])).await;
+        self.reply(&chat_id, &reply).await
+    }
+
+    fn is_allowed(&self, chat_id: &str) -> bool {
+        self.config.allowed_chat_ids.iter().any(|allowed| allowed == chat_id)
+    }
+
+    async fn reply(&self, chat_id: &str, text: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        // Replies are plain text regardless of the channel's alert format
+        // Long replies (/last 50, /incidents) are split to fit Telegram's limit
+        for chunk in split_message(text, TELEGRAM_MAX_MESSAGE_LEN, MessageFormat::Plain) {
+            self.api
+                .call("sendMessage", &json!({ "chat_id": chat_id, "text": chunk }), Duration::from_secs(10))
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Runs a `/command` and returns the reply text.
+    pub async fn handle_command(&self, text: &str, user: &str) -> String {
+        let mut parts = text.split_whitespace();
+        // Commands may be addressed as /status@my_bot in group chats
+        let command = parts.next().unwrap_or_default().split('@').next().unwrap_or_default();
+        let args: Vec<&str> = parts.collect();
+
+        match command {
+            "/status" => self.status(),
+            "/last" => {
+                let n = args.first().and_then(|n| n.parse().ok()).unwrap_or(5);
+                self.last(n)
+            }
+            "/report" => match &self.reports {
+                Some(reports) => match reports.request_report().await {
+                    Ok(()) => "Generating report...".to_string(),
+                    Err(e) => format!("Could not request report: {}", e),
+                },
+                None => "Reports are not enabled.".to_string(),
+            },
+            "/mute" => match (args.first(), args.get(1).and_then(|d| parse_duration_secs(d))) {
+                (Some(fingerprint), Some(secs)) => self.mute(fingerprint, secs),
+                _ => "Usage: /mute <fingerprint> <duration, e.g. 30m, 1h, 2d>".to_string(),
+            },
+            "/unmute" => match args.first() {
+                Some(fingerprint) => match self.mutes.unmute(fingerprint) {
+                    Ok(true) => format!("Unmuted {}.", fingerprint),
+                    Ok(false) => format!("{} was not muted.", fingerprint),
+                    Err(e) => format!("Could not unmute: {}", e),
+                },
+                None => "Usage: /unmute <fingerprint>".to_string(),
+            },
+            "/incidents" => self.incidents(),
+            "/ack" => match args.first() {
+                Some(id) => self.acknowledge(id, user),
+                None => "Usage: /ack <id|fingerprint>".to_string(),
+            },
+            "/start" | "/help" => HELP.to_string(),
+            _ => format!("Unknown command.\n\n{}", HELP),
+        }
+    }
+
+    fn handle_callback(&self, data: &str, user: &str) -> String {
+        let parts: Vec<&str> = data.split(':').collect();
+        match parts.as_slice() {
+            ["ack", id] => self.acknowledge(id, user),
+            ["mute", fingerprint, duration] => match parse_duration_secs(duration) {
+                Some(secs) => self.mute(fingerprint, secs),
+                None => "Invalid mute duration.".to_string(),
+            },
+            _ => "Unknown action.".to_string(),
+        }
+    }
+
+    fn status(&self) -> String {
+        let entries = read_log_file(&self.log_file_path).unwrap_or_default();
+        let since = now_secs().saturating_sub(86400);
+        let recent: Vec<&LogEntry> = entries.iter().filter(|e| e.timestamp >= since).collect();
+        let critical = recent.iter().filter(|e| e.priority == "very high").count();
+        let unacknowledged = recent.iter().filter(|e| e.acknowledged_by.is_none()).count();
+
+        format!(
+            "Entries stored: {}\nLast 24h: {} ({} critical, {} unacknowledged)\nAlerts pending delivery: {}\nActive mutes: {}",
+            entries.len(),
+            recent.len(),
+            critical,
+            unacknowledged,
+            self.outbox.len(),
+            self.mutes.active().len()
+        )
+    }
+
+    fn last(&self, n: usize) -> String {
+        let entries = read_log_file(&self.log_file_path).unwrap_or_default();
+        if entries.is_empty() {
+            return "No entries stored.".to_string();
+        }
+
+        let n = n.clamp(1, MAX_LAST);
+        entries
+            .iter()
+            .rev()
+            .take(n)
+            .map(|e| {
+                format!(
+                    "{} [{}] {}\nid: {} fp: {}",
+                    format_timestamp(e.timestamp),
+                    e.log_type,
+                    e.message,
+                    e.id,
+                    e.fingerprint
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    fn mute(&self, fingerprint: &str, secs: u64) -> String {
+        match self.mutes.mute(fingerprint, secs) {
+            Ok(until) => format!("Muted {} until {}.", fingerprint, format_timestamp(until)),
+            Err(e) => format!("Could not mute: {}", e),
+        }
+    }
+
+    fn incidents(&self) -> String {
+        let Some(tracker) = &self.incidents else {
+            return "Incident tracking is not enabled.".to_string();
+        };
+        let list = tracker.list();
+        if list.is_empty() {
+            return "No incidents.".to_string();
+        }
+
+        list.iter()
+            .take(MAX_LAST)
+            .map(|incident| {
+                let state = match (&incident.state, &incident.acknowledged_by) {
+                    (IncidentState::Open, _) => "OPEN".to_string(),
+                    (IncidentState::Acknowledged, Some(by)) => format!("ACK by {}", by),
+                    (IncidentState::Acknowledged, None) => "ACK".to_string(),
+                    (IncidentState::Resolved, _) => "RESOLVED".to_string(),
+                };
+                format!(
+                    "[{}] {} ({}x since {})\nfp: {}",
+                    state,
+                    incident.title,
+                    incident.occurrences,
+                    format_timestamp(incident.opened_at),
+                    incident.fingerprint
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    fn acknowledge(&self, key: &str, user: &str) -> String {
+        match incidents::acknowledge(key, user, &self.log_file_path, self.incidents.as_ref()) {
+            Ok(true) => format!("Acknowledged {} ({}).", key, user),
+            Ok(false) => format!("No entry or incident {}.", key),
+            Err(e) => format!("Could not acknowledge: {}", e),
+        }
+    }
+}
+
+fn chat_id_of(value: &Value) -> String {
+    match value {
+        Value::String(id) => id.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn user_name(from: &Value) -> String {
+    from["username"]
+        .as_str()
+        .or_else(|| from["first_name"].as_str())
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::test_support::{log_entry, MockServer};
+    use std::path::PathBuf;
+
+    struct Fixture {
+        bot: TelegramBot,
+        files: Vec<PathBuf>,
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            for file in &self.files {
+                let _ = std::fs::remove_file(file);
+            }
+        }
+    }
+
+    fn fixture(name: &str, api_base: &str) -> Fixture {
+        let path = |kind: &str| std::env::temp_dir().join(format!("logsync-bot-{}-{}-{}.json", name, kind, std::process::id()));
+        let (log_path, mutes_path, outbox_path) = (path("log"), path("mutes"), path("outbox"));
+
+        let mut older = log_entry("connection refused");
+        older.id = "e0".to_string();
+        let entries = vec![older, log_entry("disk full")];
+        std::fs::write(&log_path, serde_json::to_vec(&entries).unwrap()).unwrap();
+
+        let telegram = TelegramConfig {
+            token: "TOKEN".to_string(),
+            chat_id: "42".to_string(),
+            api_base: api_base.to_string(),
+            format: MessageFormat::Plain,
+            buttons: false,
+        };
+        let config = TelegramBotConfig {
+            channel: default_channel(),
+            allowed_chat_ids: vec!["42".to_string()],
+            poll_timeout_secs: 1,
+        };
+        let bot = TelegramBot::new(
+            telegram,
+            config,
+            &log_path.to_string_lossy(),
+            MuteList::open(&mutes_path).unwrap(),
+            Outbox::open(&outbox_path).unwrap(),
+        );
+        Fixture {
+            bot,
+            files: vec![log_path, mutes_path, outbox_path],
+        }
+    }
+
+    #[tokio::test]
+    async fn parses_commands_and_arguments() {
+        let fixture = fixture("commands", "http://127.0.0.1:9");
+        let bot = &fixture.bot;
+
+        assert!(bot.handle_command("/status@logsync_bot", "ana").await.starts_with("Entries stored: 2\n"));
+        let last = bot.handle_command("/last 1", "ana").await;
+        assert!(last.contains("disk full") && !last.contains("connection refused"));
+        let defaulted = bot.handle_command("/last lots", "ana").await;
+        assert!(defaulted.contains("disk full") && defaulted.contains("connection refused"));
+        assert_eq!(bot.handle_command("/report", "ana").await, "Reports are not enabled.");
+        assert!(bot.handle_command("/help", "ana").await.starts_with("Commands:"));
+        assert!(bot.handle_command("/frobnicate", "ana").await.starts_with("Unknown command."));
+        assert!(bot.handle_command("", "ana").await.starts_with("Unknown command."));
+    }
+
+    #[tokio::test]
+    async fn mutes_unmutes_and_acknowledges() {
+        let fixture = fixture("mute", "http://127.0.0.1:9");
+        let bot = &fixture.bot;
+
+        assert!(bot.handle_command("/mute 3f2a9c1d0b7e4a55", "ana").await.starts_with("Usage: /mute"));
+        assert!(bot.handle_command("/mute 3f2a9c1d0b7e4a55 soon", "ana").await.starts_with("Usage: /mute"));
+        assert!(bot.handle_command("/mute  3f2a9c1d0b7e4a55   1h", "ana").await.starts_with("Muted 3f2a9c1d0b7e4a55 until "));
+        assert!(bot.mutes.is_muted("3f2a9c1d0b7e4a55"));
+        assert_eq!(bot.handle_command("/unmute 3f2a9c1d0b7e4a55", "ana").await, "Unmuted 3f2a9c1d0b7e4a55.");
+        assert_eq!(bot.handle_command("/unmute 3f2a9c1d0b7e4a55", "ana").await, "3f2a9c1d0b7e4a55 was not muted.");
+        assert_eq!(bot.handle_command("/unmute", "ana").await, "Usage: /unmute <fingerprint>");
+
+        assert_eq!(bot.handle_command("/ack", "ana").await, "Usage: /ack <id|fingerprint>");
+        assert_eq!(bot.handle_command("/ack nope", "ana").await, "No entry or incident nope.");
+        assert_eq!(bot.handle_command("/ack e1", "ana").await, "Acknowledged e1 (ana).");
+        let entries = read_log_file(&bot.log_file_path).unwrap();
+        assert_eq!(entries[1].acknowledged_by.as_deref(), Some("ana"));
+    }
+
+    #[tokio::test]
+    async fn replies_to_allowed_chats_only() {
+        let server = MockServer::ok(r#"{"ok": true, "result": {}}"#).await;
+        let fixture = fixture("updates", &server.url);
+
+        let update = json!({
+            "update_id": 7,
+            "message": { "chat": { "id": 42 }, "from": { "username": "ana" }, "text": "/unmute abc" }
+        });
+        fixture.bot.handle_update(&update).await.unwrap();
+        let intruder = json!({
+            "update_id": 8,
+            "message": { "chat": { "id": 666 }, "from": { "username": "eve" }, "text": "/status" }
+        });
+        fixture.bot.handle_update(&intruder).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/botTOKEN/sendMessage");
+        assert_eq!(requests[0].json(), json!({ "chat_id": "42", "text": "abc was not muted." }));
+    }
+
+    #[tokio::test]
+    async fn splits_long_replies() {
+        let server = MockServer::ok(r#"{"ok": true, "result": {}}"#).await;
+        let fixture = fixture("long", &server.url);
+        let entries: Vec<LogEntry> = (0..50)
+            .map(|i| {
+                let mut entry = log_entry(&format!("{} {}", i, "x".repeat(150)));
+                entry.id = format!("e{}", i);
+                entry
+            })
+            .collect();
+        std::fs::write(&fixture.bot.log_file_path, serde_json::to_vec(&entries).unwrap()).unwrap();
+
+        let update = json!({ "update_id": 1, "message": { "chat": { "id": 42 }, "text": "/last 50" } });
+        fixture.bot.handle_update(&update).await.unwrap();
+
+        let texts: Vec<String> = server
+            .requests()
+            .iter()
+            .map(|r| r.json()["text"].as_str().unwrap().to_string())
+            .collect();
+        assert!(texts.len() > 1);
+        assert!(texts.iter().all(|text| text.chars().count() <= TELEGRAM_MAX_MESSAGE_LEN));
+        assert_eq!(texts.iter().map(|text| text.matches("id: e").count()).sum::<usize>(), 50);
+    }
+
+    #[tokio::test]
+    async fn answers_button_presses() {
+        let server = MockServer::ok(r#"{"ok": true, "result": true}"#).await;
+        let fixture = fixture("callbacks", &server.url);
+
+        for (id, chat, data) in [("c1", 42, "mute:3f2a9c1d0b7e4a55:30m"), ("c2", 42, "mute:x:never"), ("c3", 666, "ack:e1")] {
+            let update = json!({
+                "update_id": 9,
+                "callback_query": {
+                    "id": id,
+                    "from": { "first_name": "Ana" },
+                    "message": { "chat": { "id": chat } },
+                    "data": data
+                }
+            });
+            fixture.bot.handle_update(&update).await.unwrap();
+        }
+
+        let answers: Vec<Value> = server.requests().iter().map(|r| r.json()).collect();
+        assert!(server.requests().iter().all(|r| r.path == "/botTOKEN/answerCallbackQuery"));
+        assert_eq!(answers[0]["callback_query_id"], "c1");
+        assert!(answers[0]["text"].as_str().unwrap().starts_with("Muted 3f2a9c1d0b7e4a55 until "));
+        assert_eq!(answers[1]["text"], "Invalid mute duration.");
+        assert_eq!(answers[2]["text"], "Not authorized.");
+        assert!(fixture.bot.mutes.is_muted("3f2a9c1d0b7e4a55"));
+        assert!(read_log_file(&fixture.bot.log_file_path).unwrap()[1].acknowledged_by.is_none());
+    }
+
+    #[tokio::test]
+    async fn surfaces_api_errors() {
+        let server = MockServer::ok(r#"{"ok": false, "description": "chat not found"}"#).await;
+        let fixture = fixture("errors", &server.url);
+
+        let update = json!({ "update_id": 1, "message": { "chat": { "id": "42" }, "text": "/help" } });
+        let error = fixture.bot.handle_update(&update).await.unwrap_err();
+
+        assert!(error.to_string().contains("chat not found"));
+    }
+}
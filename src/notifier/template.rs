@@ -0,0 +1,562 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::Path;
+
+use super::{NotifyError, Notifier};
+use crate::types::LogEntry;
+use crate::utils::{format_timestamp, hostname};
+
+/// Template used for single-entry alerts when a channel does not set its own.
+pub const DEFAULT_TEMPLATE: &str = "{{emoji}} [{{priority_label}}] {{source}} on {{host}} at {{time}}\n{{message}}";
+
+/// `DEFAULT_TEMPLATE` with its literal brackets escaped, as MarkdownV2 requires.
+pub const DEFAULT_MARKDOWN_V2_TEMPLATE: &str =
+    "{{emoji}} \\[{{priority_label}}\\] {{source}} on {{host}} at {{time}}\n{{message}}";
+
+/// Default template for a channel's markup. Custom templates are written in
+/// that markup already, so their literal text is never escaped.
+pub fn default_template(format: MessageFormat) -> &'static str {
+    match format {
+        MessageFormat::MarkdownV2 | MessageFormat::Markdown => DEFAULT_MARKDOWN_V2_TEMPLATE,
+        MessageFormat::Plain | MessageFormat::Html => DEFAULT_TEMPLATE,
+    }
+}
+
+/// Markup a channel interprets; values substituted into templates are escaped for it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageFormat {
+    #[default]
+    Plain,
+    /// Telegram MarkdownV2.
+    MarkdownV2,
+    /// Standard Markdown, as rendered by Microsoft Teams.
+    Markdown,
+    Html,
+}
+
+impl MessageFormat {
+    pub fn escape(self, text: &str) -> String {
+        match self {
+            MessageFormat::Plain => text.to_string(),
+            MessageFormat::MarkdownV2 => escape_markdown_v2(text),
+            MessageFormat::Markdown => escape_markdown(text),
+            MessageFormat::Html => escape_html(text),
+        }
+    }
+}
+
+/// Escapes every character Telegram reserves in MarkdownV2.
+pub fn escape_markdown_v2(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "_*[]()~`>#+-=|{}.!\\".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Backslash-escapes the punctuation Markdown could read as formatting.
+pub fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\`*_{}[]()<>#+-.!|~".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Escapes a value for use inside a JSON string literal.
+pub fn escape_json(text: &str) -> String {
+    let quoted = Value::String(text.to_string()).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+/// Short label and emoji for a priority.
+pub fn priority_label(priority: &str) -> (&'static str, &'static str) {
+    match priority {
+        "very high" => ("CRITICAL", "🚨"),
+        "high" => ("HIGH", "🔴"),
+        "medium" => ("MEDIUM", "🟠"),
+        "low" => ("LOW", "🔵"),
+        _ => ("INFO", "ℹ️"),
+    }
+}
+
+/// Replaces `{{field}}` placeholders with escaped values, in a single pass so
+/// placeholders inside the values themselves (log text) are never expanded.
+///
+/// Available fields: `text` (the alert text), `id`, `timestamp` (epoch seconds),
+/// `time` (formatted), `log_type`, `priority`, `priority_label`, `emoji`,
+/// `message`, `source`, `tags`, `host` and `delivery_status`. Unknown
+/// placeholders are left untouched.
+pub fn render(template: &str, text: &str, entry: Option<&LogEntry>, escape: impl Fn(&str) -> String) -> String {
+    let (label, emoji) = entry.map(|e| priority_label(&e.priority)).unwrap_or_default();
+    let fields: [(&str, String); 13] = [
+        ("text", text.to_string()),
+        ("id", entry.map(|e| e.id.clone()).unwrap_or_default()),
+        ("timestamp", entry.map(|e| e.timestamp.to_string()).unwrap_or_default()),
+        ("time", entry.map(|e| format_timestamp(e.timestamp)).unwrap_or_default()),
+        ("log_type", entry.map(|e| e.log_type.clone()).unwrap_or_default()),
+        ("priority", entry.map(|e| e.priority.clone()).unwrap_or_default()),
+        ("priority_label", label.to_string()),
+        ("emoji", emoji.to_string()),
+        ("message", entry.map(|e| e.message.clone()).unwrap_or_else(|| text.to_string())),
+        ("source", entry.and_then(|e| e.source.clone()).unwrap_or_else(|| "unknown".to_string())),
+        ("tags", entry.map(|e| e.tags.join(", ")).unwrap_or_default()),
+        ("host", hostname()),
+        ("delivery_status", entry.map(|e| e.delivery_status.label().to_string()).unwrap_or_default()),
+    ];
+
+    let fields: Vec<(&str, String)> = fields.iter().map(|(key, value)| (*key, escape(value))).collect();
+    fill_template(template, &fields)
+}
+
+/// Substitutes `{{key}}` placeholders in a single pass, so text inside the
+/// values (such as a log line containing `{{host}}`) is never expanded.
+/// Unknown placeholders are kept as they are.
+pub fn fill_template(template: &str, fields: &[(&str, String)]) -> String {
+    let mut page = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        page.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let value = after
+            .find("}}")
+            .and_then(|end| fields.iter().find(|(key, _)| *key == &after[..end]).map(|(_, value)| (end, value)));
+        match value {
+            Some((end, value)) => {
+                page.push_str(value);
+                rest = &after[end + 2..];
+            }
+            None => {
+                page.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    page.push_str(rest);
+    page
+}
+
+/// Splits `text` into chunks of at most `max_chars` characters, preferring line
+/// boundaries. Chunks never cut through an escape, an HTML tag or entity, or
+/// a MarkdownV2 link, and formatting open at a cut (`<b>`, `*`, a code block)
+/// is closed at the end of the chunk and reopened at the start of the next, so
+/// every chunk is valid markup on its own.
+pub fn split_message(text: &str, max_chars: usize, format: MessageFormat) -> Vec<String> {
+    let mut splitter = Splitter {
+        max_chars: max_chars.max(2),
+        chunks: Vec::new(),
+        current: String::new(),
+        current_len: 0,
+        has_content: false,
+        open: Vec::new(),
+        trailing: None,
+    };
+
+    // Code blocks span lines, and nothing but their end is markup inside them
+    let mut code: Option<&str> = None;
+    for line in text.split_inclusive('\n') {
+        let units = markup_units(line, format, &mut code);
+        let line_len: usize = units.iter().map(|unit| unit.len).sum();
+        let mut open_after = splitter.open.clone();
+        for unit in &units {
+            apply_effect(&mut open_after, &unit.effect);
+        }
+        let closing_after: usize = open_after.iter().map(|(_, close)| close.chars().count()).sum();
+
+        if splitter.current_len + line_len + closing_after > splitter.max_chars && splitter.has_content {
+            splitter.flush();
+        }
+        if splitter.current_len + line_len + closing_after <= splitter.max_chars {
+            for unit in units {
+                splitter.push(unit);
+            }
+            continue;
+        }
+
+        // A single line longer than the limit is cut between units
+        for unit in units {
+            let mut open = splitter.open.clone();
+            apply_effect(&mut open, &unit.effect);
+            let closing: usize = open.iter().map(|(_, close)| close.chars().count()).sum();
+            if splitter.current_len + unit.len + closing > splitter.max_chars && splitter.has_content {
+                splitter.flush();
+            }
+            splitter.push(unit);
+        }
+    }
+
+    if splitter.has_content {
+        splitter.flush();
+    }
+    splitter.chunks
+}
+
+/// Text that must stay in one chunk, and how it changes the open formatting.
+struct Unit {
+    text: String,
+    len: usize,
+    effect: Effect,
+}
+
+enum Effect {
+    None,
+    /// Opens formatting, with the markup that reopens and closes it.
+    Open(String, String),
+    Close,
+    /// MarkdownV2 markers open and close with the same text.
+    Toggle(String),
+}
+
+fn apply_effect(open: &mut Vec<(String, String)>, effect: &Effect) {
+    match effect {
+        Effect::None => {}
+        Effect::Open(opening, closing) => open.push((opening.clone(), closing.clone())),
+        Effect::Close => {
+            open.pop();
+        }
+        Effect::Toggle(marker) => {
+            if open.last().is_some_and(|(_, close)| close == marker) {
+                open.pop();
+            } else {
+                open.push((marker.clone(), marker.clone()));
+            }
+        }
+    }
+}
+
+struct Splitter {
+    max_chars: usize,
+    chunks: Vec<String>,
+    current: String,
+    current_len: usize,
+    /// Whether the chunk holds text, not just formatting markup.
+    has_content: bool,
+    open: Vec<(String, String)>,
+    /// Where formatting opened after the last text starts in `current`, and
+    /// how much of `open` was there before it. That markup moves to the next
+    /// chunk instead of ending this one as an empty entity.
+    trailing: Option<(usize, usize)>,
+}
+
+impl Splitter {
+    fn push(&mut self, unit: Unit) {
+        let (start, depth) = (self.current.len(), self.open.len());
+        apply_effect(&mut self.open, &unit.effect);
+        self.current.push_str(&unit.text);
+        self.current_len += unit.len;
+
+        if self.open.len() > depth {
+            self.trailing.get_or_insert((start, depth));
+        } else {
+            self.has_content = true;
+            self.trailing = None;
+        }
+    }
+
+    fn flush(&mut self) {
+        let (cut, depth) = self.trailing.take().unwrap_or((self.current.len(), self.open.len()));
+        let mut chunk = std::mem::take(&mut self.current);
+        chunk.truncate(cut);
+        for (_, closing) in self.open[..depth].iter().rev() {
+            chunk.push_str(closing);
+        }
+        // Telegram rejects messages that are only whitespace
+        if !chunk.trim().is_empty() {
+            self.chunks.push(chunk);
+        }
+        for (opening, _) in &self.open {
+            self.current.push_str(opening);
+        }
+        self.current_len = self.current.chars().count();
+        self.has_content = false;
+    }
+}
+
+fn markup_units(line: &str, format: MessageFormat, code: &mut Option<&'static str>) -> Vec<Unit> {
+    let chars: Vec<char> = line.chars().collect();
+    let unit = |from: usize, to: usize, effect: Effect| Unit {
+        text: chars[from..to].iter().collect(),
+        len: to - from,
+        effect,
+    };
+    let find = |from: usize, target: char, limit: usize| {
+        chars[from..].iter().take(limit).position(|&c| c == target).map(|i| from + i)
+    };
+
+    let mut units = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let rest = &chars[i..];
+        let next = match format {
+            MessageFormat::Plain => unit(i, i + 1, Effect::None),
+            MessageFormat::Html => match rest[0] {
+                '<' => match find(i, '>', usize::MAX) {
+                    Some(end) if rest.get(1) == Some(&'/') => unit(i, end + 1, Effect::Close),
+                    Some(end) => {
+                        let name: String = chars[i + 1..end].iter().take_while(|c| c.is_ascii_alphanumeric()).collect();
+                        let mut tag = unit(i, end + 1, Effect::None);
+                        tag.effect = Effect::Open(tag.text.clone(), format!("</{}>", name));
+                        tag
+                    }
+                    None => unit(i, i + 1, Effect::None),
+                },
+                '&' => match find(i, ';', 10) {
+                    Some(end) => unit(i, end + 1, Effect::None),
+                    None => unit(i, i + 1, Effect::None),
+                },
+                _ => unit(i, i + 1, Effect::None),
+            },
+            MessageFormat::MarkdownV2 | MessageFormat::Markdown => {
+                let starts = |marker: &str| rest.iter().take(marker.len()).copied().eq(marker.chars());
+                if rest[0] == '\\' && rest.len() > 1 {
+                    unit(i, i + 2, Effect::None)
+                } else if let Some(marker) = *code {
+                    if starts(marker) {
+                        *code = None;
+                        unit(i, i + marker.len(), Effect::Close)
+                    } else {
+                        unit(i, i + 1, Effect::None)
+                    }
+                } else if starts("```") {
+                    // The opening line, with its optional language, is kept with
+                    // the marker and repeated when the block is reopened
+                    let language = chars[i + 3..].iter().position(|c| c.is_whitespace() || *c == '`');
+                    let end = match language.map(|n| i + 3 + n) {
+                        Some(end) if chars[end] == '\n' => end + 1,
+                        _ => i + 3,
+                    };
+                    *code = Some("```");
+                    let mut block = unit(i, end, Effect::None);
+                    let opening = if block.text.ends_with('\n') { block.text.clone() } else { "```\n".to_string() };
+                    block.effect = Effect::Open(opening, "```".to_string());
+                    block
+                } else if starts("`") {
+                    *code = Some("`");
+                    unit(i, i + 1, Effect::Open("`".to_string(), "`".to_string()))
+                } else if let Some(marker) = ["__", "||", "*", "_", "~"].into_iter().find(|marker| starts(marker)) {
+                    unit(i, i + marker.len(), Effect::Toggle(marker.to_string()))
+                } else if rest[0] == '[' {
+                    // A link `[text](url)` is kept whole
+                    let link_end = find(i, ']', usize::MAX)
+                        .filter(|&close| chars.get(close + 1) == Some(&'('))
+                        .and_then(|close| find(close, ')', usize::MAX));
+                    match link_end {
+                        Some(end) => unit(i, end + 1, Effect::None),
+                        None => unit(i, i + 1, Effect::None),
+                    }
+                } else {
+                    unit(i, i + 1, Effect::None)
+                }
+            }
+        };
+        i += next.len;
+        units.push(next);
+    }
+    units
+}
+
+/// Renders each alert with the channel's template and markup, splitting it to
+/// fit the channel's message length limit.
+pub struct TemplatedNotifier {
+    inner: Box<dyn Notifier>,
+    template: String,
+}
+
+impl TemplatedNotifier {
+    pub fn new(inner: Box<dyn Notifier>, template: Option<String>) -> Self {
+        Self {
+            template: template.unwrap_or_else(|| default_template(inner.format()).to_string()),
+            inner,
+        }
+    }
+
+    pub fn format_message(&self, message: &str, entry: Option<&LogEntry>) -> String {
+        let format = self.inner.format();
+        match entry {
+            Some(entry) => render(&self.template, message, Some(entry), |value| format.escape(value)),
+            // Multi-entry messages such as digests have no template fields
+            None => format.escape(message),
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for TemplatedNotifier {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn format(&self) -> MessageFormat {
+        self.inner.format()
+    }
+
+    fn max_message_len(&self) -> Option<usize> {
+        self.inner.max_message_len()
+    }
+
+    async fn notify(&self, message: &str, entry: Option<&LogEntry>) -> Result<(), NotifyError> {
+        let text = self.format_message(message, entry);
+        match self.inner.max_message_len() {
+            Some(max) => {
+                for chunk in split_message(&text, max, self.inner.format()) {
+                    self.inner.notify(&chunk, entry).await?;
+                }
+                Ok(())
+            }
+            None => self.inner.notify(&text, entry).await,
+        }
+    }
+
+    async fn send_report(&self, path: &Path, caption: &str) -> Result<(), NotifyError> {
+        self.inner.send_report(path, caption).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::test_support::log_entry;
+
+    fn entry(message: &str) -> LogEntry {
+        let mut entry = log_entry(message);
+        entry.id = "abc123".to_string();
+        entry.timestamp = 1_709_294_400;
+        entry.source = Some("nginx".to_string());
+        entry.tags = vec!["web".to_string()];
+        entry
+    }
+
+    /// Reserved MarkdownV2 characters not preceded by an escaping backslash.
+    fn unescaped_markdown(text: &str) -> Vec<char> {
+        let mut found = Vec::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                chars.next();
+            } else if "_*[]()~`>#+-=|{}.!".contains(c) {
+                found.push(c);
+            }
+        }
+        found
+    }
+
+    #[test]
+    fn escapes_values_for_each_format() {
+        assert_eq!(escape_markdown_v2("a_b [x](y) 1.5!"), "a\\_b \\[x\\]\\(y\\) 1\\.5\\!");
+        assert_eq!(escape_html("<a href=\"x\">&</a>"), "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;");
+        assert_eq!(escape_json("say \"hi\"\n"), "say \\\"hi\\\"\\n");
+        assert_eq!(escape_markdown("**bold** <b> 1. [x](y)"), "\\*\\*bold\\*\\* \\<b\\> 1\\. \\[x\\]\\(y\\)");
+        assert_eq!(MessageFormat::Plain.escape("*as is*"), "*as is*");
+    }
+
+    #[test]
+    fn default_markdown_template_is_fully_escaped() {
+        let log = entry("disk [sda] at 95.5% (critical)!");
+        let text = render(default_template(MessageFormat::MarkdownV2), "", Some(&log), escape_markdown_v2);
+
+        assert!(text.contains("\\[HIGH\\] nginx"), "{}", text);
+        assert_eq!(unescaped_markdown(&text), Vec::<char>::new(), "{}", text);
+    }
+
+    #[test]
+    fn placeholders_in_values_are_not_expanded() {
+        let log = entry("user sent {{host}} and {{id}} and {{message}}");
+        let text = render("{{id}}: {{message}} {{unknown}}", "", Some(&log), str::to_string);
+
+        assert_eq!(text, "abc123: user sent {{host}} and {{id}} and {{message}} {{unknown}}");
+    }
+
+    #[test]
+    fn render_escapes_values_but_not_the_template() {
+        let log = entry("a < b & c");
+        let text = render("<b>{{priority_label}}</b> {{message}}", "", Some(&log), escape_html);
+
+        assert_eq!(text, "<b>HIGH</b> a &lt; b &amp; c");
+    }
+
+    #[test]
+    fn splits_on_line_boundaries() {
+        let chunks = split_message("first line\nsecond line\nthird\n", 20, MessageFormat::Plain);
+
+        assert_eq!(chunks, vec!["first line\n", "second line\nthird\n"]);
+    }
+
+    #[test]
+    fn never_cuts_markdown_escapes() {
+        let text = "\\.".repeat(20);
+        for max in [3, 4, 5, 7] {
+            let chunks = split_message(&text, max, MessageFormat::MarkdownV2);
+            assert_eq!(chunks.concat(), text);
+            for chunk in &chunks {
+                assert!(chunk.chars().count() <= max);
+                assert_eq!(unescaped_markdown(chunk), Vec::<char>::new(), "{:?}", chunk);
+            }
+        }
+    }
+
+    #[test]
+    fn never_cuts_html_entities() {
+        let text = escape_html("<&>\"").repeat(10);
+        let chunks = split_message(&text, 13, MessageFormat::Html);
+
+        assert_eq!(chunks.concat(), text);
+        for chunk in &chunks {
+            assert!(chunk.chars().count() <= 13);
+            assert!(chunk.starts_with('&') && chunk.ends_with(';'), "{:?}", chunk);
+            assert_eq!(chunk.matches('&').count(), chunk.matches(';').count());
+        }
+    }
+
+    #[test]
+    fn reopens_html_tags_across_chunks() {
+        let text = format!("<b>{}</b> <code>{}</code>", "x".repeat(20), "y".repeat(20));
+        let chunks = split_message(&text, 24, MessageFormat::Html);
+
+        assert!(chunks.len() > 2);
+        for chunk in &chunks {
+            assert!(chunk.chars().count() <= 24, "{:?}", chunk);
+            assert_eq!(chunk.matches("<b>").count(), chunk.matches("</b>").count(), "{:?}", chunk);
+            assert_eq!(chunk.matches("<code>").count(), chunk.matches("</code>").count(), "{:?}", chunk);
+        }
+        let plain: String = chunks.concat().replace("<b>", "").replace("</b>", "").replace("<code>", "").replace("</code>", "");
+        assert_eq!(plain, format!("{} {}", "x".repeat(20), "y".repeat(20)));
+    }
+
+    #[test]
+    fn reopens_markdown_formatting_across_chunks() {
+        let text = format!("*{}* ```\n{}```", "a".repeat(12), "b".repeat(12));
+        let chunks = split_message(&text, 8, MessageFormat::MarkdownV2);
+
+        assert!(chunks.iter().all(|chunk| !chunk.ends_with("```\n```")), "{:?}", chunks);
+        assert!(chunks.last().unwrap().starts_with("```\n"), "{:?}", chunks);
+        for chunk in &chunks {
+            assert!(chunk.chars().count() <= 8, "{:?}", chunk);
+            assert_eq!(chunk.matches("```").count() % 2, 0, "{:?}", chunk);
+            assert_eq!(chunk.replace("```", "").matches('*').count() % 2, 0, "{:?}", chunk);
+        }
+    }
+
+    #[test]
+    fn keeps_markdown_links_whole() {
+        let text = "see [the dashboard](https://example.com/d) now";
+        let chunks = split_message(text, 36, MessageFormat::MarkdownV2);
+
+        assert!(chunks.iter().any(|chunk| chunk.contains("[the dashboard](https://example.com/d)")));
+        assert_eq!(chunks.concat(), text);
+    }
+}
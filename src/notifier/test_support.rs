@@ -0,0 +1,181 @@
+//! Helpers for tests: local HTTP and SMTP servers recording every request, so
+//! payloads can be checked without reaching the real services, and a sample
+//! log entry that tests adjust field by field.
+
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{HeaderMap, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde_json::Value;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+use crate::types::{DeliveryStatus, LogEntry};
+
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    /// Path and query string.
+    pub path: String,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl RecordedRequest {
+    pub fn json(&self) -> Value {
+        serde_json::from_slice(&self.body).expect("request body is not JSON")
+    }
+}
+
+type Responder = dyn Fn(&RecordedRequest) -> (StatusCode, String) + Send + Sync;
+
+/// HTTP server on a random local port answering with `responder`.
+pub struct MockServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl MockServer {
+    pub async fn start(responder: impl Fn(&RecordedRequest) -> (StatusCode, String) + Send + Sync + 'static) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let responder: Arc<Responder> = Arc::new(responder);
+
+        let recorded = Arc::clone(&requests);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let requests = Arc::clone(&recorded);
+                let responder = Arc::clone(&responder);
+                tokio::spawn(async move {
+                    let service = service_fn(move |request: Request<Incoming>| {
+                        let requests = Arc::clone(&requests);
+                        let responder = Arc::clone(&responder);
+                        async move {
+                            let path = request.uri().path_and_query().map(|p| p.to_string()).unwrap_or_default();
+                            let headers = request.headers().clone();
+                            let body = request.into_body().collect().await.map(|b| b.to_bytes()).unwrap_or_default();
+                            let recorded = RecordedRequest { path, headers, body };
+                            let (status, body) = responder(&recorded);
+                            requests.lock().unwrap().push(recorded);
+
+                            let response = Response::builder()
+                                .status(status)
+                                .header("Content-Type", "application/json")
+                                .body(Full::new(Bytes::from(body)))
+                                .unwrap();
+                            Ok::<_, Infallible>(response)
+                        }
+                    });
+                    let _ = http1::Builder::new().serve_connection(TokioIo::new(stream), service).await;
+                });
+            }
+        });
+
+        Self { url, requests }
+    }
+
+    /// Server answering every request with `200 OK` and `body`.
+    pub async fn ok(body: &str) -> Self {
+        let body = body.to_string();
+        Self::start(move |_| (StatusCode::OK, body.clone())).await
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+/// A message accepted by `MockSmtpServer`.
+#[derive(Debug, Clone, Default)]
+pub struct ReceivedMail {
+    pub from: String,
+    pub to: Vec<String>,
+    /// Headers and body as sent after `DATA`, with CRLF line endings.
+    pub data: String,
+}
+
+/// Plain SMTP server on a random local port that accepts every message.
+pub struct MockSmtpServer {
+    pub port: u16,
+    mails: Arc<Mutex<Vec<ReceivedMail>>>,
+}
+
+impl MockSmtpServer {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let mails = Arc::new(Mutex::new(Vec::new()));
+
+        let received = Arc::clone(&mails);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let mails = Arc::clone(&received);
+                tokio::spawn(async move {
+                    let (reader, mut writer) = stream.into_split();
+                    let mut lines = BufReader::new(reader).lines();
+                    let mut mail = ReceivedMail::default();
+                    writer.write_all(b"220 localhost ESMTP\r\n").await?;
+
+                    while let Some(line) = lines.next_line().await? {
+                        let command = line.to_ascii_uppercase();
+                        let reply: &[u8] = if command.starts_with("EHLO") || command.starts_with("HELO") {
+                            b"250 localhost\r\n"
+                        } else if command.starts_with("MAIL FROM:") {
+                            mail.from = line[10..].trim().to_string();
+                            b"250 OK\r\n"
+                        } else if command.starts_with("RCPT TO:") {
+                            mail.to.push(line[8..].trim().to_string());
+                            b"250 OK\r\n"
+                        } else if command == "DATA" {
+                            writer.write_all(b"354 End data with <CR><LF>.<CR><LF>\r\n").await?;
+                            while let Some(data) = lines.next_line().await? {
+                                if data == "." {
+                                    break;
+                                }
+                                mail.data.push_str(&data);
+                                mail.data.push_str("\r\n");
+                            }
+                            mails.lock().unwrap().push(std::mem::take(&mut mail));
+                            b"250 OK queued\r\n"
+                        } else if command == "QUIT" {
+                            writer.write_all(b"221 Bye\r\n").await?;
+                            break;
+                        } else {
+                            b"250 OK\r\n"
+                        };
+                        writer.write_all(reply).await?;
+                    }
+                    Ok::<_, std::io::Error>(())
+                });
+            }
+        });
+
+        Self { port, mails }
+    }
+
+    pub fn mails(&self) -> Vec<ReceivedMail> {
+        self.mails.lock().unwrap().clone()
+    }
+}
+
+/// A pending high-priority `ERROR` from `api` with an alert requested.
+pub fn log_entry(message: &str) -> LogEntry {
+    LogEntry {
+        id: "e1".to_string(),
+        timestamp: 1_700_000_000,
+        log_type: "ERROR".to_string(),
+        priority: "high".to_string(),
+        message: message.to_string(),
+        source: Some("api".to_string()),
+        tags: vec!["db".to_string()],
+        fingerprint: "3f2a9c1d0b7e4a55".to_string(),
+        alert_requested: true,
+        delivery_status: DeliveryStatus::Pending,
+        acknowledged_by: None,
+    }
+}
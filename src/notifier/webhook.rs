@@ -0,0 +1,140 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+use super::template::{escape_json, render};
+use super::{check_response, NotifyError, Notifier, HTTP_TIMEOUT};
+use crate::types::LogEntry;
+
+/// Generic JSON webhook.
+///
+/// `body_template` accepts the same placeholders as message templates (see
+/// `template::render`); values are JSON-escaped so they can be placed inside
+/// string literals. Without a template the body is `{"text": ..., "entry": ...}`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body_template: Option<String>,
+}
+
+pub struct WebhookNotifier {
+    name: String,
+    config: WebhookConfig,
+    client: reqwest::Client,
+}
+
+impl WebhookNotifier {
+    pub fn new(name: String, config: WebhookConfig) -> Self {
+        Self {
+            name,
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn render_body(&self, message: &str, entry: Option<&LogEntry>) -> Result<String, NotifyError> {
+        match &self.config.body_template {
+            Some(template) => {
+                let body = render(template, message, entry, escape_json);
+                // Fail early on templates that do not produce valid JSON
+                serde_json::from_str::<Value>(&body)
+                    .map_err(|e| format!("Webhook body template is not valid JSON: {}", e))?;
+                Ok(body)
+            }
+            None => Ok(json!({ "text": message, "entry": entry }).to_string()),
+        }
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn notify(&self, message: &str, entry: Option<&LogEntry>) -> Result<(), NotifyError> {
+        let body = self.render_body(message, entry)?;
+
+        let mut request = self
+            .client
+            .post(&self.config.url)
+            .header("Content-Type", "application/json")
+            .body(body)
+            .timeout(HTTP_TIMEOUT);
+        for (key, value) in &self.config.headers {
+            request = request.header(key, value);
+        }
+
+        check_response(request.send().await?).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::test_support::{log_entry, MockServer};
+    use hyper::StatusCode;
+
+    fn notifier(url: &str, body_template: Option<&str>) -> WebhookNotifier {
+        let config = WebhookConfig {
+            url: format!("{}/hooks/alerts", url),
+            headers: BTreeMap::from([("X-Api-Key".to_string(), "secret".to_string())]),
+            body_template: body_template.map(str::to_string),
+        };
+        WebhookNotifier::new("webhook".to_string(), config)
+    }
+
+    #[tokio::test]
+    async fn posts_the_text_and_entry_with_configured_headers() {
+        let server = MockServer::ok("{}").await;
+        let entry = log_entry("disk full");
+
+        notifier(&server.url, None).notify("ERROR: disk full", Some(&entry)).await.unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].path, "/hooks/alerts");
+        assert_eq!(requests[0].headers["x-api-key"], "secret");
+        assert_eq!(requests[0].headers["content-type"], "application/json");
+        let body = requests[0].json();
+        assert_eq!(body["text"], "ERROR: disk full");
+        assert_eq!(body["entry"]["message"], "disk full");
+        assert_eq!(body["entry"]["fingerprint"], "3f2a9c1d0b7e4a55");
+    }
+
+    #[tokio::test]
+    async fn body_template_escapes_values_into_valid_json() {
+        let server = MockServer::ok("{}").await;
+        let entry = log_entry("bad \"quote\" \\ and\nnewline {{host}}");
+        let template = r#"{"summary": "{{priority_label}}: {{message}}", "source": "{{source}}"}"#;
+
+        notifier(&server.url, Some(template)).notify("ignored", Some(&entry)).await.unwrap();
+
+        let body = server.requests()[0].json();
+        assert_eq!(body["summary"], "HIGH: bad \"quote\" \\ and\nnewline {{host}}");
+        assert_eq!(body["source"], "api");
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_templates_without_sending() {
+        let server = MockServer::ok("{}").await;
+
+        let result = notifier(&server.url, Some("{\"text\": {{message}}}")).notify("x", None).await;
+
+        assert!(result.unwrap_err().to_string().contains("not valid JSON"));
+        assert!(server.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn reports_error_responses() {
+        let server = MockServer::start(|_| (StatusCode::BAD_GATEWAY, "upstream down".to_string())).await;
+
+        let error = notifier(&server.url, None).notify("x", None).await.unwrap_err();
+
+        assert_eq!(error.to_string(), "HTTP 502 Bad Gateway: upstream down");
+    }
+}
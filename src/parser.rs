@@ -1,7 +1,5 @@
 use serde_json::Value;
 
-use crate::{config::LogMonitorConfig, types::{LogEntry, LogPriority, LogType}};
-
 
 pub fn parse_log_line(line: &str) -> Option<Value> {
     let priority = if line.contains("CRITICAL") {
@@ -21,13 +19,84 @@ pub fn parse_log_line(line: &str) -> Option<Value> {
         "timestamp": timestamp,
         "type": if line.contains("CRITICAL") { "CRITICAL" } else { "ERROR" },
         "priority": priority,
-        "message": line.trim()
+        "message": line.trim(),
+        "source": extract_source(line),
+        "tags": extract_tags(line)
     });
 
     Some(log_json)
 }
 
+/// Type and priority of any monitored line, including the ones that are not
+/// stored (used by alert conditions).
+pub fn line_level(line: &str) -> (&'static str, &'static str) {
+    if line.contains("CRITICAL") {
+        ("CRITICAL", "very high")
+    } else if line.contains("ERROR") {
+        ("ERROR", "high")
+    } else if line.contains("WARN") {
+        ("WARN", "medium")
+    } else {
+        ("INFO", "low")
+    }
+}
+
+/// Extracts the program name from a syslog-style line
+/// (e.g. `Nov 5 12:34:56 myhost app[1234]: ERROR ...` yields `app`).
+pub fn extract_source(line: &str) -> Option<String> {
+    line.split_whitespace()
+        .take_while(|token| !token.contains("ERROR") && !token.contains("CRITICAL"))
+        .find_map(|token| {
+            let tag = token.strip_suffix(':')?;
+            let name = tag.split('[').next().unwrap_or(tag);
+            if name.is_empty() || name.chars().all(|c| c.is_ascii_digit() || c == ':') {
+                None
+            } else {
+                Some(name.to_string())
+            }
+        })
+}
+
+/// Collects `#tag` tokens from the line, lowercased and without the `#`.
+pub fn extract_tags(line: &str) -> Vec<String> {
+    line.split_whitespace()
+        .filter_map(|token| token.strip_prefix('#'))
+        .map(|tag| tag.trim_end_matches(|c: char| !c.is_alphanumeric()))
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Stable identifier for recurring errors: a 64-bit FNV-1a hash of the type,
+/// source and message with the syslog prefix removed and digits collapsed.
+pub fn fingerprint(log_type: &str, source: Option<&str>, message: &str) -> String {
+    let body = ["CRITICAL", "ERROR"]
+        .iter()
+        .filter_map(|keyword| message.find(keyword))
+        .min()
+        .map(|start| &message[start..])
+        .unwrap_or(message);
+
+    let mut normalized = String::with_capacity(body.len());
+    for c in body.chars() {
+        if c.is_ascii_digit() {
+            if !normalized.ends_with('#') {
+                normalized.push('#');
+            }
+        } else {
+            normalized.extend(c.to_lowercase());
+        }
+    }
+
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in [log_type, source.unwrap_or_default(), normalized.trim()].join("\u{1f}").bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
 // Funções auxiliares de parsing podem ser adicionadas aqui
 pub fn sanitize_log_message(message: &str) -> String {
     message.trim().to_string()
-}
\ No newline at end of file
+}
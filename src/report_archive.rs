@@ -0,0 +1,199 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::report_generator::ReportPeriod;
+use crate::utils::write_atomically;
+
+const INDEX_FILE: &str = "index.json";
+
+/// Where past reports are kept.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReportArchiveConfig {
+    #[serde(default = "default_directory")]
+    pub directory: PathBuf,
+    /// Reports kept in the index; older ones are deleted. 0 keeps everything.
+    #[serde(default = "default_keep")]
+    pub keep: usize,
+}
+
+impl Default for ReportArchiveConfig {
+    fn default() -> Self {
+        Self {
+            directory: default_directory(),
+            keep: default_keep(),
+        }
+    }
+}
+
+fn default_directory() -> PathBuf {
+    PathBuf::from("reports")
+}
+
+fn default_keep() -> usize {
+    200
+}
+
+/// One generated report in the index.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportRecord {
+    /// File names inside the archive directory, one per format.
+    pub files: Vec<String>,
+    /// `hourly`, `daily`, `weekly`, `storm`, `on-demand` or `range`.
+    pub kind: String,
+    pub period: ReportPeriod,
+    pub generated_at: u64,
+    pub entries: usize,
+    /// Channels the report was delivered to.
+    #[serde(default)]
+    pub delivered_to: Vec<String>,
+}
+
+/// Copies of the generated reports plus `index.json`, newest last.
+pub struct ReportArchive {
+    config: ReportArchiveConfig,
+}
+
+impl ReportArchive {
+    pub fn new(config: ReportArchiveConfig) -> Self {
+        Self { config }
+    }
+
+    /// Copies `report` into the archive under a name made of its kind and
+    /// generation time, e.g. `daily-20240301-000000.html`.
+    pub fn store(&self, kind: &str, report: &Path, generated_at: u64) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        std::fs::create_dir_all(&self.config.directory)?;
+        let time = DateTime::from_timestamp(generated_at as i64, 0)
+            .unwrap_or_default()
+            .with_timezone(&Local)
+            .format("%Y%m%d-%H%M%S");
+        let extension = report.extension().and_then(|e| e.to_str()).unwrap_or("html");
+
+        let mut path = self.config.directory.join(format!("{}-{}.{}", kind, time, extension));
+        let mut attempt = 1;
+        while path.exists() {
+            attempt += 1;
+            path = self.config.directory.join(format!("{}-{}-{}.{}", kind, time, attempt, extension));
+        }
+        std::fs::copy(report, &path)
+            .map_err(|e| format!("Error archiving report to {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+
+    /// Adds `record` to the index and deletes the reports beyond `keep`.
+    pub fn record(&self, record: ReportRecord) -> Result<(), Box<dyn std::error::Error>> {
+        let mut records = self.records()?;
+        records.push(record);
+
+        if self.config.keep > 0 && records.len() > self.config.keep {
+            let expired: Vec<ReportRecord> = records.drain(..records.len() - self.config.keep).collect();
+            for file in expired.iter().flat_map(|old| &old.files) {
+                let _ = std::fs::remove_file(self.config.directory.join(file));
+            }
+        }
+
+        let content = serde_json::to_vec_pretty(&records)?;
+        write_atomically(&self.index_path(), &content)?;
+        Ok(())
+    }
+
+    /// Past reports, oldest first.
+    pub fn records(&self) -> Result<Vec<ReportRecord>, Box<dyn std::error::Error>> {
+        let path = self.index_path();
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        if content.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(serde_json::from_str(&content).map_err(|e| format!("Invalid report index {}: {}", path.display(), e))?)
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.config.directory
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.config.directory.join(INDEX_FILE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn archive(name: &str, keep: usize) -> ReportArchive {
+        let directory = std::env::temp_dir().join(format!("logsync-archive-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        ReportArchive::new(ReportArchiveConfig { directory, keep })
+    }
+
+    /// Archives a one-file report generated at `generated_at`.
+    fn add(archive: &ReportArchive, generated_at: u64) -> PathBuf {
+        let source = archive.directory().with_extension("html");
+        std::fs::write(&source, generated_at.to_string()).unwrap();
+        let stored = archive.store("daily", &source, generated_at).unwrap();
+        std::fs::remove_file(&source).unwrap();
+
+        archive
+            .record(ReportRecord {
+                files: vec![stored.file_name().unwrap().to_string_lossy().into_owned()],
+                kind: "daily".to_string(),
+                period: ReportPeriod { start: generated_at - 86400, end: generated_at },
+                generated_at,
+                entries: 1,
+                delivered_to: Vec::new(),
+            })
+            .unwrap();
+        stored
+    }
+
+    #[test]
+    fn keeps_the_newest_reports_and_deletes_the_rest() {
+        let archive = archive("prune", 2);
+        let stored: Vec<PathBuf> = (1..=4).map(|day| add(&archive, 1_704_844_800 + day * 86400)).collect();
+
+        let records = archive.records().unwrap();
+        let generated: Vec<u64> = records.iter().map(|r| r.generated_at).collect();
+        let exists: Vec<bool> = stored.iter().map(|path| path.exists()).collect();
+        let _ = std::fs::remove_dir_all(archive.directory());
+
+        assert_eq!(generated, [1_704_844_800 + 3 * 86400, 1_704_844_800 + 4 * 86400]);
+        assert_eq!(exists, [false, false, true, true]);
+    }
+
+    #[test]
+    fn keep_zero_keeps_everything() {
+        let archive = archive("keep-all", 0);
+        let stored: Vec<PathBuf> = (1..=3).map(|day| add(&archive, 1_704_844_800 + day * 86400)).collect();
+
+        let records = archive.records().unwrap().len();
+        let all_exist = stored.iter().all(|path| path.exists());
+        let _ = std::fs::remove_dir_all(archive.directory());
+
+        assert_eq!(records, 3);
+        assert!(all_exist);
+    }
+
+    #[test]
+    fn reports_generated_in_the_same_second_get_distinct_names() {
+        let archive = archive("same-second", 10);
+        let first = add(&archive, 1_704_844_800);
+        let second = add(&archive, 1_704_844_800);
+
+        let both_exist = first.exists() && second.exists();
+        let _ = std::fs::remove_dir_all(archive.directory());
+
+        assert_ne!(first, second);
+        assert!(second.to_string_lossy().ends_with("-2.html"));
+        assert!(both_exist);
+    }
+
+    #[test]
+    fn missing_index_means_no_reports() {
+        let archive = archive("empty", 10);
+        assert!(archive.records().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,345 @@
+use chrono::{Datelike, Timelike};
+
+use crate::notifier::template::{escape_html, priority_label};
+use crate::report_generator::{priority_rank, Report, ReportTimezone, PRIORITIES};
+
+const WIDTH: u64 = 760;
+
+/// Candidate bucket lengths for the timeline, in seconds; the smallest one
+/// giving at most `MAX_BUCKETS` bars is used.
+const BUCKET_SIZES: [u64; 11] = [60, 300, 900, 1800, 3600, 10800, 21600, 43200, 86400, 604800, 2592000];
+const MAX_BUCKETS: u64 = 48;
+
+/// Sources shown in the bar chart.
+const SOURCE_BARS: usize = 10;
+
+/// Fill per priority rank, matching the table markers; the last one is for
+/// priorities outside `PRIORITIES`.
+const COLORS: [&str; 5] = ["#b00020", "#e65100", "#f9a825", "#1565c0", "#9e9e9e"];
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Inline SVG charts for the HTML report. They are plain markup, so the report
+/// needs no scripts or network access to show them.
+pub fn charts_html(report: &Report, tz: &ReportTimezone) -> String {
+    if report.entries.is_empty() {
+        return "<p>No entries in this period.</p>".to_string();
+    }
+    format!(
+        "<div class=\"charts\">\
+         <figure><figcaption>Entries over time</figcaption>{}</figure>\
+         <figure><figcaption>Entries by source</figcaption>{}</figure>\
+         <figure><figcaption>Entries by weekday and hour</figcaption>{}</figure>\
+         </div>",
+        timeline_svg(report, tz),
+        sources_svg(report),
+        heatmap_svg(report, tz)
+    )
+}
+
+/// Stacked bars of entries per time bucket, most severe priority at the bottom.
+fn timeline_svg(report: &Report, tz: &ReportTimezone) -> String {
+    let (first, size, counts) = timeline_buckets(report, tz);
+    let buckets = counts.len();
+    let max = counts.iter().map(|bucket| bucket.iter().sum::<usize>()).max().unwrap_or(0).max(1);
+
+    let (left, top, plot_width, plot_height) = (40, 28, WIDTH - 50, 180);
+    let height = top + plot_height + 24;
+    let bar_width = plot_width as f64 / buckets as f64;
+    let gap = if bar_width > 4.0 { 1.0 } else { 0.0 };
+    let label_format = if size >= 86400 { "%Y-%m-%d" } else { "%m-%d %H:%M" };
+
+    let mut svg = svg_open(WIDTH, height, "Entries over time by priority");
+    svg.push_str(&legend(report));
+    svg.push_str(&format!(
+        "<line x1=\"{l}\" y1=\"{t}\" x2=\"{r}\" y2=\"{t}\" stroke=\"#ddd\" stroke-dasharray=\"4 3\"/>\
+         <text x=\"{lx}\" y=\"{ty}\" text-anchor=\"end\">{max}</text>\
+         <text x=\"{lx}\" y=\"{by}\" text-anchor=\"end\">0</text>",
+        l = left,
+        r = left + plot_width,
+        t = top,
+        lx = left - 4,
+        ty = top + 4,
+        by = top + plot_height,
+        max = max
+    ));
+
+    for (index, bucket) in counts.iter().enumerate() {
+        let bucket_start = first + index as u64 * size;
+        let x = left as f64 + index as f64 * bar_width;
+        let mut y = (top + plot_height) as f64;
+        for (rank, &count) in bucket.iter().enumerate().filter(|(_, &count)| count > 0) {
+            let bar_height = count as f64 * plot_height as f64 / max as f64;
+            y -= bar_height;
+            let label = PRIORITIES.get(rank).map_or("INFO", |priority| priority_label(priority).0);
+            svg.push_str(&format!(
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\">\
+                 <title>{}: {} {}</title></rect>",
+                x,
+                y,
+                (bar_width - gap).max(0.5),
+                bar_height,
+                COLORS[rank],
+                tz.datetime(bucket_start).format(label_format),
+                count,
+                label
+            ));
+        }
+    }
+
+    svg.push_str(&format!(
+        "<line x1=\"{l}\" y1=\"{b}\" x2=\"{r}\" y2=\"{b}\" stroke=\"#999\"/>",
+        l = left,
+        r = left + plot_width,
+        b = top + plot_height
+    ));
+    let mut ticks = vec![(0, "start"), (buckets / 2, "middle"), (buckets - 1, "end")];
+    ticks.dedup_by_key(|(index, _)| *index);
+    for (index, anchor) in ticks {
+        let x = match anchor {
+            "start" => left as f64,
+            "end" => (left + plot_width) as f64,
+            _ => left as f64 + (index as f64 + 0.5) * bar_width,
+        };
+        svg.push_str(&format!(
+            "<text x=\"{:.1}\" y=\"{}\" text-anchor=\"{}\">{}</text>",
+            x,
+            top + plot_height + 16,
+            anchor,
+            tz.datetime(first + index as u64 * size).format(label_format)
+        ));
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Start and length of the first bucket, and the entries per priority rank in
+/// each bucket covering the report's period.
+fn timeline_buckets(report: &Report, tz: &ReportTimezone) -> (u64, u64, Vec<[usize; COLORS.len()]>) {
+    let start = report.period.start;
+    let end = report.period.end.max(start + 1);
+    let size = bucket_size(end - start);
+    // Buckets start on round times in the report's timezone (e.g. local midnight)
+    let offset = i64::from(tz.datetime(start).offset().local_minus_utc());
+    let first = ((start as i64 + offset).div_euclid(size as i64) * size as i64 - offset).max(0) as u64;
+    let buckets = ((end - 1 - first) / size + 1) as usize;
+
+    let mut counts = vec![[0usize; COLORS.len()]; buckets];
+    for entry in &report.entries {
+        let bucket = (entry.timestamp.saturating_sub(first) / size) as usize;
+        if let Some(bucket) = counts.get_mut(bucket) {
+            bucket[priority_rank(&entry.priority)] += 1;
+        }
+    }
+    (first, size, counts)
+}
+
+fn bucket_size(span: u64) -> u64 {
+    BUCKET_SIZES
+        .iter()
+        .copied()
+        .find(|size| span.div_ceil(*size) <= MAX_BUCKETS)
+        .unwrap_or_else(|| span.div_ceil(MAX_BUCKETS))
+}
+
+/// Colour key for the priorities present in the report.
+fn legend(report: &Report) -> String {
+    let mut legend = String::new();
+    for (index, (priority, _)) in report.by_priority.iter().enumerate() {
+        let x = 40 + index * 100;
+        legend.push_str(&format!(
+            "<rect x=\"{}\" y=\"4\" width=\"10\" height=\"10\" fill=\"{}\"/>\
+             <text x=\"{}\" y=\"13\">{}</text>",
+            x,
+            COLORS[priority_rank(priority)],
+            x + 14,
+            priority_label(priority).0
+        ));
+    }
+    legend
+}
+
+/// Horizontal bars for the busiest sources.
+fn sources_svg(report: &Report) -> String {
+    let (label_width, row_height) = (180, 22);
+    let rows = report.by_source.len().min(SOURCE_BARS);
+    let more = report.by_source.len() - rows;
+    let height = (rows + usize::from(more > 0)) as u64 * row_height + 4;
+    let bar_area = (WIDTH - label_width - 60) as f64;
+    let max = report.by_source.first().map_or(1, |(_, count)| *count).max(1);
+
+    let mut svg = svg_open(WIDTH, height, "Entries by source");
+    for (index, (source, count)) in report.by_source.iter().take(rows).enumerate() {
+        let y = index as u64 * row_height;
+        let width = (*count as f64 * bar_area / max as f64).max(1.0);
+        let name: String = if source.chars().count() > 28 {
+            source.chars().take(27).chain(['…']).collect()
+        } else {
+            source.clone()
+        };
+        svg.push_str(&format!(
+            "<text x=\"{lx}\" y=\"{ty}\" text-anchor=\"end\"><title>{full}</title>{name}</text>\
+             <rect x=\"{x}\" y=\"{ry}\" width=\"{w:.1}\" height=\"{h}\" fill=\"#546e7a\"><title>{full}: {count}</title></rect>\
+             <text x=\"{cx:.1}\" y=\"{ty}\">{count}</text>",
+            lx = label_width - 6,
+            ty = y + 15,
+            full = escape_html(source),
+            name = escape_html(&name),
+            x = label_width,
+            ry = y + 3,
+            w = width,
+            h = row_height - 6,
+            count = count,
+            cx = label_width as f64 + width + 4.0
+        ));
+    }
+    if more > 0 {
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" fill=\"#666\">{} more sources not shown</text>",
+            label_width,
+            rows as u64 * row_height + 15,
+            more
+        ));
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Entries per hour of the day (columns) and day of the week (rows), darker
+/// cells having more entries.
+fn heatmap_svg(report: &Report, tz: &ReportTimezone) -> String {
+    let mut counts = [[0usize; 24]; 7];
+    for entry in &report.entries {
+        let time = tz.datetime(entry.timestamp);
+        counts[time.weekday().num_days_from_monday() as usize][time.hour() as usize] += 1;
+    }
+    let max = counts.iter().flatten().copied().max().unwrap_or(0).max(1);
+
+    let (left, top, cell_width, cell_height) = (40, 18, 28, 20);
+    let mut svg = svg_open(left + 24 * cell_width + 4, top + 7 * cell_height + 4, "Entries by weekday and hour");
+    for hour in (0..24).step_by(3) {
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"12\">{:02}h</text>",
+            left + hour * cell_width + 2,
+            hour
+        ));
+    }
+    for (day, hours) in counts.iter().enumerate() {
+        let y = top + day as u64 * cell_height;
+        svg.push_str(&format!("<text x=\"0\" y=\"{}\">{}</text>", y + 14, WEEKDAYS[day]));
+        for (hour, &count) in hours.iter().enumerate() {
+            let opacity = if count == 0 { 0.0 } else { 0.15 + 0.85 * count as f64 / max as f64 };
+            svg.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#f4f4f4\"/>\
+                 <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#b00020\" fill-opacity=\"{:.2}\">\
+                 <title>{} {:02}:00: {} entries</title></rect>",
+                left + hour as u64 * cell_width,
+                y,
+                cell_width - 2,
+                cell_height - 2,
+                left + hour as u64 * cell_width,
+                y,
+                cell_width - 2,
+                cell_height - 2,
+                opacity,
+                WEEKDAYS[day],
+                hour,
+                count
+            ));
+        }
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+fn svg_open(width: u64, height: u64, label: &str) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" \
+         role=\"img\" aria-label=\"{label}\">",
+        w = width,
+        h = height,
+        label = label
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::test_support::log_entry;
+    use crate::report_generator::ReportPeriod;
+
+    /// 2024-01-10 10:07:00 UTC
+    const START: u64 = 1_704_881_220;
+
+    fn report(start: u64, end: u64, entries: &[(u64, &str)]) -> Report {
+        let entries: Vec<_> = entries
+            .iter()
+            .map(|&(timestamp, priority)| {
+                let mut entry = log_entry("db down");
+                entry.timestamp = timestamp;
+                entry.priority = priority.to_string();
+                entry
+            })
+            .collect();
+        Report::new(&entries, ReportPeriod { start, end })
+    }
+
+    fn timezone(name: &str) -> ReportTimezone {
+        ReportTimezone::try_from(name.to_string()).unwrap()
+    }
+
+    #[test]
+    fn picks_the_smallest_bucket_within_the_limit() {
+        assert_eq!(bucket_size(1), 60);
+        assert_eq!(bucket_size(48 * 60), 60);
+        assert_eq!(bucket_size(48 * 60 + 1), 300);
+        assert_eq!(bucket_size(3600), 300);
+        assert_eq!(bucket_size(86400), 1800);
+        assert_eq!(bucket_size(7 * 86400), 21600);
+        // Beyond 48 months the buckets grow with the span
+        assert_eq!(bucket_size(100 * 2_592_000 + 1), 100 * 2_592_000 / 48 + 1);
+    }
+
+    #[test]
+    fn aligns_buckets_to_round_times_in_the_report_timezone() {
+        let end = START + 86400;
+        let (first, size, counts) = timeline_buckets(&report(START, end, &[]), &timezone("utc"));
+        assert_eq!((first, size, counts.len()), (START - 7 * 60, 1800, 49));
+
+        // Kolkata is UTC+05:30, so hours start at half past in UTC
+        let (first, size, _) = timeline_buckets(&report(START, START + 2 * 86400, &[]), &timezone("Asia/Kolkata"));
+        assert_eq!((first, size), (START - 37 * 60, 3600));
+
+        // Days start at local midnight, 05:00 UTC in New York in January
+        let month = report(START, START + 30 * 86400, &[]);
+        let (first, size, counts) = timeline_buckets(&month, &timezone("America/New_York"));
+        assert_eq!((first, size, counts.len()), (1_704_862_800, 86400, 31));
+    }
+
+    #[test]
+    fn counts_entries_per_bucket_and_priority() {
+        let entries = [
+            (START, "high"),
+            (START + 1799 - 7 * 60, "very high"),
+            (START + 1800, "high"),
+            (START + 86399, "unknown"),
+        ];
+        let (_, _, counts) = timeline_buckets(&report(START, START + 86400, &entries), &timezone("utc"));
+
+        assert_eq!(counts[0], [1, 1, 0, 0, 0]);
+        assert_eq!(counts[1], [0, 1, 0, 0, 0]);
+        assert_eq!(counts[48], [0, 0, 0, 0, 1]);
+        assert_eq!(counts.iter().flatten().sum::<usize>(), 4);
+    }
+
+    #[test]
+    fn handles_empty_periods() {
+        let empty = report(START, START + 3600, &[]);
+        assert_eq!(charts_html(&empty, &timezone("utc")), "<p>No entries in this period.</p>");
+
+        // A zero-length period still gets one bucket
+        let (first, size, counts) = timeline_buckets(&report(START, START, &[]), &timezone("utc"));
+        assert_eq!((first, size, counts.len()), (START, 60, 1));
+        assert!(counts.iter().flatten().all(|&count| count == 0));
+    }
+}
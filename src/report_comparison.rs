@@ -0,0 +1,259 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::report_generator::{ErrorGroup, Report, ReportPeriod};
+use crate::types::LogEntry;
+
+/// When a group's change in frequency is worth reporting.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ComparisonConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// A group rose when it occurred at least this many times as often as in
+    /// the previous period, and fell when it occurred this many times less.
+    #[serde(default = "default_min_ratio")]
+    pub min_ratio: f64,
+    /// Smallest difference in occurrences that counts, so 1 -> 3 is not a spike.
+    #[serde(default = "default_min_change")]
+    pub min_change: usize,
+}
+
+impl Default for ComparisonConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            min_ratio: default_min_ratio(),
+            min_change: default_min_change(),
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_min_ratio() -> f64 {
+    2.0
+}
+
+fn default_min_change() -> usize {
+    5
+}
+
+/// An error group with its occurrences in both periods.
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupChange {
+    pub fingerprint: String,
+    pub priority: String,
+    pub log_type: String,
+    pub source: Option<String>,
+    pub message: String,
+    pub previous: usize,
+    pub current: usize,
+}
+
+impl GroupChange {
+    fn new(group: &ErrorGroup, previous: usize, current: usize) -> Self {
+        Self {
+            fingerprint: group.fingerprint.clone(),
+            priority: group.priority.clone(),
+            log_type: group.log_type.clone(),
+            source: group.source.clone(),
+            message: group.message.clone(),
+            previous,
+            current,
+        }
+    }
+}
+
+/// How the error groups of a report differ from the previous period.
+#[derive(Debug, Clone, Serialize)]
+pub struct Comparison {
+    pub previous_period: ReportPeriod,
+    pub previous_total: usize,
+    /// Groups that did not occur in the previous period, most frequent first.
+    pub new: Vec<GroupChange>,
+    /// Largest increase first.
+    pub rising: Vec<GroupChange>,
+    /// Largest decrease first.
+    pub falling: Vec<GroupChange>,
+    /// Groups of the previous period that no longer occur, most frequent first.
+    pub disappeared: Vec<GroupChange>,
+}
+
+impl Comparison {
+    pub fn is_empty(&self) -> bool {
+        self.new.is_empty() && self.rising.is_empty() && self.falling.is_empty() && self.disappeared.is_empty()
+    }
+}
+
+/// Compares `report` with the period of the same length right before
+/// `period`, using the same `entries`. Open-ended periods (everything, or
+/// until now) have no previous period and are left without a comparison.
+pub fn compare_with_previous(report: &mut Report, period: ReportPeriod, entries: &[LogEntry], config: &ComparisonConfig) {
+    if !config.enabled || period.start == 0 || period.end == u64::MAX {
+        return;
+    }
+    let previous_period = ReportPeriod {
+        start: period.start.saturating_sub(period.end - period.start),
+        end: period.start,
+    };
+    let previous = Report::new(entries, previous_period);
+    report.comparison = Some(compare(report, &previous, config));
+}
+
+/// Matches the groups of both reports by fingerprint.
+pub fn compare(current: &Report, previous: &Report, config: &ComparisonConfig) -> Comparison {
+    let before: HashMap<&str, &ErrorGroup> =
+        previous.groups.iter().map(|group| (group.fingerprint.as_str(), group)).collect();
+    let ratio = config.min_ratio.max(1.0);
+
+    let mut comparison = Comparison {
+        previous_period: previous.period,
+        previous_total: previous.entries.len(),
+        new: Vec::new(),
+        rising: Vec::new(),
+        falling: Vec::new(),
+        disappeared: Vec::new(),
+    };
+    for group in &current.groups {
+        let Some(old) = before.get(group.fingerprint.as_str()) else {
+            comparison.new.push(GroupChange::new(group, 0, group.count));
+            continue;
+        };
+        let (was, now) = (old.count, group.count);
+        if now >= was + config.min_change && now as f64 >= was as f64 * ratio {
+            comparison.rising.push(GroupChange::new(group, was, now));
+        } else if was >= now + config.min_change && was as f64 >= now as f64 * ratio {
+            comparison.falling.push(GroupChange::new(group, was, now));
+        }
+    }
+    let seen: HashSet<&str> = current.groups.iter().map(|group| group.fingerprint.as_str()).collect();
+    for group in previous.groups.iter().filter(|group| !seen.contains(group.fingerprint.as_str())) {
+        comparison.disappeared.push(GroupChange::new(group, group.count, 0));
+    }
+
+    comparison.new.sort_by_key(|change| std::cmp::Reverse(change.current));
+    comparison.rising.sort_by_key(|change| std::cmp::Reverse(change.current - change.previous));
+    comparison.falling.sort_by_key(|change| std::cmp::Reverse(change.previous - change.current));
+    comparison.disappeared.sort_by_key(|change| std::cmp::Reverse(change.previous));
+    comparison
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::test_support::log_entry;
+
+    const DAY: u64 = 86400;
+    const START: u64 = 1_704_844_800;
+
+    /// `count` occurrences of group `name` from `start` on, one a minute.
+    fn occurrences(name: &str, count: usize, start: u64) -> Vec<LogEntry> {
+        (0..count)
+            .map(|index| {
+                let mut entry = log_entry(name);
+                entry.fingerprint = name.to_string();
+                entry.timestamp = start + index as u64 * 60;
+                entry
+            })
+            .collect()
+    }
+
+    /// Entries for groups given as `(name, previous day, current day)`.
+    fn entries(groups: &[(&str, usize, usize)]) -> Vec<LogEntry> {
+        groups
+            .iter()
+            .flat_map(|&(name, previous, current)| {
+                let mut entries = occurrences(name, previous, START - DAY);
+                entries.extend(occurrences(name, current, START));
+                entries
+            })
+            .collect()
+    }
+
+    fn changes(changes: &[GroupChange]) -> Vec<(&str, usize, usize)> {
+        changes.iter().map(|c| (c.fingerprint.as_str(), c.previous, c.current)).collect()
+    }
+
+    fn compared(groups: &[(&str, usize, usize)], config: &ComparisonConfig) -> Comparison {
+        let entries = entries(groups);
+        let period = ReportPeriod { start: START, end: START + DAY };
+        let mut report = Report::new(&entries, period);
+        compare_with_previous(&mut report, period, &entries, config);
+        report.comparison.unwrap()
+    }
+
+    #[test]
+    fn sorts_groups_into_changes() {
+        let comparison = compared(
+            &[
+                ("rising", 2, 10),
+                ("rising-more", 3, 30),
+                ("small-drop", 10, 6),
+                ("falling", 12, 2),
+                ("new", 0, 1),
+                ("new-often", 0, 4),
+                ("gone", 3, 0),
+                ("small-rise", 5, 9),
+                ("below-ratio", 10, 16),
+            ],
+            &ComparisonConfig::default(),
+        );
+
+        assert_eq!(comparison.previous_period, ReportPeriod { start: START - DAY, end: START });
+        assert_eq!(comparison.previous_total, 2 + 3 + 10 + 12 + 3 + 5 + 10);
+        assert_eq!(changes(&comparison.new), [("new-often", 0, 4), ("new", 0, 1)]);
+        assert_eq!(changes(&comparison.rising), [("rising-more", 3, 30), ("rising", 2, 10)]);
+        assert_eq!(changes(&comparison.falling), [("falling", 12, 2)]);
+        assert_eq!(changes(&comparison.disappeared), [("gone", 3, 0)]);
+    }
+
+    #[test]
+    fn applies_the_thresholds() {
+        let groups = [("a", 10, 16), ("b", 5, 9), ("c", 9, 5)];
+
+        let lenient = ComparisonConfig {
+            min_ratio: 1.5,
+            min_change: 4,
+            ..ComparisonConfig::default()
+        };
+        let comparison = compared(&groups, &lenient);
+        assert_eq!(changes(&comparison.rising), [("a", 10, 16), ("b", 5, 9)]);
+        assert_eq!(changes(&comparison.falling), [("c", 9, 5)]);
+
+        // Ratios below 1 count as 1, so only the change matters
+        let by_change = ComparisonConfig {
+            min_ratio: 0.5,
+            min_change: 6,
+            ..ComparisonConfig::default()
+        };
+        let comparison = compared(&groups, &by_change);
+        assert_eq!(changes(&comparison.rising), [("a", 10, 16)]);
+        assert!(comparison.falling.is_empty());
+    }
+
+    #[test]
+    fn skips_open_periods_and_disabled_comparisons() {
+        let entries = entries(&[("a", 1, 1)]);
+        let disabled = ComparisonConfig {
+            enabled: false,
+            ..ComparisonConfig::default()
+        };
+
+        for (period, config) in [
+            (ReportPeriod::all(), ComparisonConfig::default()),
+            (ReportPeriod::since(START), ComparisonConfig::default()),
+            (ReportPeriod { start: START, end: START + DAY }, disabled),
+        ] {
+            let mut report = Report::new(&entries, period);
+            compare_with_previous(&mut report, period, &entries, &config);
+            assert!(report.comparison.is_none());
+        }
+    }
+
+    #[test]
+    fn unchanged_periods_compare_as_empty() {
+        assert!(compared(&[("a", 4, 4), ("b", 20, 24)], &ComparisonConfig::default()).is_empty());
+    }
+}
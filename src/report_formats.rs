@@ -0,0 +1,449 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::notifier::template::priority_label;
+use crate::report_comparison::{Comparison, GroupChange};
+use crate::report_generator::{render_html, ErrorGroup, HtmlReportConfig, Report, ReportPeriod};
+use crate::utils::write_atomically;
+
+/// Longest message shown per group in the text digest, in characters.
+const DIGEST_MESSAGE_CHARS: usize = 120;
+
+/// Sources listed in the text digest.
+const DIGEST_SOURCES: usize = 5;
+
+/// Output formats for reports, all rendered from the same `Report`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    Html,
+    /// GitHub-flavoured Markdown tables, for tickets and pull requests.
+    Markdown,
+    /// One row per entry, for spreadsheets.
+    Csv,
+    /// Short plain-text digest, sent as a chat message rather than a file.
+    Text,
+    /// Machine-readable summary: counts and top groups, without the entries.
+    Json,
+}
+
+impl ReportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Html => "html",
+            ReportFormat::Markdown => "md",
+            ReportFormat::Csv => "csv",
+            ReportFormat::Text => "txt",
+            ReportFormat::Json => "json",
+        }
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "html" => Ok(ReportFormat::Html),
+            "markdown" | "md" => Ok(ReportFormat::Markdown),
+            "csv" => Ok(ReportFormat::Csv),
+            "text" | "txt" => Ok(ReportFormat::Text),
+            "json" => Ok(ReportFormat::Json),
+            _ => Err(format!("unknown report format '{}' (html, markdown, csv, text or json)", name)),
+        }
+    }
+}
+
+/// Renders `report` in `format`. The title, timezone and number of groups come
+/// from `config` for every format.
+pub fn render(report: &Report, format: ReportFormat, config: &HtmlReportConfig) -> Result<String, Box<dyn std::error::Error>> {
+    match format {
+        ReportFormat::Html => render_html(report, config),
+        ReportFormat::Markdown => Ok(render_markdown(report, config)),
+        ReportFormat::Csv => Ok(render_csv(report, config)),
+        ReportFormat::Text => Ok(render_text(report, config)),
+        ReportFormat::Json => render_json(report, config),
+    }
+}
+
+/// Renders `report` in `format` next to the configured HTML path, with the
+/// format's extension, and returns the file written.
+pub fn write_report(
+    report: &Report,
+    format: ReportFormat,
+    config: &HtmlReportConfig,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let content = render(report, format, config)?;
+    let path = config.path.with_extension(format.extension());
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    write_atomically(&path, content.as_bytes()).map_err(|e| format!("Error writing report {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+fn period_text(report: &Report, config: &HtmlReportConfig) -> String {
+    let tz = &config.timezone;
+    format!("{} to {}", tz.format(report.period.start), tz.format(report.period.end))
+}
+
+fn render_markdown(report: &Report, config: &HtmlReportConfig) -> String {
+    let tz = &config.timezone;
+    let mut md = format!(
+        "# {}\n\n{} entries on `{}`, {}. Generated {}; times in {}.\n\n## Summary\n",
+        markdown_cell(&config.title),
+        report.entries.len(),
+        report.host.replace('`', "'"),
+        period_text(report, config),
+        tz.format(report.generated_at),
+        tz.name()
+    );
+
+    let total = report.entries.len().max(1);
+    for (title, counts) in [
+        ("Priority", &report.by_priority),
+        ("Type", &report.by_type),
+        ("Source", &report.by_source),
+    ] {
+        md.push_str(&format!("\n| {} | Entries | % |\n| --- | ---: | ---: |\n", title));
+        for (key, count) in counts {
+            md.push_str(&format!(
+                "| {} | {} | {:.1} |\n",
+                markdown_cell(key),
+                count,
+                *count as f64 * 100.0 / total as f64
+            ));
+        }
+    }
+
+    if let Some(comparison) = &report.comparison {
+        md.push_str(&markdown_changes(comparison, config));
+    }
+
+    md.push_str("\n## Top error groups\n\n");
+    if report.groups.is_empty() {
+        md.push_str("No errors in this period.\n");
+    } else {
+        md.push_str(
+            "| Occurrences | Priority | Type | Source | First seen | Last seen | Latest message |\n\
+             | ---: | --- | --- | --- | --- | --- | --- |\n",
+        );
+        for group in report.groups.iter().take(config.top_groups) {
+            md.push_str(&format!(
+                "| {} | {} | {} | {} | {} | {} | {} |\n",
+                group.count,
+                priority_label(&group.priority).0,
+                markdown_cell(&group.log_type),
+                markdown_cell(group.source.as_deref().unwrap_or("unknown")),
+                tz.format(group.first_seen),
+                tz.format(group.last_seen),
+                markdown_cell(&group.message)
+            ));
+        }
+        if report.groups.len() > config.top_groups {
+            md.push_str(&format!("\n_{} more groups not shown._\n", report.groups.len() - config.top_groups));
+        }
+    }
+
+    md.push_str("\n## Entries\n\n| Time | Priority | Type | Source | Message | Alert |\n| --- | --- | --- | --- | --- | --- |\n");
+    for entry in &report.entries {
+        md.push_str(&format!(
+            "| {} | {} | {} | {} | {} | {} |\n",
+            tz.format(entry.timestamp),
+            priority_label(&entry.priority).0,
+            markdown_cell(&entry.log_type),
+            markdown_cell(entry.source.as_deref().unwrap_or("unknown")),
+            markdown_cell(&entry.message),
+            entry.delivery_status.label()
+        ));
+    }
+    md
+}
+
+fn markdown_changes(comparison: &Comparison, config: &HtmlReportConfig) -> String {
+    let tz = &config.timezone;
+    let mut md = format!(
+        "\n## Changes since the previous period\n\nCompared with {} to {} ({} entries).\n",
+        tz.format(comparison.previous_period.start),
+        tz.format(comparison.previous_period.end),
+        comparison.previous_total
+    );
+    if comparison.is_empty() {
+        md.push_str("\nNo new errors and no significant changes.\n");
+        return md;
+    }
+    for (title, changes) in change_lists(comparison) {
+        if changes.is_empty() {
+            continue;
+        }
+        md.push_str(&format!(
+            "\n### {} ({})\n\n| Previous | Current | Priority | Source | Message |\n| ---: | ---: | --- | --- | --- |\n",
+            title,
+            changes.len()
+        ));
+        for change in changes.iter().take(config.top_groups) {
+            md.push_str(&format!(
+                "| {} | {} | {} | {} | {} |\n",
+                change.previous,
+                change.current,
+                priority_label(&change.priority).0,
+                markdown_cell(change.source.as_deref().unwrap_or("unknown")),
+                markdown_cell(&change.message)
+            ));
+        }
+        if changes.len() > config.top_groups {
+            md.push_str(&format!("\n_{} more not shown._\n", changes.len() - config.top_groups));
+        }
+    }
+    md
+}
+
+fn change_lists(comparison: &Comparison) -> [(&'static str, &Vec<GroupChange>); 4] {
+    [
+        ("New errors", &comparison.new),
+        ("Rising", &comparison.rising),
+        ("Falling", &comparison.falling),
+        ("Disappeared", &comparison.disappeared),
+    ]
+}
+
+/// Escapes Markdown punctuation so log text shows literally inside a table cell.
+fn markdown_cell(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\r' => {}
+            '\n' => escaped.push(' '),
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|' | '~' | '!' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn render_csv(report: &Report, config: &HtmlReportConfig) -> String {
+    let mut csv = String::from("time,timestamp,priority,type,source,message,tags,fingerprint,alert\r\n");
+    for entry in &report.entries {
+        let fields = [
+            config.timezone.format(entry.timestamp),
+            entry.timestamp.to_string(),
+            entry.priority.clone(),
+            entry.log_type.clone(),
+            entry.source.clone().unwrap_or_default(),
+            entry.message.clone(),
+            entry.tags.join(" "),
+            entry.fingerprint.clone(),
+            entry.delivery_status.label().to_string(),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+/// Quotes a CSV field when needed. Text that a spreadsheet would run as a
+/// formula (`=`, `+`, `-`, `@`, or a leading tab or carriage return) is prefixed
+/// with `'`, since log lines are untrusted.
+fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+fn render_text(report: &Report, config: &HtmlReportConfig) -> String {
+    let mut text = format!(
+        "{}: {} entries on {}\n{} ({})\n",
+        config.title,
+        report.entries.len(),
+        report.host,
+        period_text(report, config),
+        config.timezone.name()
+    );
+    if report.entries.is_empty() {
+        text.push_str("No errors in this period.\n");
+        return text;
+    }
+
+    let priorities: Vec<String> = report
+        .by_priority
+        .iter()
+        .map(|(priority, count)| format!("{} {}", priority_label(priority).0, count))
+        .collect();
+    text.push_str(&format!("By priority: {}\n", priorities.join(", ")));
+    let sources: Vec<String> = report
+        .by_source
+        .iter()
+        .take(DIGEST_SOURCES)
+        .map(|(source, count)| format!("{} {}", source, count))
+        .collect();
+    text.push_str(&format!("Top sources: {}\n", sources.join(", ")));
+
+    if let Some(comparison) = &report.comparison {
+        text.push_str(&text_changes(comparison, config));
+    }
+
+    text.push_str("\nTop errors:\n");
+    for group in report.groups.iter().take(config.top_groups) {
+        text.push_str(&format!(
+            "{}x {} {}: {}\n",
+            group.count,
+            priority_label(&group.priority).0,
+            group.source.as_deref().unwrap_or("unknown"),
+            truncate(&group.message, DIGEST_MESSAGE_CHARS)
+        ));
+    }
+    if report.groups.len() > config.top_groups {
+        text.push_str(&format!("... and {} more groups\n", report.groups.len() - config.top_groups));
+    }
+    text
+}
+
+/// Counts of each kind of change, then the new and rising groups, which are
+/// the ones worth acting on.
+fn text_changes(comparison: &Comparison, config: &HtmlReportConfig) -> String {
+    let mut text = format!(
+        "Since the previous period ({} entries): {} new, {} rising, {} falling, {} gone\n",
+        comparison.previous_total,
+        comparison.new.len(),
+        comparison.rising.len(),
+        comparison.falling.len(),
+        comparison.disappeared.len()
+    );
+    let notable = comparison
+        .new
+        .iter()
+        .map(|change| ("NEW", change))
+        .chain(comparison.rising.iter().map(|change| ("UP", change)));
+    for (kind, change) in notable.take(config.top_groups) {
+        text.push_str(&format!(
+            "{} {}->{} {} {}: {}\n",
+            kind,
+            change.previous,
+            change.current,
+            priority_label(&change.priority).0,
+            change.source.as_deref().unwrap_or("unknown"),
+            truncate(&change.message, DIGEST_MESSAGE_CHARS)
+        ));
+    }
+    text
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= max_chars {
+        return text;
+    }
+    let mut truncated: String = text.chars().take(max_chars.saturating_sub(3)).collect();
+    truncated.push_str("...");
+    truncated
+}
+
+#[derive(Serialize)]
+struct JsonSummary<'a> {
+    title: &'a str,
+    host: &'a str,
+    generated_at: u64,
+    period: ReportPeriod,
+    total: usize,
+    by_priority: Vec<Count<'a>>,
+    by_type: Vec<Count<'a>>,
+    by_source: Vec<Count<'a>>,
+    /// All groups, so consumers can tell when `groups` was cut to `top_groups`.
+    group_count: usize,
+    groups: &'a [ErrorGroup],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comparison: Option<&'a Comparison>,
+}
+
+#[derive(Serialize)]
+struct Count<'a> {
+    name: &'a str,
+    count: usize,
+}
+
+fn counts(counts: &[(String, usize)]) -> Vec<Count<'_>> {
+    counts.iter().map(|(name, count)| Count { name, count: *count }).collect()
+}
+
+fn render_json(report: &Report, config: &HtmlReportConfig) -> Result<String, Box<dyn std::error::Error>> {
+    let summary = JsonSummary {
+        title: &config.title,
+        host: &report.host,
+        generated_at: report.generated_at,
+        period: report.period,
+        total: report.entries.len(),
+        by_priority: counts(&report.by_priority),
+        by_type: counts(&report.by_type),
+        by_source: counts(&report.by_source),
+        group_count: report.groups.len(),
+        groups: &report.groups[..report.groups.len().min(config.top_groups)],
+        comparison: report.comparison.as_ref(),
+    };
+    Ok(serde_json::to_string_pretty(&summary)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::test_support::log_entry;
+
+    fn report(message: &str) -> Report {
+        Report::new(&[log_entry(message)], ReportPeriod::all())
+    }
+
+    #[test]
+    fn guards_csv_fields_against_formulas() {
+        let cases = [
+            ("=1+1", "'=1+1"),
+            ("+SUM(A1)", "'+SUM(A1)"),
+            ("-2", "'-2"),
+            ("@cmd", "'@cmd"),
+            ("\t=1+1", "'\t=1+1"),
+            ("\r=1+1", "\"'\r=1+1\""),
+            ("a, b", "\"a, b\""),
+            ("say \"hi\"", "\"say \"\"hi\"\"\""),
+            ("line\nbreak", "\"line\nbreak\""),
+            ("plain 1+1", "plain 1+1"),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(csv_field(value), expected, "{:?}", value);
+        }
+
+        let csv = render(&report("=HYPERLINK(\"http://x\")"), ReportFormat::Csv, &HtmlReportConfig::default()).unwrap();
+        assert!(csv.lines().nth(1).unwrap().contains(",\"'=HYPERLINK(\"\"http://x\"\")\","));
+    }
+
+    #[test]
+    fn escapes_markdown_table_cells() {
+        assert_eq!(markdown_cell("a|b"), "a\\|b");
+        assert_eq!(markdown_cell("*bold* [link](x) `code`"), "\\*bold\\* \\[link\\](x) \\`code\\`");
+        assert_eq!(markdown_cell("two\r\nlines"), "two lines");
+        assert_eq!(markdown_cell("<img> #1 ~x~ !"), "\\<img\\> \\#1 \\~x\\~ \\!");
+
+        let md = render(&report("pipe | in <b>message</b>"), ReportFormat::Markdown, &HtmlReportConfig::default()).unwrap();
+        assert!(md.contains("| pipe \\| in \\<b\\>message\\</b\\> |"));
+        assert!(!md.contains("<b>"));
+    }
+
+    #[test]
+    fn json_summary_keeps_message_text_intact() {
+        let message = "quote \" backslash \\ newline\n</script>";
+        let json = render(&report(message), ReportFormat::Json, &HtmlReportConfig::default()).unwrap();
+
+        let summary: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(summary["groups"][0]["message"], message);
+        assert_eq!(summary["total"], 1);
+    }
+}
@@ -1,36 +1,558 @@
-use std::fs::File as StdFile;
-use std::io::{BufWriter, Write};
+use chrono::{DateTime, FixedOffset, Local, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
 
+use crate::notifier::template::{escape_html, fill_template, priority_label};
+use crate::parser::fingerprint;
+use crate::report_charts::charts_html;
+use crate::report_comparison::{Comparison, GroupChange};
 use crate::types::LogEntry;
+use crate::utils::{hostname, now_secs};
 
-pub fn generate_html_report(log_entries: &Vec<LogEntry>) -> String {
-    let mut html_content = String::new();
-    html_content.push_str("<html><head><title>Error Report</title></head><body>");
-    html_content.push_str("<h1>Error Report</h1>");
-    html_content.push_str("<table border='1'><tr><th>Timestamp</th><th>Log Type</th><th>Priority</th><th>Message</th></tr>");
-
-    for entry in log_entries {
-        html_content.push_str("<tr>");
-        html_content.push_str(&format!(
-            "<td>{}</td><td>{}</td><td>{}</td><td>{}</td>",
-            entry.timestamp, entry.log_type, entry.priority, entry.message
-        ));
-        html_content.push_str("</tr>");
+/// Page used when `template` is not set. Placeholders: `{{title}}`, `{{host}}`,
+/// `{{generated}}`, `{{period}}`, `{{timezone}}`, `{{total}}`, `{{summary}}`,
+/// `{{changes}}`, `{{charts}}`, `{{groups}}`, `{{entries}}`, `{{style}}` and `{{script}}`.
+const DEFAULT_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{{title}}</title>
+<style>{{style}}</style>
+</head>
+<body>
+<h1>{{title}}</h1>
+<p class="meta">{{total}} entries on {{host}}, {{period}}. Generated {{generated}}; times in {{timezone}}.</p>
+<h2>Summary</h2>
+{{summary}}
+{{changes}}
+<h2>Activity</h2>
+{{charts}}
+<h2>Top error groups</h2>
+{{groups}}
+<h2>Entries</h2>
+{{entries}}
+<script>{{script}}</script>
+</body>
+</html>
+"#;
+
+const STYLE: &str = "
+body { font-family: -apple-system, 'Segoe UI', Roboto, sans-serif; margin: 2em; color: #222; }
+h1 { margin-bottom: 0.2em; }
+.meta { color: #666; }
+.summary { display: flex; flex-wrap: wrap; gap: 2em; }
+table { border-collapse: collapse; margin: 0.5em 0 1.5em; }
+th, td { border: 1px solid #ddd; padding: 4px 8px; text-align: left; vertical-align: top; }
+th { background: #f4f4f4; }
+table.sortable th { cursor: pointer; user-select: none; }
+table.sortable th[data-order=asc]::after { content: ' \\25B2'; }
+table.sortable th[data-order=desc]::after { content: ' \\25BC'; }
+td.number { text-align: right; }
+td.message { font-family: monospace; white-space: pre-wrap; word-break: break-word; }
+tr.very-high td:first-child { border-left: 4px solid #b00020; }
+tr.high td:first-child { border-left: 4px solid #e65100; }
+tr.medium td:first-child { border-left: 4px solid #f9a825; }
+tr.low td:first-child { border-left: 4px solid #1565c0; }
+.filters { margin: 0.5em 0; }
+.charts figure { margin: 0 0 1.5em; }
+.charts figcaption { font-weight: bold; margin-bottom: 0.3em; }
+.charts svg { max-width: 100%; height: auto; font-size: 11px; }
+.filters input { width: 20em; }
+";
+
+/// Sorts tables on header click and filters rows by text and priority; the
+/// report stays readable without it (e.g. in mail clients that strip scripts).
+const SCRIPT: &str = "
+document.querySelectorAll('table.sortable').forEach(function (table) {
+  var headers = table.tHead.rows[0].cells;
+  Array.prototype.forEach.call(headers, function (th, column) {
+    th.addEventListener('click', function () {
+      var ascending = th.dataset.order !== 'asc';
+      Array.prototype.forEach.call(headers, function (other) { delete other.dataset.order; });
+      th.dataset.order = ascending ? 'asc' : 'desc';
+      var body = table.tBodies[0];
+      var rows = Array.prototype.slice.call(body.rows);
+      rows.sort(function (a, b) {
+        var x = a.cells[column].dataset.sort || a.cells[column].textContent;
+        var y = b.cells[column].dataset.sort || b.cells[column].textContent;
+        var order = (x !== '' && y !== '' && !isNaN(x) && !isNaN(y)) ? x - y : x.localeCompare(y);
+        return ascending ? order : -order;
+      });
+      rows.forEach(function (row) { body.appendChild(row); });
+    });
+  });
+});
+document.querySelectorAll('.filters').forEach(function (filters) {
+  var table = document.getElementById(filters.dataset.table);
+  var text = filters.querySelector('input');
+  var priority = filters.querySelector('select');
+  function apply() {
+    var needle = text.value.toLowerCase();
+    Array.prototype.forEach.call(table.tBodies[0].rows, function (row) {
+      var matches = row.textContent.toLowerCase().indexOf(needle) !== -1;
+      row.hidden = !(matches && (!priority.value || row.dataset.priority === priority.value));
+    });
+  }
+  text.addEventListener('input', apply);
+  priority.addEventListener('change', apply);
+});
+";
+
+/// Priorities from most to least severe, as stored in entries.
+pub(crate) const PRIORITIES: [&str; 4] = ["very high", "high", "medium", "low"];
+
+/// Where and how the HTML report is written.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HtmlReportConfig {
+    #[serde(default = "default_path")]
+    pub path: PathBuf,
+    #[serde(default = "default_title")]
+    pub title: String,
+    /// Timezone for every time shown in the report.
+    #[serde(default)]
+    pub timezone: ReportTimezone,
+    /// Error groups listed in the top-N table.
+    #[serde(default = "default_top_groups")]
+    pub top_groups: usize,
+    /// HTML file replacing the built-in page, with the same `{{placeholders}}`.
+    #[serde(default)]
+    pub template: Option<PathBuf>,
+}
+
+impl Default for HtmlReportConfig {
+    fn default() -> Self {
+        Self {
+            path: default_path(),
+            title: default_title(),
+            timezone: ReportTimezone::default(),
+            top_groups: default_top_groups(),
+            template: None,
+        }
+    }
+}
+
+fn default_path() -> PathBuf {
+    PathBuf::from("error_report.html")
+}
+
+fn default_title() -> String {
+    "Error Report".to_string()
+}
+
+fn default_top_groups() -> usize {
+    10
+}
+
+/// `local` (the default), `utc` or an IANA name such as `Europe/Lisbon`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum ReportTimezone {
+    #[default]
+    Local,
+    Utc,
+    Named(Tz),
+}
+
+impl TryFrom<String> for ReportTimezone {
+    type Error = String;
+
+    fn try_from(name: String) -> Result<Self, Self::Error> {
+        match name.to_lowercase().as_str() {
+            "local" => Ok(ReportTimezone::Local),
+            "utc" => Ok(ReportTimezone::Utc),
+            _ => name
+                .parse::<Tz>()
+                .map(ReportTimezone::Named)
+                .map_err(|_| format!("unknown timezone '{}'", name)),
+        }
+    }
+}
+
+impl ReportTimezone {
+    /// Epoch seconds as a date and time in this timezone.
+    pub fn datetime(&self, timestamp: u64) -> DateTime<FixedOffset> {
+        let utc = DateTime::from_timestamp(timestamp as i64, 0).unwrap_or_default();
+        match self {
+            ReportTimezone::Local => utc.with_timezone(&Local).fixed_offset(),
+            ReportTimezone::Utc => utc.fixed_offset(),
+            ReportTimezone::Named(tz) => utc.with_timezone(tz).fixed_offset(),
+        }
+    }
+
+    /// Formats epoch seconds as a date and time in this timezone.
+    pub fn format(&self, timestamp: u64) -> String {
+        self.datetime(timestamp).format("%Y-%m-%d %H:%M:%S").to_string()
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            ReportTimezone::Local => format!("local time (UTC{})", Local::now().format("%:z")),
+            ReportTimezone::Utc => "UTC".to_string(),
+            ReportTimezone::Named(tz) => {
+                let offset = tz.from_utc_datetime(&Utc::now().naive_utc()).format("%Z, UTC%:z");
+                format!("{} ({})", tz.name(), offset)
+            }
+        }
     }
+}
+
+/// Time range covered by a report, in Unix seconds. `start` is inclusive and
+/// `end` exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportPeriod {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl ReportPeriod {
+    /// Every stored entry, whatever its time.
+    pub fn all() -> Self {
+        Self { start: 0, end: u64::MAX }
+    }
+
+    /// From `start` until now, including entries written while the report is generated.
+    pub fn since(start: u64) -> Self {
+        Self { start, end: u64::MAX }
+    }
+
+    pub fn contains(&self, timestamp: u64) -> bool {
+        self.start <= timestamp && timestamp < self.end
+    }
+
+    /// Narrows an open start to the oldest entry and an open end to `now`.
+    pub fn bounded(self, oldest: Option<u64>, now: u64) -> Self {
+        let end = if self.end == u64::MAX { now } else { self.end };
+        let start = match (self.start, oldest) {
+            (0, Some(oldest)) => oldest,
+            (0, None) => end,
+            (start, _) => start,
+        };
+        Self { start, end }
+    }
+}
+
+/// Occurrences of the same error (same fingerprint) within the report.
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorGroup {
+    pub fingerprint: String,
+    pub count: usize,
+    pub first_seen: u64,
+    pub last_seen: u64,
+    /// Most severe priority among the occurrences.
+    pub priority: String,
+    pub log_type: String,
+    pub source: Option<String>,
+    /// Message of the latest occurrence.
+    pub message: String,
+}
+
+/// Everything shown in a report, computed once from the entries.
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub host: String,
+    pub generated_at: u64,
+    /// Covered period. An open start or end is narrowed to the oldest entry and
+    /// the generation time.
+    pub period: ReportPeriod,
+    /// Newest first.
+    pub entries: Vec<LogEntry>,
+    pub by_priority: Vec<(String, usize)>,
+    pub by_type: Vec<(String, usize)>,
+    pub by_source: Vec<(String, usize)>,
+    /// Most frequent first.
+    pub groups: Vec<ErrorGroup>,
+    /// Differences from the previous period (see `report_comparison`).
+    pub comparison: Option<Comparison>,
+}
 
-    html_content.push_str("</table></body></html>");
+impl Report {
+    /// Builds the report over the entries that fall within `period`.
+    pub fn new(entries: &[LogEntry], period: ReportPeriod) -> Self {
+        let mut entries: Vec<LogEntry> =
+            entries.iter().filter(|entry| period.contains(entry.timestamp)).cloned().collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.timestamp));
+        // Entries stored before fingerprints existed are grouped like new ones
+        for entry in entries.iter_mut().filter(|entry| entry.fingerprint.is_empty()) {
+            entry.fingerprint = fingerprint(&entry.log_type, entry.source.as_deref(), &entry.message);
+        }
 
-    let file_name = "error_report.html";
+        let mut by_priority = count_by(&entries, |entry| entry.priority.clone());
+        by_priority.sort_by_key(|(priority, _)| priority_rank(priority));
+        let by_type = count_by(&entries, |entry| entry.log_type.clone());
+        let by_source = count_by(&entries, |entry| source_name(entry).to_string());
 
-    match StdFile::create(file_name) {
-        Ok(file) => {
-            let mut writer = BufWriter::new(file);
-            if let Err(e) = writer.write_all(html_content.as_bytes()) {
-                eprintln!("Error writing to HTML file: {}", e);
+        let mut groups: HashMap<String, ErrorGroup> = HashMap::new();
+        // Oldest first, so the last message seen is the latest occurrence
+        for entry in entries.iter().rev() {
+            let group = groups.entry(entry.fingerprint.clone()).or_insert_with(|| ErrorGroup {
+                fingerprint: entry.fingerprint.clone(),
+                count: 0,
+                first_seen: entry.timestamp,
+                last_seen: entry.timestamp,
+                priority: entry.priority.clone(),
+                log_type: entry.log_type.clone(),
+                source: entry.source.clone(),
+                message: entry.message.clone(),
+            });
+            group.count += 1;
+            group.first_seen = group.first_seen.min(entry.timestamp);
+            group.last_seen = group.last_seen.max(entry.timestamp);
+            group.message = entry.message.clone();
+            if priority_rank(&entry.priority) < priority_rank(&group.priority) {
+                group.priority = entry.priority.clone();
             }
         }
-        Err(e) => eprintln!("Error creating HTML file: {}", e),
+        let mut groups: Vec<ErrorGroup> = groups.into_values().collect();
+        groups.sort_by(|a, b| b.count.cmp(&a.count).then(b.last_seen.cmp(&a.last_seen)));
+
+        let generated_at = now_secs();
+        Self {
+            host: hostname(),
+            generated_at,
+            period: period.bounded(entries.last().map(|entry| entry.timestamp), generated_at),
+            entries,
+            by_priority,
+            by_type,
+            by_source,
+            groups,
+            comparison: None,
+        }
+    }
+}
+
+/// Counts entries per key, most frequent first.
+fn count_by(entries: &[LogEntry], key: impl Fn(&LogEntry) -> String) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for entry in entries {
+        *counts.entry(key(entry)).or_default() += 1;
+    }
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+pub(crate) fn priority_rank(priority: &str) -> usize {
+    PRIORITIES.iter().position(|p| *p == priority).unwrap_or(PRIORITIES.len())
+}
+
+fn source_name(entry: &LogEntry) -> &str {
+    entry.source.as_deref().unwrap_or("unknown")
+}
+
+/// Renders the report as a standalone HTML page.
+pub fn render_html(report: &Report, config: &HtmlReportConfig) -> Result<String, Box<dyn std::error::Error>> {
+    let template = match &config.template {
+        Some(path) => std::fs::read_to_string(path)
+            .map_err(|e| format!("Cannot read report template {}: {}", path.display(), e))?,
+        None => DEFAULT_TEMPLATE.to_string(),
+    };
+    let tz = &config.timezone;
+    let period = format!("{} to {}", tz.format(report.period.start), tz.format(report.period.end));
+
+    // Plain values are escaped; the fragments below escape their own content
+    let fields: [(&str, String); 13] = [
+        ("title", escape_html(&config.title)),
+        ("host", escape_html(&report.host)),
+        ("generated", escape_html(&tz.format(report.generated_at))),
+        ("period", escape_html(&period)),
+        ("timezone", escape_html(&tz.name())),
+        ("total", report.entries.len().to_string()),
+        ("summary", summary_html(report)),
+        ("changes", changes_html(report, config)),
+        ("charts", charts_html(report, tz)),
+        ("groups", groups_html(report, config)),
+        ("entries", entries_html(report, tz)),
+        ("style", STYLE.to_string()),
+        ("script", SCRIPT.to_string()),
+    ];
+    Ok(fill_template(&template, &fields))
+}
+
+fn summary_html(report: &Report) -> String {
+    let total = report.entries.len().max(1);
+    let table = |title: &str, counts: &[(String, usize)]| {
+        let mut html = format!(
+            "<table class=\"sortable\"><thead><tr><th>{}</th><th>Entries</th><th>%</th></tr></thead><tbody>",
+            title
+        );
+        for (key, count) in counts {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td class=\"number\">{}</td><td class=\"number\">{:.1}</td></tr>",
+                escape_html(key),
+                count,
+                *count as f64 * 100.0 / total as f64
+            ));
+        }
+        html.push_str("</tbody></table>");
+        html
+    };
+    format!(
+        "<div class=\"summary\">{}{}{}</div>",
+        table("Priority", &report.by_priority),
+        table("Type", &report.by_type),
+        table("Source", &report.by_source)
+    )
+}
+
+/// The comparison with the previous period, with its own heading; empty when
+/// the report has none.
+fn changes_html(report: &Report, config: &HtmlReportConfig) -> String {
+    let Some(comparison) = &report.comparison else {
+        return String::new();
+    };
+    let tz = &config.timezone;
+    let mut html = format!(
+        "<h2>Changes since the previous period</h2><p class=\"meta\">Compared with {} to {} ({} entries).</p>",
+        tz.format(comparison.previous_period.start),
+        tz.format(comparison.previous_period.end),
+        comparison.previous_total
+    );
+    if comparison.is_empty() {
+        html.push_str("<p>No new errors and no significant changes.</p>");
+        return html;
+    }
+    for (title, changes) in [
+        ("New errors", &comparison.new),
+        ("Rising", &comparison.rising),
+        ("Falling", &comparison.falling),
+        ("Disappeared", &comparison.disappeared),
+    ] {
+        if changes.is_empty() {
+            continue;
+        }
+        html.push_str(&format!(
+            "<h3>{} ({})</h3><table class=\"sortable\"><thead><tr><th>Previous</th><th>Current</th><th>Priority</th>\
+             <th>Type</th><th>Source</th><th>Message</th></tr></thead><tbody>",
+            title,
+            changes.len()
+        ));
+        for change in changes.iter().take(config.top_groups) {
+            html.push_str(&change_row(change));
+        }
+        html.push_str("</tbody></table>");
+        if changes.len() > config.top_groups {
+            html.push_str(&format!("<p class=\"meta\">{} more not shown.</p>", changes.len() - config.top_groups));
+        }
+    }
+    html
+}
+
+fn change_row(change: &GroupChange) -> String {
+    format!(
+        "<tr class=\"{}\"><td class=\"number\">{}</td><td class=\"number\">{}</td><td data-sort=\"{}\">{}</td>\
+         <td>{}</td><td>{}</td><td class=\"message\">{}</td></tr>",
+        priority_class(&change.priority),
+        change.previous,
+        change.current,
+        priority_rank(&change.priority),
+        priority_label(&change.priority).0,
+        escape_html(&change.log_type),
+        escape_html(change.source.as_deref().unwrap_or("unknown")),
+        escape_html(&change.message)
+    )
+}
+
+fn groups_html(report: &Report, config: &HtmlReportConfig) -> String {
+    if report.groups.is_empty() {
+        return "<p>No errors in this period.</p>".to_string();
+    }
+    let tz = &config.timezone;
+    let mut html = String::from(
+        "<table class=\"sortable\"><thead><tr><th>Occurrences</th><th>Priority</th><th>Type</th><th>Source</th>\
+         <th>First seen</th><th>Last seen</th><th>Latest message</th><th>Fingerprint</th></tr></thead><tbody>",
+    );
+    for group in report.groups.iter().take(config.top_groups) {
+        html.push_str(&format!(
+            "<tr class=\"{}\"><td class=\"number\">{}</td><td data-sort=\"{}\">{}</td><td>{}</td><td>{}</td>\
+             <td data-sort=\"{}\">{}</td><td data-sort=\"{}\">{}</td><td class=\"message\">{}</td><td><code>{}</code></td></tr>",
+            priority_class(&group.priority),
+            group.count,
+            priority_rank(&group.priority),
+            priority_label(&group.priority).0,
+            escape_html(&group.log_type),
+            escape_html(group.source.as_deref().unwrap_or("unknown")),
+            group.first_seen,
+            tz.format(group.first_seen),
+            group.last_seen,
+            tz.format(group.last_seen),
+            escape_html(&group.message),
+            escape_html(&group.fingerprint)
+        ));
     }
+    html.push_str("</tbody></table>");
+    if report.groups.len() > config.top_groups {
+        html.push_str(&format!(
+            "<p class=\"meta\">{} more groups not shown.</p>",
+            report.groups.len() - config.top_groups
+        ));
+    }
+    html
+}
 
-    file_name.to_string()
+fn entries_html(report: &Report, tz: &ReportTimezone) -> String {
+    let mut html = String::from(
+        "<div class=\"filters\" data-table=\"entries\"><input type=\"search\" placeholder=\"Filter entries\"> \
+         <select><option value=\"\">All priorities</option>",
+    );
+    for priority in PRIORITIES {
+        html.push_str(&format!("<option value=\"{}\">{}</option>", priority, priority_label(priority).0));
+    }
+    html.push_str(
+        "</select></div><table id=\"entries\" class=\"sortable\"><thead><tr><th>Time</th><th>Priority</th>\
+         <th>Type</th><th>Source</th><th>Message</th><th>Alert</th></tr></thead><tbody>",
+    );
+    for entry in &report.entries {
+        html.push_str(&format!(
+            "<tr class=\"{}\" data-priority=\"{}\"><td data-sort=\"{}\">{}</td><td data-sort=\"{}\">{}</td><td>{}</td>\
+             <td>{}</td><td class=\"message\">{}</td><td>{}</td></tr>",
+            priority_class(&entry.priority),
+            escape_html(&entry.priority),
+            entry.timestamp,
+            tz.format(entry.timestamp),
+            priority_rank(&entry.priority),
+            priority_label(&entry.priority).0,
+            escape_html(&entry.log_type),
+            escape_html(source_name(entry)),
+            escape_html(&entry.message),
+            entry.delivery_status.label()
+        ));
+    }
+    html.push_str("</tbody></table>");
+    html
+}
+
+/// CSS class for a priority (`very high` becomes `very-high`).
+fn priority_class(priority: &str) -> String {
+    escape_html(&priority.replace(' ', "-"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::test_support::log_entry;
+
+    #[test]
+    fn escapes_entry_text_in_the_html_report() {
+        let mut entry = log_entry("<script>alert('x')</script> & more");
+        entry.source = Some("\"><img src=x onerror=alert(1)>".to_string());
+        entry.log_type = "<b>ERROR</b>".to_string();
+        let report = Report::new(&[entry], ReportPeriod::all());
+        let config = HtmlReportConfig {
+            title: "Errors <i>today</i>".to_string(),
+            ..HtmlReportConfig::default()
+        };
+
+        let html = render_html(&report, &config).unwrap();
+
+        // Only the report's own script tag remains
+        assert_eq!(html.matches("<script>").count(), 1);
+        assert!(!html.contains("<img"));
+        assert!(!html.contains("<b>") && !html.contains("<i>"));
+        assert!(html.contains("&lt;script&gt;alert('x')&lt;/script&gt; &amp; more"));
+        assert!(html.contains("&quot;&gt;&lt;img src=x onerror=alert(1)&gt;"));
+        assert!(html.contains("<title>Errors &lt;i&gt;today&lt;/i&gt;</title>"));
+    }
 }
@@ -0,0 +1,271 @@
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+
+use crate::utils::tmp_path;
+
+/// Key used to encrypt secret files at rest: 32 bytes as 64 hex characters
+/// (`openssl rand -hex 32`). Like any secret, it can come from
+/// `LOGSYNC_SECRET_KEY_FILE` or a systemd credential instead.
+pub const SECRET_KEY_VAR: &str = "LOGSYNC_SECRET_KEY";
+
+/// Set to `1` in the environment to start with a `.env` other users can read,
+/// with a warning instead of an error.
+pub const ALLOW_INSECURE_ENV_VAR: &str = "LOGSYNC_ALLOW_INSECURE_ENV";
+
+/// Header of files written encrypted by [`SecretStore`].
+const ENCRYPTED_MAGIC: &[u8] = b"LOGSYNC-ENC1\n";
+
+pub type SecretError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Reads the secret `name` from, in order: the environment variable itself,
+/// the file named by `<name>_FILE`, or the systemd credential `<name>` (or its
+/// lowercase form) in `$CREDENTIALS_DIRECTORY`.
+pub fn env_secret(name: &str) -> Result<Option<String>, SecretError> {
+    if let Ok(value) = std::env::var(name) {
+        return Ok(Some(value));
+    }
+    if let Some(path) = std::env::var_os(format!("{}_FILE", name)) {
+        return read_secret_text(Path::new(&path)).map(Some);
+    }
+    if let Some(dir) = std::env::var_os("CREDENTIALS_DIRECTORY") {
+        let dir = PathBuf::from(dir);
+        for candidate in [name.to_string(), name.to_lowercase()] {
+            let path = dir.join(candidate);
+            if path.exists() {
+                return read_secret_text(&path).map(Some);
+            }
+        }
+    }
+    Ok(None)
+}
+
+fn read_secret_text(path: &Path) -> Result<String, SecretError> {
+    let content = read_secret_file(path)?;
+    let text = String::from_utf8(content).map_err(|_| format!("{} is not valid UTF-8", path.display()))?;
+    Ok(text.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Refuses files that other users can read or write.
+pub fn check_permissions(path: &Path) -> Result<(), SecretError> {
+    let mode = std::fs::metadata(path)
+        .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?
+        .permissions()
+        .mode();
+    if mode & 0o007 != 0 {
+        return Err(format!(
+            "{} is accessible by other users (mode {:o}); run `chmod o-rwx {}`",
+            path.display(),
+            mode & 0o777,
+            path.display()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Reads a secret file after checking its permissions.
+pub fn read_secret_file(path: &Path) -> Result<Vec<u8>, SecretError> {
+    check_permissions(path)?;
+    std::fs::read(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e).into())
+}
+
+/// Like `utils::write_atomically`, but the file is only accessible by its owner.
+pub fn write_secret_file(path: &Path, content: &[u8]) -> std::io::Result<()> {
+    let tmp_path = tmp_path(path);
+    {
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&tmp_path)?;
+        // `mode` only applies to new files; fix up a leftover temporary file
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        file.write_all(content)?;
+        file.sync_all()?;
+    }
+    std::fs::rename(tmp_path, path)
+}
+
+/// Stores secret files (such as the OAuth token cache) encrypted when
+/// `LOGSYNC_SECRET_KEY` is set, and readable only by their owner either way.
+#[derive(Clone, Default)]
+pub struct SecretStore {
+    key: Option<Key>,
+}
+
+impl SecretStore {
+    pub fn from_env() -> Result<Self, SecretError> {
+        let key = match env_secret(SECRET_KEY_VAR)? {
+            Some(text) => Some(parse_key(&text)?),
+            None => None,
+        };
+        Ok(Self { key })
+    }
+
+    /// Whether files are encrypted when written.
+    pub fn encrypts(&self) -> bool {
+        self.key.is_some()
+    }
+
+    /// Reads a file written by [`SecretStore::write`]; plaintext files are
+    /// returned as they are. Returns `None` when the file does not exist.
+    pub fn read(&self, path: &Path) -> Result<Option<Vec<u8>>, SecretError> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = read_secret_file(path)?;
+        if !is_encrypted(&content) {
+            return Ok(Some(content));
+        }
+        let key = self
+            .key
+            .as_ref()
+            .ok_or_else(|| format!("{} is encrypted but {} is not set", path.display(), SECRET_KEY_VAR))?;
+        decrypt(key, &content)
+            .map(Some)
+            .map_err(|e| format!("Cannot decrypt {}: {}", path.display(), e).into())
+    }
+
+    pub fn write(&self, path: &Path, content: &[u8]) -> Result<(), SecretError> {
+        let content = match &self.key {
+            Some(key) => encrypt(key, content)?,
+            None => content.to_vec(),
+        };
+        write_secret_file(path, &content)?;
+        Ok(())
+    }
+}
+
+/// Whether `content` was written by [`encrypt`].
+pub fn is_encrypted(content: &[u8]) -> bool {
+    content.starts_with(ENCRYPTED_MAGIC)
+}
+
+fn parse_key(text: &str) -> Result<Key, SecretError> {
+    let bytes = hex::decode(text.trim()).map_err(|_| format!("{} must be hex-encoded", SECRET_KEY_VAR))?;
+    if bytes.len() != 32 {
+        return Err(format!("{} must be 32 bytes (64 hex characters)", SECRET_KEY_VAR).into());
+    }
+    Ok(*Key::from_slice(&bytes))
+}
+
+/// XChaCha20-Poly1305 with a random nonce: magic, nonce, then ciphertext.
+fn encrypt(key: &Key, plaintext: &[u8]) -> Result<Vec<u8>, SecretError> {
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = XChaCha20Poly1305::new(key)
+        .encrypt(&nonce, plaintext)
+        .map_err(|_| "encryption failed")?;
+    let mut content = ENCRYPTED_MAGIC.to_vec();
+    content.extend_from_slice(&nonce);
+    content.extend_from_slice(&ciphertext);
+    Ok(content)
+}
+
+fn decrypt(key: &Key, content: &[u8]) -> Result<Vec<u8>, SecretError> {
+    let body = &content[ENCRYPTED_MAGIC.len()..];
+    if body.len() < 24 {
+        return Err("file is truncated".into());
+    }
+    let (nonce, ciphertext) = body.split_at(24);
+    XChaCha20Poly1305::new(key)
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "wrong key or corrupted file".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("logsync-secrets-{}-{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn encrypts_and_decrypts_with_the_same_key_only() {
+        let key = parse_key(KEY).unwrap();
+        let other = parse_key(&KEY.replace("1f", "ff")).unwrap();
+
+        let first = encrypt(&key, b"token cache").unwrap();
+        let second = encrypt(&key, b"token cache").unwrap();
+
+        assert!(is_encrypted(&first));
+        assert_ne!(first, second, "nonces must be random");
+        assert_eq!(decrypt(&key, &first).unwrap(), b"token cache");
+        assert_eq!(decrypt(&other, &first).unwrap_err().to_string(), "wrong key or corrupted file");
+
+        let mut tampered = first.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(decrypt(&key, &tampered).is_err());
+        assert_eq!(
+            decrypt(&key, &first[..ENCRYPTED_MAGIC.len() + 10]).unwrap_err().to_string(),
+            "file is truncated"
+        );
+    }
+
+    #[test]
+    fn parses_hex_keys_of_32_bytes() {
+        assert!(parse_key(&format!("  {}\n", KEY)).is_ok());
+        assert_eq!(
+            parse_key("not hex").unwrap_err().to_string(),
+            "LOGSYNC_SECRET_KEY must be hex-encoded"
+        );
+        assert_eq!(
+            parse_key(&KEY[..62]).unwrap_err().to_string(),
+            "LOGSYNC_SECRET_KEY must be 32 bytes (64 hex characters)"
+        );
+    }
+
+    #[test]
+    fn store_round_trips_encrypted_and_plain_files() {
+        let path = temp_path("store");
+        let encrypted = SecretStore {
+            key: Some(parse_key(KEY).unwrap()),
+        };
+
+        encrypted.write(&path, b"{\"token\": 1}").unwrap();
+        let on_disk = std::fs::read(&path).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        let read_back = encrypted.read(&path).unwrap();
+        let without_key = SecretStore::default().read(&path);
+        SecretStore::default().write(&path, b"plain").unwrap();
+        let plain = encrypted.read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(is_encrypted(&on_disk));
+        assert_eq!(mode, 0o600);
+        assert_eq!(read_back.as_deref(), Some(&b"{\"token\": 1}"[..]));
+        assert!(without_key.unwrap_err().to_string().contains("is encrypted but LOGSYNC_SECRET_KEY is not set"));
+        assert_eq!(plain.as_deref(), Some(&b"plain"[..]));
+        assert_eq!(encrypted.read(&path).unwrap(), None);
+    }
+
+    #[test]
+    fn env_secret_reads_the_variable_then_its_file() {
+        let name = format!("LOGSYNC_TEST_SECRET_{}", std::process::id());
+        let path = temp_path("env-file");
+        std::fs::write(&path, "from file\n").unwrap();
+
+        assert_eq!(env_secret(&name).unwrap(), None);
+        std::env::set_var(format!("{}_FILE", name), &path);
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        let insecure = env_secret(&name);
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        let from_file = env_secret(&name).unwrap();
+        std::env::set_var(&name, "from variable");
+        let from_variable = env_secret(&name).unwrap();
+        std::env::remove_var(&name);
+        std::env::remove_var(format!("{}_FILE", name));
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(insecure.unwrap_err().to_string().contains("is accessible by other users (mode 644)"));
+        assert_eq!(from_file.as_deref(), Some("from file"));
+        assert_eq!(from_variable.as_deref(), Some("from variable"));
+    }
+}
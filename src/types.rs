@@ -1,12 +1,58 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LogEntry {
+    #[serde(default)]
+    pub id: String,
     pub timestamp: u64,
     pub log_type: String,
     pub priority: String,
     pub message: String,
-    pub telegram_notification: Option<bool>,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Groups recurring occurrences of the same error (see `parser::fingerprint`).
+    #[serde(default)]
+    pub fingerprint: String,
+    /// Whether an alert should be sent for the entry; whether it was sent is
+    /// tracked by `delivery_status`. Older files call it `telegram_notification`.
+    #[serde(default, alias = "telegram_notification", deserialize_with = "bool_or_null")]
+    pub alert_requested: bool,
+    #[serde(default)]
+    pub delivery_status: DeliveryStatus,
+    #[serde(default)]
+    pub acknowledged_by: Option<String>,
+}
+
+fn bool_or_null<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    Ok(Option::<bool>::deserialize(deserializer)?.unwrap_or(false))
+}
+
+/// Whether the alert for an entry reached its destinations.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "state", content = "reason", rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    /// No alert was requested or no route selected a destination.
+    #[default]
+    NotRequired,
+    Pending,
+    Sent,
+    Failed(String),
+    /// An alert was requested but suppressed by a mute or silence (the reason names it).
+    Silenced(String),
+}
+
+impl DeliveryStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DeliveryStatus::NotRequired => "-",
+            DeliveryStatus::Pending => "Pending",
+            DeliveryStatus::Sent => "Alert Sent",
+            DeliveryStatus::Failed(_) => "Failed",
+            DeliveryStatus::Silenced(_) => "Silenced",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,4 +69,28 @@ pub enum LogPriority {
     Medium,
     High,
     VeryHigh,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_old_notification_flag() {
+        let entry = |flag: &str| {
+            let json = format!(
+                r#"{{"timestamp": 1, "log_type": "ERROR", "priority": "high", "message": "m", {}}}"#,
+                flag
+            );
+            serde_json::from_str::<LogEntry>(&json).unwrap()
+        };
+
+        assert!(entry(r#""telegram_notification": true"#).alert_requested);
+        assert!(!entry(r#""telegram_notification": null"#).alert_requested);
+        assert!(entry(r#""alert_requested": true"#).alert_requested);
+        assert!(!entry(r#""source": null"#).alert_requested);
+
+        let written = serde_json::to_value(entry(r#""telegram_notification": true"#)).unwrap();
+        assert_eq!(written["alert_requested"], true);
+        assert!(written.get("telegram_notification").is_none());
+    }
+}
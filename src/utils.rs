@@ -1,14 +1,21 @@
 use std::fs::File as StdFile;
 use std::io::{self, Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use chrono::NaiveDateTime;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+
+/// Latest timestamp formatted as is (9999-12-30, a four-digit year in any
+/// timezone); later ones, such as a mute "forever", are shown as this date
+/// instead of wrapping around.
+const MAX_FORMATTED_TIMESTAMP: u64 = 253_402_214_399;
 
 pub fn format_timestamp(timestamp: u64) -> String {
-    // Converte o timestamp Unix para NaiveDateTime
-    let naive = NaiveDateTime::from_timestamp(timestamp as i64, 0);
+    // Converte o timestamp Unix para DateTime
+    let datetime = DateTime::from_timestamp(timestamp.min(MAX_FORMATTED_TIMESTAMP) as i64, 0).unwrap_or_default();
     // Formata a data para um formato legível
-    naive.format("%Y-%m-%d %H:%M:%S").to_string()
+    datetime.format("%Y-%m-%d %H:%M:%S").to_string()
 }
 
 
@@ -25,3 +32,103 @@ pub fn read_file_to_buffer(file_path: &str) -> io::Result<Vec<u8>> {
     file.read_to_end(&mut buffer)?;
     Ok(buffer)
 }
+
+/// Returns the current Unix time in seconds.
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Generates a unique identifier for a log entry (nanosecond clock plus a counter).
+pub fn generate_entry_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    format!("{:x}-{:x}", nanos, COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
+/// Writes `content` to a temporary file next to `path` and renames it into place.
+pub fn write_atomically(path: &Path, content: &[u8]) -> io::Result<()> {
+    let tmp_path = tmp_path(path);
+    {
+        let mut file = StdFile::create(&tmp_path)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+    }
+    std::fs::rename(tmp_path, path)
+}
+
+/// Temporary file used while replacing `path`: its full name plus `.tmp`, so
+/// files differing only in extension (`report.html`, `report.md`) never share one.
+pub fn tmp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+/// Returns the machine's host name, or `unknown` when it cannot be determined.
+pub fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/proc/sys/kernel/hostname").ok())
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Parses durations such as `90s`, `30m`, `1h` or `2d` (plain numbers are seconds).
+pub fn parse_duration_secs(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let (number, unit) = match text.find(|c: char| !c.is_ascii_digit()) {
+        Some(idx) => text.split_at(idx),
+        None => (text, "s"),
+    };
+    let value: u64 = number.parse().ok()?;
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return None,
+    };
+    value.checked_mul(multiplier)
+}
+
+/// Formats a Unix timestamp as RFC 3339 in local time.
+pub fn format_rfc3339(timestamp: u64) -> String {
+    DateTime::from_timestamp(timestamp.min(MAX_FORMATTED_TIMESTAMP) as i64, 0)
+        .unwrap_or_default()
+        .with_timezone(&Local)
+        .to_rfc3339()
+}
+
+/// Parses an RFC 3339 timestamp or a local `YYYY-MM-DD HH:MM[:SS]` time into Unix seconds.
+pub fn parse_time(text: &str) -> Option<u64> {
+    let text = text.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return u64::try_from(time.timestamp()).ok();
+    }
+    ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .and_then(|naive| Local.from_local_datetime(&naive).earliest())
+        .and_then(|time| u64::try_from(time.timestamp()).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temporary_files_keep_the_full_name() {
+        assert_eq!(tmp_path(Path::new("reports/daily.html")), Path::new("reports/daily.html.tmp"));
+        assert_eq!(tmp_path(Path::new("reports/daily.md")), Path::new("reports/daily.md.tmp"));
+        assert_eq!(tmp_path(Path::new(".env")), Path::new(".env.tmp"));
+        assert_eq!(tmp_path(Path::new("outbox")), Path::new("outbox.tmp"));
+    }
+}
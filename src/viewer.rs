@@ -1,150 +1,288 @@
 use crossterm::{
-    event::{self, Event, KeyCode},
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use std::{collections::VecDeque, io, time::Duration};
-use tokio::sync::mpsc::{self, error::TryRecvError};
+use tokio::sync::mpsc::{error::TryRecvError, Receiver};
+use tokio::sync::watch;
 use tui::{
     backend::{Backend, CrosstermBackend},
     layout::{Constraint, Direction, Layout},
     style::{Color, Modifier, Style},
     text::{Span, Spans},
-    widgets::{Block, Borders, Cell, Paragraph, Row, Table},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, Wrap},
     Frame, Terminal,
 };
 
-use tokio::sync::mpsc::Receiver;
+use crate::notifier::silences::{silence_like, SilenceList};
+use crate::{types::{DeliveryStatus, LogEntry}, utils};
 
-use crate::{types::LogEntry, utils};
+/// How long a silence added from the viewer lasts.
+const VIEWER_SILENCE_SECS: u64 = 3600;
 
-/// Starts an interactive viewer that displays logs in the terminal.
-pub async fn start_interactive_viewer(mut rx: Receiver<LogEntry>, max_logs: usize) -> Result<(), Box<dyn std::error::Error>> {
+/// Messages kept in the bottom panel.
+const MAX_MESSAGES: usize = 10;
+
+/// Table borders plus the header row.
+const TABLE_CHROME_ROWS: u16 = 3;
+
+/// Widths of the timestamp, type, priority and alert columns; the message
+/// column takes the rest.
+const FIXED_COLUMNS: [u16; 4] = [19, 8, 9, 10];
+
+/// Starts an interactive viewer that displays logs in the terminal, until the
+/// user quits with `q` or Ctrl+C.
+pub async fn start_interactive_viewer(
+    mut rx: Receiver<LogEntry>,
+    max_logs: usize,
+    silences: Option<SilenceList>,
+    backup_status: Option<watch::Receiver<String>>,
+) -> Result<(), Box<dyn std::error::Error>> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    println!("Viewer started, waiting for logs..."); // Debug log
-
-    loop {
-        // Clear the screen on each render to ensure proper layout
-        terminal.clear()?;
-
-        // Call run_app to display logs
-        if let Err(e) = run_app(&mut terminal, &mut rx, max_logs).await {
-            eprintln!("Error in viewer: {:?}", e);
-            break;
-        }
-    }
+    let mut state = ViewerState::new(max_logs);
+    let result = run_app(&mut terminal, &mut rx, &mut state, silences.as_ref(), backup_status.as_ref()).await;
 
     // Cleanup
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
     terminal.show_cursor()?;
 
-    Ok(())
+    result.map_err(Into::into)
+}
+
+/// Leaves raw mode and the alternate screen when the viewer is stopped from outside.
+pub fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), LeaveAlternateScreen, crossterm::cursor::Show);
+}
+
+/// Everything the viewer shows, kept for the whole session and independent of
+/// the terminal.
+pub struct ViewerState {
+    logs: VecDeque<LogEntry>,
+    max_logs: usize,
+    /// Index in `logs` of the highlighted entry, shown in the details pane.
+    selected: usize,
+    /// Index in `logs` of the first visible row.
+    offset: usize,
+    /// Table rows that fit on screen, measured on every draw.
+    viewport: usize,
+    messages: VecDeque<String>,
+}
+
+impl ViewerState {
+    pub fn new(max_logs: usize) -> Self {
+        let max_logs = max_logs.max(1);
+        Self {
+            logs: VecDeque::with_capacity(max_logs),
+            max_logs,
+            selected: 0,
+            offset: 0,
+            viewport: 1,
+            messages: VecDeque::new(),
+        }
+    }
+
+    /// Adds an entry, or replaces the one with the same id (delivery status
+    /// updates). The selection stays on the same entry as older ones are
+    /// dropped, and follows new entries while the newest one is selected.
+    pub fn push(&mut self, log: LogEntry) {
+        if let Some(existing) = self.logs.iter_mut().find(|l| !log.id.is_empty() && l.id == log.id) {
+            *existing = log;
+            return;
+        }
+
+        let following = self.selected + 1 >= self.logs.len();
+        if self.logs.len() == self.max_logs {
+            self.logs.pop_front();
+            self.selected = self.selected.saturating_sub(1);
+            self.offset = self.offset.saturating_sub(1);
+        }
+        self.logs.push_back(log);
+        if following {
+            self.selected = self.logs.len() - 1;
+        }
+        self.scroll_to_selection();
+    }
+
+    pub fn push_message(&mut self, message: String) {
+        if self.messages.len() == MAX_MESSAGES {
+            self.messages.pop_front();
+        }
+        self.messages.push_back(message);
+    }
+
+    pub fn logs(&self) -> &VecDeque<LogEntry> {
+        &self.logs
+    }
+
+    pub fn selected(&self) -> Option<&LogEntry> {
+        self.logs.get(self.selected)
+    }
+
+    /// Index of the first visible row.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn viewport(&self) -> usize {
+        self.viewport
+    }
+
+    pub fn select_next(&mut self) {
+        self.move_selection(1);
+    }
+
+    pub fn select_previous(&mut self) {
+        self.move_selection(-1);
+    }
+
+    pub fn page_down(&mut self) {
+        self.move_selection(self.viewport as isize);
+    }
+
+    pub fn page_up(&mut self) {
+        self.move_selection(-(self.viewport as isize));
+    }
+
+    pub fn select_first(&mut self) {
+        self.selected = 0;
+        self.scroll_to_selection();
+    }
+
+    /// Selects the newest entry, which also resumes following new entries.
+    pub fn select_last(&mut self) {
+        self.selected = self.logs.len().saturating_sub(1);
+        self.scroll_to_selection();
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let last = self.logs.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+        self.scroll_to_selection();
+    }
+
+    /// Records how many rows fit in the table and keeps the selection visible.
+    pub fn set_viewport(&mut self, rows: usize) {
+        self.viewport = rows.max(1);
+        self.scroll_to_selection();
+    }
+
+    fn scroll_to_selection(&mut self) {
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + self.viewport {
+            self.offset = self.selected + 1 - self.viewport;
+        }
+        // No blank rows below the last entry, e.g. after the terminal grows
+        self.offset = self.offset.min(self.logs.len().saturating_sub(self.viewport));
+    }
 }
 
 /// Runs the application to display logs and handle user input.
 async fn run_app<B: Backend>(
     terminal: &mut Terminal<B>,
-    rx: &mut mpsc::Receiver<LogEntry>,
-    max_logs: usize,
+    rx: &mut Receiver<LogEntry>,
+    state: &mut ViewerState,
+    silences: Option<&SilenceList>,
+    backup_status: Option<&watch::Receiver<String>>,
 ) -> io::Result<()> {
-    let mut logs = VecDeque::with_capacity(max_logs);
-    let mut debug_messages = Vec::new();
-    let mut scroll_offset = 0;
-    let mut selected_log = 0;
+    let mut disconnected = false;
 
     loop {
-        // Process received logs
-        match rx.try_recv() {
-            Ok(log) => {
-                println!("Received log: {:?}", log); // Debug log
-                if logs.len() == max_logs {
-                    logs.pop_front();
-                }
-                logs.push_back(log);
-                debug_messages.push(format!("New log received at {}", chrono::Local::now()));
-                if debug_messages.len() > 10 {
-                    debug_messages.remove(0);
+        // Recebe todos os logs pendentes antes de desenhar
+        loop {
+            match rx.try_recv() {
+                Ok(log) => state.push(log),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    if !disconnected {
+                        disconnected = true;
+                        state.push_message("Channel disconnected!".to_string());
+                    }
+                    break;
                 }
             }
-            Err(TryRecvError::Empty) => {
-                // Channel is empty, continue normally
-            }
-            Err(TryRecvError::Disconnected) => {
-                debug_messages.push("Channel disconnected!".to_string());
-            }
         }
 
-        // Atualiza a posição máxima permitida para `selected_log`
-        let max_index = logs.len().saturating_sub(1);
-        selected_log = selected_log.min(max_index);
-
-        // Renderiza a interface
-        terminal.draw(|f| {
-            ui(f, &logs.as_slices().0, Some(selected_log), &debug_messages, scroll_offset)
-        })?;
+        // Renderiza a interface; o tamanho do terminal é medido a cada desenho
+        let backup = backup_status.map(|status| status.borrow().clone());
+        terminal.draw(|f| ui(f, state, backup.as_deref()))?;
 
         // Captura eventos do teclado
         if event::poll(Duration::from_millis(100))? {
             if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char('q') => return Ok(()), // Sai com 'q'
-                    KeyCode::Char('c') if key.modifiers == event::KeyModifiers::CONTROL => return Ok(()), // Sai com Ctrl+C
-                    KeyCode::Down => {
-                        if selected_log < max_index {
-                            selected_log += 1; // Avança para o próximo log
-                        }
-                        // Ajusta a rolagem caso o cursor desça além da área visível
-                        if selected_log >= scroll_offset + 10 {
-                            scroll_offset += 1;
-                        }
-                    }
-                    KeyCode::Up => {
-                        if selected_log > 0 {
-                            selected_log -= 1; // Retorna ao log anterior
-                        }
-                        // Ajusta a rolagem caso o cursor suba além da área visível
-                        if selected_log < scroll_offset {
-                            scroll_offset = scroll_offset.saturating_sub(1);
-                        }
-                    }
-                    _ => {}
+                if handle_key(state, key, silences) {
+                    return Ok(());
                 }
             }
         }
     }
 }
 
+/// Applies a key press. Returns `true` when the user asked to quit.
+fn handle_key(state: &mut ViewerState, key: KeyEvent, silences: Option<&SilenceList>) -> bool {
+    match key.code {
+        KeyCode::Char('q') => return true,
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return true,
+        KeyCode::Down | KeyCode::Char('j') => state.select_next(),
+        KeyCode::Up | KeyCode::Char('k') => state.select_previous(),
+        KeyCode::PageDown => state.page_down(),
+        KeyCode::PageUp => state.page_up(),
+        KeyCode::Home | KeyCode::Char('g') => state.select_first(),
+        KeyCode::End | KeyCode::Char('G') => state.select_last(),
+        KeyCode::Char('s') | KeyCode::Char('u') => {
+            let message = match (silences, state.selected()) {
+                (Some(silences), Some(log)) => toggle_silence(silences, log, key.code == KeyCode::Char('s')),
+                (None, _) => "Silences are not available".to_string(),
+                (_, None) => "No log selected".to_string(),
+            };
+            state.push_message(message);
+        }
+        _ => {}
+    }
+    false
+}
 
+/// Adds a one-hour silence for entries like `log`, or removes the silences matching it.
+fn toggle_silence(silences: &SilenceList, log: &LogEntry, add: bool) -> String {
+    if add {
+        match silences.add(silence_like(log, VIEWER_SILENCE_SECS, "viewer")) {
+            Ok(silence) => format!("Silenced {} for 1h ({})", silence.matcher.describe(), silence.id),
+            Err(e) => format!("Could not add silence: {}", e),
+        }
+    } else {
+        match silences.remove_matching(log) {
+            Ok(0) => "No removable silence matches the selected log".to_string(),
+            Ok(removed) => format!("Removed {} silence(s)", removed),
+            Err(e) => format!("Could not remove silence: {}", e),
+        }
+    }
+}
 
 /// UI rendering function to display logs and selected log details.
-fn ui<B: Backend>(
-    f: &mut Frame<B>,
-    logs: &[LogEntry],
-    selected_log: Option<usize>,
-    debug_messages: &[String],
-    scroll_offset: usize,
-) {
-    // Divide the screen into three sections: logs & details, debug messages, and instructions
+fn ui<B: Backend>(f: &mut Frame<B>, state: &mut ViewerState, backup_status: Option<&str>) {
+    // Divide the screen into three sections: logs & details, messages, and instructions
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
         .constraints(
             [
-                Constraint::Percentage(70), // 70% for logs and details
-                Constraint::Min(5), // Minimum space for debug messages
+                Constraint::Min(6), // Remaining space for logs and details
+                Constraint::Length(7), // Backup status and the latest messages
                 Constraint::Length(3), // Fixed space for instructions
             ]
             .as_ref(),
         )
         .split(f.size());
 
-    // Split the main area (70%) into two parts: logs and log details
+    // Split the main area into two parts: logs and log details
     let main_chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints(
@@ -156,30 +294,52 @@ fn ui<B: Backend>(
         )
         .split(chunks[0]);
 
-    // Render the logs table in the first part
-    let rows = logs.iter().skip(scroll_offset).enumerate().map(|(i, log)| {
-        let cells = vec![
-            Cell::from(utils::format_timestamp(log.timestamp)),
-            Cell::from(log.log_type.clone()).style(get_color(&log.priority)),
-            Cell::from(log.priority.clone()).style(get_color(&log.priority)),
-            Cell::from(log.message.clone()),
-            Cell::from(match log.telegram_notification {
-                Some(true) => "Alert Sent",
-                _ => "Not Sent",
-            }),
-        ];
-        let row = Row::new(cells);
-        if Some(i) == selected_log {
-            row.style(
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
-            )
-        } else {
-            row
-        }
-    });
+    // The scroll window follows the real table height
+    state.set_viewport(main_chunks[0].height.saturating_sub(TABLE_CHROME_ROWS) as usize);
 
+    // Render the visible rows of the logs table in the first part
+    let rows = state
+        .logs
+        .iter()
+        .enumerate()
+        .skip(state.offset)
+        .take(state.viewport)
+        .map(|(index, log)| {
+            let cells = vec![
+                Cell::from(utils::format_timestamp(log.timestamp)),
+                Cell::from(log.log_type.clone()).style(get_color(&log.priority)),
+                Cell::from(log.priority.clone()).style(get_color(&log.priority)),
+                Cell::from(log.message.clone()),
+                Cell::from(log.delivery_status.label()),
+            ];
+            let row = Row::new(cells);
+            if index == state.selected {
+                row.style(
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                row
+            }
+        });
+
+    // Borders and the spacing between the five columns
+    let message_width = main_chunks[0]
+        .width
+        .saturating_sub(2 + 4 + FIXED_COLUMNS.iter().sum::<u16>());
+    let [time_width, type_width, priority_width, alert_width] = FIXED_COLUMNS;
+    let widths = [
+        Constraint::Length(time_width),
+        Constraint::Length(type_width),
+        Constraint::Length(priority_width),
+        Constraint::Length(message_width),
+        Constraint::Length(alert_width),
+    ];
+    let title = match state.logs.len() {
+        0 => "Logs".to_string(),
+        total => format!("Logs ({}/{})", state.selected + 1, total),
+    };
     let table = Table::new(rows)
         .header(
             Row::new(vec![
@@ -187,7 +347,7 @@ fn ui<B: Backend>(
                 Cell::from("Type"),
                 Cell::from("Priority"),
                 Cell::from("Message"),
-                Cell::from("Telegram Notification"),
+                Cell::from("Alert"),
             ])
             .style(
                 Style::default()
@@ -195,80 +355,295 @@ fn ui<B: Backend>(
                     .add_modifier(Modifier::BOLD),
             ),
         )
-        .block(Block::default().borders(Borders::ALL).title("Logs"))
-        .widths(&[
-            Constraint::Length(12),
-            Constraint::Length(10),
-            Constraint::Length(10),
-            Constraint::Percentage(50),
-            Constraint::Length(20),
-        ]);
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .widths(&widths);
 
     f.render_widget(table, main_chunks[0]);
 
     // Display the selected log details next to the logs table
-    if let Some(idx) = selected_log {
-        if idx < logs.len() {
-            let log = &logs[idx];
-            let mut details = vec![
-                Spans::from(Span::raw(format!("Timestamp: {}", log.timestamp))),
-                Spans::from(Span::raw(format!("Type: {}", log.log_type))),
-                Spans::from(Span::raw(format!("Priority: {}", log.priority))),
-                Spans::from(Span::raw(format!("Message: {}", log.message))),
-            ];
+    let details = match state.selected() {
+        Some(log) => log_details(log),
+        None => vec![Spans::from(Span::raw("Waiting for logs..."))],
+    };
+    let details_paragraph = Paragraph::new(details)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title("Log Details"));
+    f.render_widget(details_paragraph, main_chunks[1]);
 
-            details.push(Spans::from(Span::styled(
-                format!(
-                    "telegram_notification: {}",
-                    match log.telegram_notification {
-                        Some(true) => "Alert sent to Telegram",
-                        _ => "Not sent",
-                    }
-                ),
-                Style::default()
-                    .fg(Color::LightBlue)
-                    .add_modifier(Modifier::BOLD),
-            )));
-
-            let details_paragraph = Paragraph::new(details).block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .title("Log Details"),
-            );
-            f.render_widget(details_paragraph, main_chunks[1]);
-        }
+    // Render the backup status and debug messages at the bottom of the screen
+    let mut status_lines = Vec::new();
+    if let Some(status) = backup_status.filter(|status| !status.is_empty()) {
+        status_lines.push(Spans::from(Span::styled(
+            format!("Backup: {}", status),
+            Style::default().fg(Color::LightBlue),
+        )));
     }
-
-    // Render debug messages at the bottom of the screen
-    let debug_paragraph = Paragraph::new(
-        debug_messages
-            .iter()
-            .map(|msg| Spans::from(Span::raw(msg.clone())))
-            .collect::<Vec<_>>(),
-    )
-    .block(
-        Block::default()
-            .borders(Borders::ALL)
-            .title("Debug Messages"),
-    );
+    status_lines.extend(state.messages.iter().map(|msg| Spans::from(Span::raw(msg.clone()))));
+    let debug_paragraph = Paragraph::new(status_lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Debug Messages"),
+        );
     f.render_widget(debug_paragraph, chunks[1]);
 
     // Render navigation and quit instructions at the bottom of the screen
-    let instructions = vec![
-        Spans::from(Span::raw("Use Up/Down arrows to scroll logs")),
-        Spans::from(Span::raw("Press Ctrl+C to quit")),
-    ];
+    let instructions = vec![Spans::from(Span::raw(
+        "Up/Down, PgUp/PgDn, Home/End: move; s/u: silence/unsilence similar logs for 1h; q: quit",
+    ))];
     let instructions_paragraph = Paragraph::new(instructions)
         .block(Block::default().borders(Borders::ALL).title("Instructions"));
     f.render_widget(instructions_paragraph, chunks[2]);
 }
 
+fn log_details(log: &LogEntry) -> Vec<Spans<'static>> {
+    let mut details = vec![
+        Spans::from(Span::raw(format!("Timestamp: {}", utils::format_timestamp(log.timestamp)))),
+        Spans::from(Span::raw(format!("Type: {}", log.log_type))),
+        Spans::from(Span::raw(format!("Priority: {}", log.priority))),
+        Spans::from(Span::raw(format!("Source: {}", log.source.as_deref().unwrap_or("unknown")))),
+        Spans::from(Span::raw(format!("Message: {}", log.message))),
+        Spans::from(Span::raw(format!("Fingerprint: {}", log.fingerprint))),
+    ];
+
+    if let Some(user) = &log.acknowledged_by {
+        details.push(Spans::from(Span::raw(format!("Acknowledged by: {}", user))));
+    }
+
+    let delivery = match &log.delivery_status {
+        DeliveryStatus::Failed(reason) => format!("Failed ({})", reason),
+        DeliveryStatus::Silenced(reason) => format!("Silenced ({})", reason),
+        status => status.label().to_string(),
+    };
+    details.push(Spans::from(Span::styled(
+        format!("Alert: {}", delivery),
+        Style::default()
+            .fg(Color::LightBlue)
+            .add_modifier(Modifier::BOLD),
+    )));
+    details
+}
+
 /// Returns a color style based on the log priority.
 fn get_color(priority: &str) -> Style {
     match priority {
+        "very high" => Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
         "high" => Style::default().fg(Color::Red),
         "medium" => Style::default().fg(Color::Yellow),
         "low" => Style::default().fg(Color::Green),
         _ => Style::default(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifier::test_support::log_entry;
+    use tui::backend::TestBackend;
+    use tui::buffer::Buffer;
+
+    /// 2024-03-01 12:00:00 UTC
+    const BASE_TIME: u64 = 1_709_294_400;
+
+    fn entry(n: u64) -> LogEntry {
+        let mut entry = log_entry(&format!("message {}", n));
+        entry.id = format!("id-{}", n);
+        entry.timestamp = BASE_TIME + n * 60;
+        entry.log_type = "error".to_string();
+        entry.source = Some("nginx".to_string());
+        entry.tags = Vec::new();
+        entry.fingerprint = format!("fp{}", n);
+        entry.alert_requested = false;
+        entry.delivery_status = DeliveryStatus::Sent;
+        entry
+    }
+
+    fn state_with(max_logs: usize, entries: impl IntoIterator<Item = u64>) -> ViewerState {
+        let mut state = ViewerState::new(max_logs);
+        for n in entries {
+            state.push(entry(n));
+        }
+        state
+    }
+
+    fn draw(state: &mut ViewerState, width: u16, height: u16) -> Buffer {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|f| ui(f, state, None)).unwrap();
+        terminal.backend().buffer().clone()
+    }
+
+    fn lines(buffer: &Buffer) -> Vec<String> {
+        let width = buffer.area.width as usize;
+        buffer
+            .content
+            .chunks(width)
+            .map(|row| row.iter().map(|cell| cell.symbol.as_str()).collect::<String>().trim_end().to_string())
+            .collect()
+    }
+
+    /// Row of `buffer` holding the highlighted table row, if any.
+    fn highlighted_line(buffer: &Buffer) -> Option<String> {
+        let width = buffer.area.width as usize;
+        buffer
+            .content
+            .chunks(width)
+            .find(|row| row.iter().any(|cell| cell.fg == Color::Yellow && cell.symbol != " "))
+            .map(|row| row.iter().map(|cell| cell.symbol.as_str()).collect())
+    }
+
+    #[test]
+    fn renders_full_screen() {
+        let mut state = state_with(5, 1..=8);
+        state.select_previous();
+        let buffer = draw(&mut state, 100, 24);
+
+        let expected = vec![
+            "",
+            " ┌Logs (4/5)─────────────────────────────────────────────────────────────┐┌Log Details────────────┐",
+            " │Timestamp           Type     Priority  Message               Alert     ││Timestamp: 2024-03-01  │",
+            " │2024-03-01 12:04:00 error    high      message 4             Alert Sent││12:07:00               │",
+            " │2024-03-01 12:05:00 error    high      message 5             Alert Sent││Type: error            │",
+            " │2024-03-01 12:06:00 error    high      message 6             Alert Sent││Priority: high         │",
+            " │2024-03-01 12:07:00 error    high      message 7             Alert Sent││Source: nginx          │",
+            " │2024-03-01 12:08:00 error    high      message 8             Alert Sent││Message: message 7     │",
+            " │                                                                       ││Fingerprint: fp7       │",
+            " │                                                                       ││Alert: Alert Sent      │",
+            " │                                                                       ││                       │",
+            " │                                                                       ││                       │",
+            " └───────────────────────────────────────────────────────────────────────┘└───────────────────────┘",
+            " ┌Debug Messages──────────────────────────────────────────────────────────────────────────────────┐",
+            " │                                                                                                │",
+            " │                                                                                                │",
+            " │                                                                                                │",
+            " │                                                                                                │",
+            " │                                                                                                │",
+            " └────────────────────────────────────────────────────────────────────────────────────────────────┘",
+            " ┌Instructions────────────────────────────────────────────────────────────────────────────────────┐",
+            " │Up/Down, PgUp/PgDn, Home/End: move; s/u: silence/unsilence similar logs for 1h; q: quit         │",
+            " └────────────────────────────────────────────────────────────────────────────────────────────────┘",
+            "",
+        ];
+        assert_eq!(lines(&buffer), expected);
+        assert!(highlighted_line(&buffer).unwrap().contains("message 7"));
+    }
+
+    #[test]
+    fn renders_every_entry_after_the_buffer_wraps() {
+        let mut state = state_with(3, 1..=5);
+        let screen = lines(&draw(&mut state, 100, 24)).join("\n");
+
+        for n in 3..=5 {
+            assert!(screen.contains(&format!("12:0{}:00 error", n)), "entry {} missing", n);
+        }
+        assert!(!screen.contains("12:01:00 error"));
+        assert!(!screen.contains("12:02:00 error"));
+        assert!(screen.contains("Logs (3/3)"));
+    }
+
+    #[test]
+    fn highlighted_row_matches_details_after_scrolling() {
+        let mut state = state_with(50, 1..=40);
+        state.select_first();
+        draw(&mut state, 100, 24);
+        state.page_down();
+        state.page_down();
+        state.select_next();
+        let buffer = draw(&mut state, 100, 24);
+
+        assert!(state.offset() > 0);
+        let selected = state.selected().unwrap().message.clone();
+        assert!(highlighted_line(&buffer).unwrap().contains(&format!("{} ", selected)));
+        assert!(lines(&buffer).iter().any(|line| line.contains(&format!("Message: {}", selected))));
+    }
+
+    #[test]
+    fn viewport_follows_terminal_height() {
+        let mut state = state_with(100, 1..=60);
+        state.select_first();
+
+        draw(&mut state, 100, 24);
+        let small = state.viewport();
+        state.page_down();
+        assert_eq!(state.selected().unwrap().message, format!("message {}", 1 + small));
+
+        draw(&mut state, 100, 40);
+        let large = state.viewport();
+        assert_eq!(large, small + 16);
+        state.select_first();
+        state.page_down();
+        assert_eq!(state.selected().unwrap().message, format!("message {}", 1 + large));
+
+        // Every visible row is drawn and the selection stays on screen
+        let screen = lines(&draw(&mut state, 100, 40)).join("\n");
+        assert!(screen.contains(&format!("message {} ", 1 + large)));
+        assert_eq!(screen.matches(" error    high ").count(), large);
+    }
+
+    #[test]
+    fn selection_stays_on_entry_when_old_ones_are_dropped() {
+        let mut state = state_with(5, 1..=5);
+        state.select_first();
+        state.select_next();
+        state.select_next();
+        assert_eq!(state.selected().unwrap().message, "message 3");
+
+        state.push(entry(6));
+        state.push(entry(7));
+        assert_eq!(state.selected().unwrap().message, "message 3");
+        assert_eq!(state.logs().front().unwrap().message, "message 3");
+    }
+
+    #[test]
+    fn follows_new_entries_only_while_newest_is_selected() {
+        let mut state = state_with(10, 1..=3);
+        state.push(entry(4));
+        assert_eq!(state.selected().unwrap().message, "message 4");
+
+        state.select_previous();
+        state.push(entry(5));
+        assert_eq!(state.selected().unwrap().message, "message 3");
+
+        state.select_last();
+        state.push(entry(6));
+        assert_eq!(state.selected().unwrap().message, "message 6");
+    }
+
+    #[test]
+    fn replaces_entry_with_the_same_id() {
+        let mut state = state_with(10, 1..=3);
+        let mut update = entry(2);
+        update.delivery_status = DeliveryStatus::Failed("timeout".to_string());
+        state.push(update);
+
+        assert_eq!(state.logs().len(), 3);
+        assert_eq!(state.selected().unwrap().message, "message 3");
+        assert!(matches!(state.logs()[1].delivery_status, DeliveryStatus::Failed(_)));
+    }
+
+    #[test]
+    fn quitting_keeps_the_state() {
+        let mut state = state_with(10, 1..=3);
+        state.select_first();
+        let quit = handle_key(&mut state, KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE), None);
+
+        assert!(quit);
+        assert_eq!(state.logs().len(), 3);
+        assert_eq!(state.selected().unwrap().message, "message 1");
+    }
+
+    #[test]
+    fn colours_critical_entries() {
+        let mut state = ViewerState::new(10);
+        let mut critical = entry(1);
+        critical.log_type = "CRITICAL".to_string();
+        critical.priority = "very high".to_string();
+        state.push(critical);
+        state.push(entry(2));
+        let buffer = draw(&mut state, 100, 24);
+
+        // Type column of the first row
+        let cell = buffer.get(22, 3);
+        assert_eq!(cell.symbol, "C");
+        assert_eq!(cell.fg, Color::Magenta);
+    }
+}